@@ -1,3 +1,6 @@
-fn main() {
-    println!("Hello, world!");
+fn main() -> Result<(), String> {
+    telemetry::init(&telemetry::TelemetryOptions::from_env("info")?)?;
+
+    tracing::info!("Hello, world!");
+    Ok(())
 }
@@ -7,6 +7,9 @@
 // Banco Authentication (mongodb) ->
 //      token -> { account_id, api_key, permissions, created_at, updated_at, expires_at }
 
-fn main() {
-    println!("Hello, world!");
+fn main() -> Result<(), String> {
+    telemetry::init(&telemetry::TelemetryOptions::from_env("info")?)?;
+
+    tracing::info!("Hello, world!");
+    Ok(())
 }
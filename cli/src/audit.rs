@@ -0,0 +1,77 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use core_database::{
+    audit::{AuditEventDAO, AuditEventsWhere, AuditLog, AuditRepository},
+    entities::SortOrder,
+    pagination::Cursor,
+    sqlite::DatabaseRepository,
+};
+use uuid::Uuid;
+
+pub struct AuditFilters {
+    pub entity: Option<String>,
+    pub id: Option<Uuid>,
+    pub organization: Option<Uuid>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: i32,
+    pub after: Option<String>,
+}
+
+fn describe(event: &AuditEventDAO) -> String {
+    let actor = event
+        .actor_id
+        .map_or_else(|| "system".to_string(), |actor| actor.to_string());
+    format!(
+        "{} {} {} {} by {}",
+        event.created_at.to_rfc3339(),
+        event.operation,
+        event.entity_type,
+        event.entity_id,
+        actor
+    )
+}
+
+pub async fn audit(db: &DatabaseRepository, filters: AuditFilters) -> Result<(), String> {
+    let after = filters
+        .after
+        .as_deref()
+        .map(Cursor::from_str)
+        .transpose()
+        .map_err(|e| format!("invalid cursor: {:#?}", e))?;
+    let page = AuditRepository::events(
+        &db.connection,
+        AuditEventsWhere {
+            entity_type: filters.entity,
+            entity_id: filters.id,
+            organization_id: filters.organization,
+            actor_id: None,
+            created_after: filters.since,
+            created_before: filters.until,
+            order: SortOrder::Descending,
+            limit: filters.limit,
+            after,
+        },
+    )
+    .await
+    .map_err(|e| format!("database error: {:#?}", e))?;
+
+    if page.items.is_empty() {
+        println!("No audit events");
+    }
+    for event in page.items.iter() {
+        println!("{}", describe(event));
+        if let Some(before) = &event.before {
+            println!("  before: {before}");
+        }
+        if let Some(after) = &event.after {
+            println!("  after:  {after}");
+        }
+    }
+    if let Some(cursor) = page.next_cursor {
+        println!("More events with --after {cursor}");
+    }
+
+    Ok(())
+}
@@ -1,5 +1,27 @@
+use chrono::{DateTime, Utc};
+use core_database::money::{Currency, Money};
+use telemetry::LogFormat;
+use uuid::Uuid;
+
 #[derive(Debug, clap::Parser)]
 pub struct Cli {
+    /// SQLite connection URL or path of the database file
+    #[arg(
+        long,
+        global = true,
+        env = "DATABASE_URL",
+        default_value = "sqlite:core.db"
+    )]
+    pub database: String,
+
+    /// Log level or filter directives, e.g. `debug` or `warn,core_database=debug`
+    #[arg(long, global = true, env = "LOG_LEVEL", default_value = "warn")]
+    pub log_level: String,
+
+    /// Log output format: pretty or json
+    #[arg(long, global = true, env = "LOG_FORMAT", default_value = "pretty")]
+    pub log_format: LogFormat,
+
     /// Subcommand for managing a organization
     #[command(subcommand)]
     pub subcommand: Option<Command>,
@@ -12,5 +34,116 @@ pub enum Command {
     CreateOrganization {
         /// Name of the organization. Must be unique
         name: String,
+        /// Currency sales reports are converted into, e.g. EUR
+        #[arg(long, default_value = "USD", value_parser = parse_currency)]
+        reporting_currency: Currency,
+    },
+    /// Inspect and apply database migrations
+    Migrate {
+        #[command(subcommand)]
+        action: MigrateCommand,
+    },
+    /// Inspect and edit products
+    Product {
+        #[command(subcommand)]
+        action: ProductCommand,
+    },
+    /// Browse the audit log, newest events first
+    Audit {
+        /// Only events of this entity type: organization, admin, seller, product or sales
+        #[arg(long)]
+        entity: Option<String>,
+        /// Only events of the entity with this id
+        #[arg(long)]
+        id: Option<Uuid>,
+        /// Only events of this organization
+        #[arg(long)]
+        organization: Option<Uuid>,
+        /// Only events recorded at or after this time, e.g. `2023-07-06T12:00:00Z`
+        #[arg(long)]
+        since: Option<DateTime<Utc>>,
+        /// Only events recorded before this time
+        #[arg(long)]
+        until: Option<DateTime<Utc>>,
+        /// Maximum number of events to print
+        #[arg(long, default_value_t = 50)]
+        limit: i32,
+        /// Cursor printed by a previous call, to continue with the next page
+        #[arg(long)]
+        after: Option<String>,
+    },
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum MigrateCommand {
+    /// List applied, pending and modified migrations
+    Status,
+    /// Apply pending migrations
+    Up {
+        /// Stop after applying this version
+        #[arg(long)]
+        to: Option<i64>,
     },
+    /// Revert applied migrations
+    Down {
+        /// Revert every migration newer than this version; 0 reverts all of them
+        #[arg(long)]
+        to: i64,
+    },
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum ProductCommand {
+    /// Print a product, including the version to pass to `update`
+    Show {
+        /// Id of the product
+        id: Uuid,
+    },
+    /// Change a product; fields left out keep their current value
+    Update {
+        /// Id of the product
+        id: Uuid,
+        /// Version printed by `show`; the update is refused if the product changed since
+        #[arg(long)]
+        version: i64,
+        /// Stock keeping unit, unique within the organization
+        #[arg(long)]
+        sku: Option<String>,
+        /// Unique within the organization
+        #[arg(long)]
+        barcode: Option<String>,
+        #[arg(long)]
+        name: Option<String>,
+        #[arg(long)]
+        description: Option<String>,
+        /// Units in stock
+        #[arg(long)]
+        amount: Option<u32>,
+        /// Price with its currency, e.g. "12.50 USD"
+        #[arg(long, value_parser = parse_money)]
+        price: Option<Money>,
+    },
+    /// Find the products of an organization by words of their name or description
+    Search {
+        /// Id of the organization
+        #[arg(long)]
+        organization: Uuid,
+        /// Words to look for; each matches the start of a word
+        query: String,
+        /// Maximum number of products to print
+        #[arg(long, default_value_t = 10)]
+        limit: i32,
+    },
+}
+
+fn parse_money(value: &str) -> Result<Money, String> {
+    value.parse().map_err(|_| {
+        format!("'{value}' is not an amount followed by a currency code, e.g. \"12.50 USD\"")
+    })
+}
+
+fn parse_currency(value: &str) -> Result<Currency, String> {
+    value
+        .parse()
+        .map_err(|_| format!("'{value}' is not an ISO 4217 currency code, e.g. USD"))
 }
@@ -1,25 +1,30 @@
 use core_database::{
-    entities::organization::{
-        NewOrganizationDAO, OrganizationBy, OrganizationDAO, OrganizationRepository,
-    },
+    audit::Audited,
+    entities::organization::{NewOrganizationDAO, OrganizationDAO, OrganizationRepository},
+    money::Currency,
     sqlite::DatabaseRepository,
-    traits::EntityRepository,
+    traits::DatabaseError,
 };
 
 pub async fn create_organization(
     db: &DatabaseRepository,
     name: String,
+    reporting_currency: Currency,
 ) -> Result<OrganizationDAO, String> {
-    let maybe_organization =
-        OrganizationRepository::try_get(&db.connection, OrganizationBy::Name(name.clone()))
-            .await
-            .map_err(|e| format!("database error: {:#?}", e))?;
-
-    if let Some(_) = maybe_organization {
-        return Err("Organization aready exists".to_string());
-    };
-
-    OrganizationRepository::insert(&db.connection, NewOrganizationDAO { name })
+    // The CLI runs with database access and has no signed-in actor
+    Audited::<OrganizationRepository>::new(None)
+        .insert(
+            &db.connection,
+            NewOrganizationDAO {
+                name: name.clone(),
+                reporting_currency,
+            },
+        )
         .await
-        .map_err(|e| format!("database error: {:#?}", e))
+        .map_err(|e| match e {
+            DatabaseError::UniqueViolation { .. } => {
+                format!("Organization '{name}' already exists")
+            }
+            e => format!("database error: {:#?}", e),
+        })
 }
@@ -1,33 +1,76 @@
 use clap::Parser;
 use cli::{Cli, Command};
-use core_database::sqlite::DatabaseRepository;
+use core_database::sqlite::{ConnectionOptions, DatabaseRepository};
+use telemetry::TelemetryOptions;
+mod audit;
 mod create_organization;
+mod migrate;
+mod product;
 
 pub mod cli;
 
+use audit::{audit, AuditFilters};
 use create_organization::create_organization;
+use migrate::migrate;
+use product::product;
 
 #[tokio::main]
 async fn main() -> Result<(), String> {
     let args = Cli::parse();
+    telemetry::init(&TelemetryOptions {
+        level: args.log_level.clone(),
+        format: args.log_format,
+    })?;
 
-    let db = DatabaseRepository::new()
+    let options = ConnectionOptions {
+        // Migrations are managed explicitly by the migrate subcommand
+        run_migrations: !matches!(args.subcommand, Some(Command::Migrate { .. })),
+        ..ConnectionOptions::new(args.database)
+    };
+    let db = DatabaseRepository::connect(options)
         .await
         .map_err(|e| format!("Database error: {:#?}", e))?;
 
     match args.subcommand {
         Some(action) => match action {
-            Command::CreateOrganization { name } => {
-                let res = create_organization(&db, name).await?;
+            Command::CreateOrganization {
+                name,
+                reporting_currency,
+            } => {
+                let res = create_organization(&db, name, reporting_currency).await?;
 
                 println!(
                     "Organization '{}' was created successfuly with id: '{}'",
                     res.name, res.id
                 );
             }
+            Command::Migrate { action } => migrate(&db, action).await?,
+            Command::Product { action } => product(&db, action).await?,
+            Command::Audit {
+                entity,
+                id,
+                organization,
+                since,
+                until,
+                limit,
+                after,
+            } => {
+                let filters = AuditFilters {
+                    entity,
+                    id,
+                    organization,
+                    since,
+                    until,
+                    limit,
+                    after,
+                };
+                audit(&db, filters).await?
+            }
         },
         None => panic!("Select a valid subcommand"),
     };
 
+    // Gracefully close the connections so pending statements are finalized before exiting
+    db.connection.close().await;
     Ok(())
 }
@@ -0,0 +1,56 @@
+use core_database::{
+    migration::{MigrationState, MigrationStatus},
+    sqlite::DatabaseRepository,
+};
+
+use crate::cli::MigrateCommand;
+
+fn describe(status: &MigrationStatus) -> String {
+    let state = match status.state {
+        MigrationState::Applied => "applied",
+        MigrationState::Pending => "pending",
+        MigrationState::ChecksumMismatch => "modified after being applied",
+        MigrationState::Missing => "applied but missing locally",
+    };
+    format!("{} {} ({})", status.version, status.description, state)
+}
+
+pub async fn migrate(db: &DatabaseRepository, action: MigrateCommand) -> Result<(), String> {
+    match action {
+        MigrateCommand::Status => {
+            let status = db
+                .migration_status()
+                .await
+                .map_err(|e| format!("database error: {:#?}", e))?;
+            for migration in status.iter() {
+                println!("{}", describe(migration));
+            }
+        }
+        MigrateCommand::Up { to } => {
+            let applied = db
+                .migrate_up(to)
+                .await
+                .map_err(|e| format!("database error: {:#?}", e))?;
+            if applied.is_empty() {
+                println!("Database is up to date");
+            }
+            for version in applied {
+                println!("Applied migration {version}");
+            }
+        }
+        MigrateCommand::Down { to } => {
+            let reverted = db
+                .migrate_down(to)
+                .await
+                .map_err(|e| format!("database error: {:#?}", e))?;
+            if reverted.is_empty() {
+                println!("Nothing to revert");
+            }
+            for version in reverted {
+                println!("Reverted migration {version}");
+            }
+        }
+    }
+
+    Ok(())
+}
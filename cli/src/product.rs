@@ -0,0 +1,102 @@
+use core_database::{
+    audit::Audited,
+    entities::product::{
+        ProductBy, ProductDAO, ProductRepository, ProductSearch, UpdateProductDAO,
+    },
+    sqlite::DatabaseRepository,
+    traits::{DatabaseError, EntityRepository},
+};
+use uuid::Uuid;
+
+use crate::cli::ProductCommand;
+
+fn describe(product: &ProductDAO) -> String {
+    let sku = product
+        .sku
+        .as_ref()
+        .map(|sku| format!(" [SKU {sku}]"))
+        .unwrap_or_default();
+    format!(
+        "{} '{}'{} ({}): {} in stock at {}, version {}",
+        product.id,
+        product.name,
+        sku,
+        product.description,
+        product.amount,
+        product.price,
+        product.version
+    )
+}
+
+fn product_error(id: Uuid, error: DatabaseError) -> String {
+    match error {
+        DatabaseError::NotFound(_) => format!("Product '{id}' does not exist"),
+        DatabaseError::Conflict { current_version } => format!(
+            "Product '{id}' was changed by someone else and is now at version {current_version}; review it and retry"
+        ),
+        e => format!("database error: {:#?}", e),
+    }
+}
+
+pub async fn product(db: &DatabaseRepository, action: ProductCommand) -> Result<(), String> {
+    match action {
+        ProductCommand::Show { id } => {
+            let product = ProductRepository::get(&db.connection, ProductBy::Id(id))
+                .await
+                .map_err(|e| product_error(id, e))?;
+            println!("{}", describe(&product));
+        }
+        ProductCommand::Update {
+            id,
+            version,
+            sku,
+            barcode,
+            name,
+            description,
+            amount,
+            price,
+        } => {
+            let current = ProductRepository::get(&db.connection, ProductBy::Id(id))
+                .await
+                .map_err(|e| product_error(id, e))?;
+            let input = UpdateProductDAO {
+                category_id: current.category_id,
+                sku: sku.or(current.sku),
+                barcode: barcode.or(current.barcode),
+                name: name.unwrap_or(current.name),
+                description: description.unwrap_or(current.description),
+                amount: amount.unwrap_or(current.amount),
+                price: price.unwrap_or(current.price),
+                // The caller's version, not the one just read, so edits made since the
+                // caller looked at the product are not overwritten
+                version,
+            };
+            let updated = Audited::<ProductRepository>::new(None)
+                .update(&db.connection, ProductBy::Id(id), input)
+                .await
+                .map_err(|e| product_error(id, e))?;
+            println!("{}", describe(&updated));
+        }
+        ProductCommand::Search {
+            organization,
+            query,
+            limit,
+        } => {
+            let results = ProductRepository::search(&db.connection, organization, &query, limit)
+                .await
+                .map_err(|e| format!("database error: {:#?}", e))?;
+            if results.is_empty() {
+                println!("No products match '{query}'");
+            }
+            for result in results.iter() {
+                println!(
+                    "{} '{}': {} in stock at {}",
+                    result.product.id, result.name, result.product.amount, result.product.price
+                );
+                println!("  {}", result.snippet);
+            }
+        }
+    }
+
+    Ok(())
+}
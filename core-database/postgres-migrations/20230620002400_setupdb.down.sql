@@ -0,0 +1,5 @@
+DROP TABLE sales;
+DROP TABLE products;
+DROP TABLE sellers;
+DROP TABLE admins;
+DROP TABLE organizations;
@@ -0,0 +1,2 @@
+ALTER TABLE admins RENAME COLUMN password TO password_hash;
+ALTER TABLE sellers RENAME COLUMN password TO password_hash;
@@ -0,0 +1 @@
+DROP INDEX admins_one_default_per_organization;
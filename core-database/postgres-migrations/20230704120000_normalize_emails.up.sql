@@ -0,0 +1,3 @@
+-- Emails are compared case-insensitively by storing them trimmed and lowercased.
+UPDATE admins SET email = lower(trim(email));
+UPDATE sellers SET email = lower(trim(email));
@@ -0,0 +1,6 @@
+-- Every mutation increments version; updates must name the version they were based on.
+ALTER TABLE organizations ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
+ALTER TABLE admins ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
+ALTER TABLE sellers ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
+ALTER TABLE products ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
+ALTER TABLE sales ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
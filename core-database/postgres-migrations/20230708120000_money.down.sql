@@ -0,0 +1,5 @@
+ALTER TABLE sales DROP COLUMN currency;
+ALTER TABLE sales ALTER COLUMN total_price TYPE NUMERIC;
+
+ALTER TABLE products DROP COLUMN currency;
+ALTER TABLE products ALTER COLUMN price TYPE NUMERIC;
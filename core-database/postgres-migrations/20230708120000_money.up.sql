@@ -0,0 +1,9 @@
+-- Prices move from NUMERIC to integer minor units plus an ISO 4217 currency code. Existing
+-- amounts are kept as minor units in USD; the cast fails if any of them exceeds 64 bits.
+ALTER TABLE products ALTER COLUMN price TYPE BIGINT USING price::BIGINT;
+ALTER TABLE products ADD COLUMN currency TEXT NOT NULL DEFAULT 'USD';
+ALTER TABLE products ALTER COLUMN currency DROP DEFAULT;
+
+ALTER TABLE sales ALTER COLUMN total_price TYPE BIGINT USING total_price::BIGINT;
+ALTER TABLE sales ADD COLUMN currency TEXT NOT NULL DEFAULT 'USD';
+ALTER TABLE sales ALTER COLUMN currency DROP DEFAULT;
@@ -0,0 +1,2 @@
+ALTER TABLE organizations DROP COLUMN reporting_currency;
+DROP TABLE exchange_rates;
@@ -0,0 +1,12 @@
+DROP TRIGGER sales_updated_at ON sales;
+DROP TRIGGER products_updated_at ON products;
+DROP TRIGGER sellers_updated_at ON sellers;
+DROP TRIGGER admins_updated_at ON admins;
+DROP TRIGGER organizations_updated_at ON organizations;
+DROP FUNCTION set_updated_at();
+
+ALTER TABLE sellers DROP COLUMN updated_at;
+ALTER TABLE admins DROP COLUMN updated_at;
+ALTER TABLE admins DROP COLUMN created_at;
+ALTER TABLE organizations DROP COLUMN updated_at;
+ALTER TABLE organizations DROP COLUMN created_at;
@@ -0,0 +1,26 @@
+-- Organizations and admins gain created_at/updated_at, sellers updated_at; rows that predate the
+-- columns get the migration time. A trigger maintains updated_at on every update.
+ALTER TABLE organizations ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT now();
+ALTER TABLE organizations ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT now();
+ALTER TABLE admins ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT now();
+ALTER TABLE admins ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT now();
+ALTER TABLE sellers ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT now();
+UPDATE sellers SET updated_at = created_at;
+
+CREATE FUNCTION set_updated_at() RETURNS trigger AS $$
+BEGIN
+    NEW.updated_at = now();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER organizations_updated_at BEFORE UPDATE ON organizations
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+CREATE TRIGGER admins_updated_at BEFORE UPDATE ON admins
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+CREATE TRIGGER sellers_updated_at BEFORE UPDATE ON sellers
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+CREATE TRIGGER products_updated_at BEFORE UPDATE ON products
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+CREATE TRIGGER sales_updated_at BEFORE UPDATE ON sales
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
@@ -0,0 +1,2 @@
+ALTER TABLE sales DROP CONSTRAINT sales_amount_non_negative;
+ALTER TABLE products DROP CONSTRAINT products_amount_non_negative;
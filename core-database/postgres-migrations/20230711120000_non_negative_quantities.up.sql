@@ -0,0 +1,4 @@
+-- Stock and sale quantities are unsigned in the repositories. The migration fails on a negative
+-- amount already stored, which has to be corrected by hand first.
+ALTER TABLE products ADD CONSTRAINT products_amount_non_negative CHECK (amount >= 0);
+ALTER TABLE sales ADD CONSTRAINT sales_amount_non_negative CHECK (amount >= 0);
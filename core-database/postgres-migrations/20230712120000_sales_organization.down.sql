@@ -0,0 +1,3 @@
+ALTER TABLE sales DROP COLUMN organization_id;
+ALTER TABLE sellers DROP CONSTRAINT sellers_id_organization;
+ALTER TABLE products DROP CONSTRAINT products_id_organization;
@@ -0,0 +1,15 @@
+-- Sales reference their organization directly. Composite foreign keys on (id, organization_id)
+-- reject a sale whose product or seller belongs to another organization; the existing sales
+-- take the organization of their product, and adding sales_seller_organization fails if a
+-- seller of a different organization made one.
+ALTER TABLE products ADD CONSTRAINT products_id_organization UNIQUE (id, organization_id);
+ALTER TABLE sellers ADD CONSTRAINT sellers_id_organization UNIQUE (id, organization_id);
+
+ALTER TABLE sales ADD COLUMN organization_id UUID;
+UPDATE sales SET organization_id = products.organization_id FROM products WHERE products.id = sales.product_id;
+ALTER TABLE sales ALTER COLUMN organization_id SET NOT NULL;
+ALTER TABLE sales ADD CONSTRAINT sales_organization FOREIGN KEY (organization_id) REFERENCES organizations(id);
+ALTER TABLE sales ADD CONSTRAINT sales_product_organization
+    FOREIGN KEY (product_id, organization_id) REFERENCES products(id, organization_id);
+ALTER TABLE sales ADD CONSTRAINT sales_seller_organization
+    FOREIGN KEY (seller_id, organization_id) REFERENCES sellers(id, organization_id);
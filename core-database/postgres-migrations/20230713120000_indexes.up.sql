@@ -0,0 +1,11 @@
+-- Indexes for the filters the repositories list and look rows up by; each leads with the
+-- filtered column and continues with the default listing order where there is one.
+CREATE INDEX organizations_active_name ON organizations (active, name);
+CREATE INDEX admins_organization_email ON admins (organization_id, email);
+CREATE INDEX sellers_organization_email ON sellers (organization_id, email);
+CREATE INDEX products_organization_created_at ON products (organization_id, created_at);
+CREATE INDEX sales_organization_created_at ON sales (organization_id, created_at);
+CREATE INDEX sales_product_created_at ON sales (product_id, created_at);
+CREATE INDEX sales_seller_created_at ON sales (seller_id, created_at);
+CREATE INDEX audit_events_organization_created_at ON audit_events (organization_id, created_at);
+CREATE INDEX audit_events_actor_created_at ON audit_events (actor_id, created_at);
@@ -0,0 +1,9 @@
+-- Adding admins_email_key and sellers_email_key back fails if an email is used in more than one
+-- organization, which has to be resolved by hand first.
+ALTER TABLE sellers DROP CONSTRAINT sellers_organization_email;
+CREATE INDEX sellers_organization_email ON sellers (organization_id, email);
+ALTER TABLE sellers ADD CONSTRAINT sellers_email_key UNIQUE (email);
+
+ALTER TABLE admins DROP CONSTRAINT admins_organization_email;
+CREATE INDEX admins_organization_email ON admins (organization_id, email);
+ALTER TABLE admins ADD CONSTRAINT admins_email_key UNIQUE (email);
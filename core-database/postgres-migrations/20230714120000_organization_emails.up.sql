@@ -0,0 +1,10 @@
+-- Emails are unique within an organization rather than globally, so one person can hold
+-- accounts in several organizations. Existing emails are globally unique, so they already are
+-- per organization. The unique constraints replace the plain indexes of 20230713120000_indexes.
+ALTER TABLE admins DROP CONSTRAINT admins_email_key;
+DROP INDEX admins_organization_email;
+ALTER TABLE admins ADD CONSTRAINT admins_organization_email UNIQUE (organization_id, email);
+
+ALTER TABLE sellers DROP CONSTRAINT sellers_email_key;
+DROP INDEX sellers_organization_email;
+ALTER TABLE sellers ADD CONSTRAINT sellers_organization_email UNIQUE (organization_id, email);
@@ -0,0 +1,5 @@
+-- Full-text search over product names and descriptions; names weigh more in the ranking.
+ALTER TABLE products ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('simple', name), 'A') || setweight(to_tsvector('simple', description), 'B')
+) STORED;
+CREATE INDEX products_search_vector ON products USING GIN (search_vector);
@@ -0,0 +1,13 @@
+-- SKUs, barcodes, categories and tags are dropped along with the tables holding them.
+DROP TABLE product_tags;
+
+DROP INDEX products_category_created_at;
+ALTER TABLE products DROP CONSTRAINT products_organization_barcode;
+ALTER TABLE products DROP CONSTRAINT products_organization_sku;
+ALTER TABLE products DROP CONSTRAINT products_category_organization;
+ALTER TABLE products DROP COLUMN barcode;
+ALTER TABLE products DROP COLUMN sku;
+ALTER TABLE products DROP COLUMN category_id;
+
+DROP TABLE tags;
+DROP TABLE categories;
@@ -0,0 +1,57 @@
+-- Product catalog: optional SKUs and barcodes, unique within an organization, a category tree
+-- and tags. Composite foreign keys on (id, organization_id) keep a product's category, a
+-- category's parent and a product's tags within the product's organization.
+CREATE TABLE categories (
+    id UUID NOT NULL PRIMARY KEY,
+    organization_id UUID NOT NULL REFERENCES organizations(id),
+    parent_id UUID,
+    name TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    version BIGINT NOT NULL DEFAULT 1,
+    CONSTRAINT categories_id_organization UNIQUE (id, organization_id),
+    CONSTRAINT categories_parent_organization
+        FOREIGN KEY (parent_id, organization_id) REFERENCES categories(id, organization_id)
+);
+
+CREATE TABLE tags (
+    id UUID NOT NULL PRIMARY KEY,
+    organization_id UUID NOT NULL REFERENCES organizations(id),
+    name TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    version BIGINT NOT NULL DEFAULT 1,
+    CONSTRAINT tags_id_organization UNIQUE (id, organization_id),
+    CONSTRAINT tags_organization_name UNIQUE (organization_id, name)
+);
+
+CREATE INDEX categories_organization_name ON categories (organization_id, name);
+CREATE INDEX categories_parent_name ON categories (parent_id, name);
+
+CREATE TRIGGER categories_updated_at BEFORE UPDATE ON categories
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+CREATE TRIGGER tags_updated_at BEFORE UPDATE ON tags
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+ALTER TABLE products ADD COLUMN category_id UUID;
+ALTER TABLE products ADD COLUMN sku TEXT;
+ALTER TABLE products ADD COLUMN barcode TEXT;
+ALTER TABLE products ADD CONSTRAINT products_category_organization
+    FOREIGN KEY (category_id, organization_id) REFERENCES categories(id, organization_id);
+ALTER TABLE products ADD CONSTRAINT products_organization_sku UNIQUE (organization_id, sku);
+ALTER TABLE products ADD CONSTRAINT products_organization_barcode UNIQUE (organization_id, barcode);
+CREATE INDEX products_category_created_at ON products (category_id, created_at);
+
+-- Tags follow the product's organization; removing a product or a tag removes its taggings.
+CREATE TABLE product_tags (
+    product_id UUID NOT NULL,
+    tag_id UUID NOT NULL,
+    organization_id UUID NOT NULL,
+    PRIMARY KEY (product_id, tag_id),
+    CONSTRAINT product_tags_product_organization FOREIGN KEY (product_id, organization_id)
+        REFERENCES products(id, organization_id) ON DELETE CASCADE,
+    CONSTRAINT product_tags_tag_organization FOREIGN KEY (tag_id, organization_id)
+        REFERENCES tags(id, organization_id) ON DELETE CASCADE
+);
+
+CREATE INDEX product_tags_tag ON product_tags (tag_id, product_id);
@@ -0,0 +1,2 @@
+ALTER TABLE sellers RENAME COLUMN password_hash TO password;
+ALTER TABLE admins RENAME COLUMN password_hash TO password;
@@ -0,0 +1,8 @@
+-- Keep a single default admin per organization before enforcing it.
+UPDATE admins SET is_default = false
+WHERE is_default AND EXISTS (
+    SELECT 1 FROM admins other
+    WHERE other.organization_id = admins.organization_id AND other.is_default AND other.id < admins.id
+);
+
+CREATE UNIQUE INDEX admins_one_default_per_organization ON admins (organization_id) WHERE is_default;
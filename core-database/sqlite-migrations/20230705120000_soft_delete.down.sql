@@ -0,0 +1,5 @@
+ALTER TABLE sales DROP COLUMN deleted_at;
+ALTER TABLE products DROP COLUMN deleted_at;
+ALTER TABLE sellers DROP COLUMN deleted_at;
+ALTER TABLE admins DROP COLUMN deleted_at;
+ALTER TABLE organizations DROP COLUMN deleted_at;
@@ -0,0 +1,6 @@
+-- Rows are soft-deleted by setting deleted_at; purging removes them for good.
+ALTER TABLE organizations ADD COLUMN deleted_at INTEGER;
+ALTER TABLE admins ADD COLUMN deleted_at INTEGER;
+ALTER TABLE sellers ADD COLUMN deleted_at INTEGER;
+ALTER TABLE products ADD COLUMN deleted_at INTEGER;
+ALTER TABLE sales ADD COLUMN deleted_at INTEGER;
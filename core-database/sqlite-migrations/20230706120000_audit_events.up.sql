@@ -0,0 +1,15 @@
+-- No foreign keys: events outlive the rows they describe, even once purged.
+CREATE TABLE audit_events (
+    id UUID NOT NULL PRIMARY KEY,
+    actor_id UUID,
+    organization_id UUID,
+    entity_type TEXT NOT NULL,
+    entity_id UUID NOT NULL,
+    operation TEXT NOT NULL,
+    before_json TEXT,
+    after_json TEXT,
+    created_at INTEGER NOT NULL DEFAULT (unixepoch('now'))
+);
+
+CREATE INDEX audit_events_entity ON audit_events (entity_type, entity_id, created_at);
+CREATE INDEX audit_events_created_at ON audit_events (created_at);
@@ -0,0 +1,5 @@
+ALTER TABLE sales DROP COLUMN version;
+ALTER TABLE products DROP COLUMN version;
+ALTER TABLE sellers DROP COLUMN version;
+ALTER TABLE admins DROP COLUMN version;
+ALTER TABLE organizations DROP COLUMN version;
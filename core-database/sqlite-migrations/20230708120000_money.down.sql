@@ -0,0 +1,36 @@
+-- Byte i of the amount is picked out of a blob holding every byte value; concatenating the
+-- picks keeps the raw bytes, giving the 8-byte little-endian form read by BigUint.
+CREATE TEMPORARY TABLE bytes (b BLOB NOT NULL);
+INSERT INTO bytes VALUES (X'000102030405060708090A0B0C0D0E0F101112131415161718191A1B1C1D1E1F202122232425262728292A2B2C2D2E2F303132333435363738393A3B3C3D3E3F404142434445464748494A4B4C4D4E4F505152535455565758595A5B5C5D5E5F606162636465666768696A6B6C6D6E6F707172737475767778797A7B7C7D7E7F808182838485868788898A8B8C8D8E8F909192939495969798999A9B9C9D9E9FA0A1A2A3A4A5A6A7A8A9AAABACADAEAFB0B1B2B3B4B5B6B7B8B9BABBBCBDBEBFC0C1C2C3C4C5C6C7C8C9CACBCCCDCECFD0D1D2D3D4D5D6D7D8D9DADBDCDDDEDFE0E1E2E3E4E5E6E7E8E9EAEBECEDEEEFF0F1F2F3F4F5F6F7F8F9FAFBFCFDFEFF');
+
+ALTER TABLE sales DROP COLUMN currency;
+ALTER TABLE sales ADD COLUMN total_price_bytes BLOB NOT NULL DEFAULT X'00';
+UPDATE sales SET total_price_bytes = (SELECT CAST(
+    substr(bytes.b, ((total_price >> 0) & 255) + 1, 1) ||
+    substr(bytes.b, ((total_price >> 8) & 255) + 1, 1) ||
+    substr(bytes.b, ((total_price >> 16) & 255) + 1, 1) ||
+    substr(bytes.b, ((total_price >> 24) & 255) + 1, 1) ||
+    substr(bytes.b, ((total_price >> 32) & 255) + 1, 1) ||
+    substr(bytes.b, ((total_price >> 40) & 255) + 1, 1) ||
+    substr(bytes.b, ((total_price >> 48) & 255) + 1, 1) ||
+    substr(bytes.b, ((total_price >> 56) & 255) + 1, 1)
+    AS BLOB) FROM bytes);
+ALTER TABLE sales DROP COLUMN total_price;
+ALTER TABLE sales RENAME COLUMN total_price_bytes TO total_price;
+
+ALTER TABLE products DROP COLUMN currency;
+ALTER TABLE products ADD COLUMN price_bytes BLOB NOT NULL DEFAULT X'00';
+UPDATE products SET price_bytes = (SELECT CAST(
+    substr(bytes.b, ((price >> 0) & 255) + 1, 1) ||
+    substr(bytes.b, ((price >> 8) & 255) + 1, 1) ||
+    substr(bytes.b, ((price >> 16) & 255) + 1, 1) ||
+    substr(bytes.b, ((price >> 24) & 255) + 1, 1) ||
+    substr(bytes.b, ((price >> 32) & 255) + 1, 1) ||
+    substr(bytes.b, ((price >> 40) & 255) + 1, 1) ||
+    substr(bytes.b, ((price >> 48) & 255) + 1, 1) ||
+    substr(bytes.b, ((price >> 56) & 255) + 1, 1)
+    AS BLOB) FROM bytes);
+ALTER TABLE products DROP COLUMN price;
+ALTER TABLE products RENAME COLUMN price_bytes TO price;
+
+DROP TABLE bytes;
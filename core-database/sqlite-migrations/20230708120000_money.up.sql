@@ -0,0 +1,36 @@
+-- Prices move from little-endian BigUint blobs to integer minor units plus an ISO 4217
+-- currency code, so SQL can compare, sort and sum them. Existing amounts are kept as minor
+-- units in USD, and the migration fails if any of them does not fit in 64 bits.
+CREATE TEMPORARY TABLE oversized_prices (id UUID, CONSTRAINT price_exceeds_64_bits CHECK (0));
+INSERT INTO oversized_prices SELECT id FROM products WHERE length(price) > 8 OR (length(price) = 8 AND substr(hex(price), 15, 1) >= '8');
+INSERT INTO oversized_prices SELECT id FROM sales WHERE length(total_price) > 8 OR (length(total_price) = 8 AND substr(hex(total_price), 15, 1) >= '8');
+DROP TABLE oversized_prices;
+
+-- Byte i of the blob, read from its hex dump, is shifted into place
+ALTER TABLE products ADD COLUMN price_minor_units INTEGER NOT NULL DEFAULT 0;
+UPDATE products SET price_minor_units =
+    (((instr('0123456789ABCDEF', substr(hex(price), 1, 1)) - 1) * 16 + instr('0123456789ABCDEF', substr(hex(price), 2, 1)) - 1) << 0) +
+    (((instr('0123456789ABCDEF', substr(hex(price), 3, 1)) - 1) * 16 + instr('0123456789ABCDEF', substr(hex(price), 4, 1)) - 1) << 8) +
+    (((instr('0123456789ABCDEF', substr(hex(price), 5, 1)) - 1) * 16 + instr('0123456789ABCDEF', substr(hex(price), 6, 1)) - 1) << 16) +
+    (((instr('0123456789ABCDEF', substr(hex(price), 7, 1)) - 1) * 16 + instr('0123456789ABCDEF', substr(hex(price), 8, 1)) - 1) << 24) +
+    (((instr('0123456789ABCDEF', substr(hex(price), 9, 1)) - 1) * 16 + instr('0123456789ABCDEF', substr(hex(price), 10, 1)) - 1) << 32) +
+    (((instr('0123456789ABCDEF', substr(hex(price), 11, 1)) - 1) * 16 + instr('0123456789ABCDEF', substr(hex(price), 12, 1)) - 1) << 40) +
+    (((instr('0123456789ABCDEF', substr(hex(price), 13, 1)) - 1) * 16 + instr('0123456789ABCDEF', substr(hex(price), 14, 1)) - 1) << 48) +
+    (((instr('0123456789ABCDEF', substr(hex(price), 15, 1)) - 1) * 16 + instr('0123456789ABCDEF', substr(hex(price), 16, 1)) - 1) << 56);
+ALTER TABLE products DROP COLUMN price;
+ALTER TABLE products RENAME COLUMN price_minor_units TO price;
+ALTER TABLE products ADD COLUMN currency TEXT NOT NULL DEFAULT 'USD';
+
+ALTER TABLE sales ADD COLUMN total_price_minor_units INTEGER NOT NULL DEFAULT 0;
+UPDATE sales SET total_price_minor_units =
+    (((instr('0123456789ABCDEF', substr(hex(total_price), 1, 1)) - 1) * 16 + instr('0123456789ABCDEF', substr(hex(total_price), 2, 1)) - 1) << 0) +
+    (((instr('0123456789ABCDEF', substr(hex(total_price), 3, 1)) - 1) * 16 + instr('0123456789ABCDEF', substr(hex(total_price), 4, 1)) - 1) << 8) +
+    (((instr('0123456789ABCDEF', substr(hex(total_price), 5, 1)) - 1) * 16 + instr('0123456789ABCDEF', substr(hex(total_price), 6, 1)) - 1) << 16) +
+    (((instr('0123456789ABCDEF', substr(hex(total_price), 7, 1)) - 1) * 16 + instr('0123456789ABCDEF', substr(hex(total_price), 8, 1)) - 1) << 24) +
+    (((instr('0123456789ABCDEF', substr(hex(total_price), 9, 1)) - 1) * 16 + instr('0123456789ABCDEF', substr(hex(total_price), 10, 1)) - 1) << 32) +
+    (((instr('0123456789ABCDEF', substr(hex(total_price), 11, 1)) - 1) * 16 + instr('0123456789ABCDEF', substr(hex(total_price), 12, 1)) - 1) << 40) +
+    (((instr('0123456789ABCDEF', substr(hex(total_price), 13, 1)) - 1) * 16 + instr('0123456789ABCDEF', substr(hex(total_price), 14, 1)) - 1) << 48) +
+    (((instr('0123456789ABCDEF', substr(hex(total_price), 15, 1)) - 1) * 16 + instr('0123456789ABCDEF', substr(hex(total_price), 16, 1)) - 1) << 56);
+ALTER TABLE sales DROP COLUMN total_price;
+ALTER TABLE sales RENAME COLUMN total_price_minor_units TO total_price;
+ALTER TABLE sales ADD COLUMN currency TEXT NOT NULL DEFAULT 'USD';
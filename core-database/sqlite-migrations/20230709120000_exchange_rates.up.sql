@@ -0,0 +1,12 @@
+-- A rate converts one major unit of from_currency into to_currency, in billionths. It applies
+-- from effective_at until the next rate of the same pair.
+CREATE TABLE exchange_rates (
+    from_currency TEXT NOT NULL,
+    to_currency TEXT NOT NULL,
+    effective_at INTEGER NOT NULL,
+    rate INTEGER NOT NULL,
+    created_at INTEGER NOT NULL DEFAULT (unixepoch('now')),
+    PRIMARY KEY (from_currency, to_currency, effective_at)
+);
+
+ALTER TABLE organizations ADD COLUMN reporting_currency TEXT NOT NULL DEFAULT 'USD';
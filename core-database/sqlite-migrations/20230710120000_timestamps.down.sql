@@ -0,0 +1,135 @@
+-- Back to integer seconds; sub-second precision and the organization and admin timestamps are
+-- lost. Tables are rebuilt the same way as in the up migration.
+DROP TRIGGER organizations_updated_at;
+DROP TRIGGER admins_updated_at;
+DROP TRIGGER sellers_updated_at;
+DROP TRIGGER products_updated_at;
+DROP TRIGGER sales_updated_at;
+
+ALTER TABLE sales RENAME TO sales_old;
+ALTER TABLE products RENAME TO products_old;
+ALTER TABLE sellers RENAME TO sellers_old;
+ALTER TABLE admins RENAME TO admins_old;
+ALTER TABLE organizations RENAME TO organizations_old;
+DROP INDEX admins_one_default_per_organization;
+
+CREATE TABLE organizations (
+    id UUID NOT NULL PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    active BOOLEAN NOT NULL DEFAULT true,
+    deleted_at INTEGER,
+    version INTEGER NOT NULL DEFAULT 1,
+    reporting_currency TEXT NOT NULL DEFAULT 'USD'
+);
+
+CREATE TABLE admins (
+    id UUID NOT NULL PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    email TEXT NOT NULL UNIQUE,
+    password_hash TEXT NOT NULL,
+    is_default BOOLEAN NOT NULL DEFAULT false,
+    deleted_at INTEGER,
+    version INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY (organization_id) REFERENCES organizations(id)
+);
+
+CREATE UNIQUE INDEX admins_one_default_per_organization ON admins (organization_id) WHERE is_default;
+
+CREATE TABLE sellers (
+    id UUID NOT NULL PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    email TEXT NOT NULL UNIQUE,
+    password_hash TEXT NOT NULL,
+    active BOOLEAN NOT NULL DEFAULT true,
+    created_at INTEGER DEFAULT (unixepoch('now')),
+    deleted_at INTEGER,
+    version INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY (organization_id) REFERENCES organizations(id)
+);
+
+CREATE TABLE products (
+    id UUID NOT NULL PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    created_at INTEGER DEFAULT (unixepoch('now')),
+    updated_at INTEGER DEFAULT (unixepoch('now')),
+    deleted_at INTEGER,
+    version INTEGER NOT NULL DEFAULT 1,
+    price INTEGER NOT NULL DEFAULT 0,
+    currency TEXT NOT NULL DEFAULT 'USD',
+    FOREIGN KEY (organization_id) REFERENCES organizations(id)
+);
+
+CREATE TABLE sales (
+    id UUID NOT NULL PRIMARY KEY,
+    product_id UUID NOT NULL,
+    seller_id UUID NOT NULL,
+    amount INTEGER NOT NULL,
+    created_at INTEGER DEFAULT (unixepoch('now')),
+    updated_at INTEGER DEFAULT (unixepoch('now')),
+    deleted_at INTEGER,
+    version INTEGER NOT NULL DEFAULT 1,
+    total_price INTEGER NOT NULL DEFAULT 0,
+    currency TEXT NOT NULL DEFAULT 'USD',
+    FOREIGN KEY (product_id) REFERENCES products(id),
+    FOREIGN KEY (seller_id) REFERENCES sellers(id)
+);
+
+INSERT INTO organizations (id, name, active, deleted_at, version, reporting_currency)
+    SELECT id, name, active, unixepoch(deleted_at), version, reporting_currency
+    FROM organizations_old;
+INSERT INTO admins (id, organization_id, email, password_hash, is_default, deleted_at, version)
+    SELECT id, organization_id, email, password_hash, is_default, unixepoch(deleted_at), version
+    FROM admins_old;
+INSERT INTO sellers (id, organization_id, email, password_hash, active, created_at, deleted_at, version)
+    SELECT id, organization_id, email, password_hash, active, unixepoch(created_at), unixepoch(deleted_at), version
+    FROM sellers_old;
+INSERT INTO products (id, organization_id, name, description, amount, created_at, updated_at, deleted_at, version, price, currency)
+    SELECT id, organization_id, name, description, amount, unixepoch(created_at), unixepoch(updated_at), unixepoch(deleted_at), version, price, currency
+    FROM products_old;
+INSERT INTO sales (id, product_id, seller_id, amount, created_at, updated_at, deleted_at, version, total_price, currency)
+    SELECT id, product_id, seller_id, amount, unixepoch(created_at), unixepoch(updated_at), unixepoch(deleted_at), version, total_price, currency
+    FROM sales_old;
+
+DROP TABLE sales_old;
+DROP TABLE products_old;
+DROP TABLE sellers_old;
+DROP TABLE admins_old;
+DROP TABLE organizations_old;
+
+ALTER TABLE audit_events RENAME TO audit_events_old;
+DROP INDEX audit_events_entity;
+DROP INDEX audit_events_created_at;
+CREATE TABLE audit_events (
+    id UUID NOT NULL PRIMARY KEY,
+    actor_id UUID,
+    organization_id UUID,
+    entity_type TEXT NOT NULL,
+    entity_id UUID NOT NULL,
+    operation TEXT NOT NULL,
+    before_json TEXT,
+    after_json TEXT,
+    created_at INTEGER NOT NULL DEFAULT (unixepoch('now'))
+);
+INSERT INTO audit_events (id, actor_id, organization_id, entity_type, entity_id, operation, before_json, after_json, created_at)
+    SELECT id, actor_id, organization_id, entity_type, entity_id, operation, before_json, after_json, unixepoch(created_at)
+    FROM audit_events_old;
+DROP TABLE audit_events_old;
+CREATE INDEX audit_events_entity ON audit_events (entity_type, entity_id, created_at);
+CREATE INDEX audit_events_created_at ON audit_events (created_at);
+
+ALTER TABLE exchange_rates RENAME TO exchange_rates_old;
+CREATE TABLE exchange_rates (
+    from_currency TEXT NOT NULL,
+    to_currency TEXT NOT NULL,
+    effective_at INTEGER NOT NULL,
+    rate INTEGER NOT NULL,
+    created_at INTEGER NOT NULL DEFAULT (unixepoch('now')),
+    PRIMARY KEY (from_currency, to_currency, effective_at)
+);
+INSERT INTO exchange_rates (from_currency, to_currency, effective_at, rate, created_at)
+    SELECT from_currency, to_currency, unixepoch(effective_at), rate, unixepoch(created_at)
+    FROM exchange_rates_old;
+DROP TABLE exchange_rates_old;
@@ -0,0 +1,182 @@
+-- Timestamps move from integer seconds (declared `NO NULL`, i.e. nullable) to NOT NULL text in
+-- the fixed-width form 2023-07-10T12:00:00.000000Z, with six fractional digits like PostgreSQL,
+-- and text order is time order. The clock of SQLite has millisecond resolution: values set by
+-- the defaults and triggers below end in `000`, only values bound by the application carry
+-- microseconds. Organizations and admins gain created_at/updated_at, sellers updated_at; rows
+-- that predate the columns get the migration time.
+--
+-- SQLite cannot change column constraints in place, so every table is rebuilt. Foreign keys
+-- cannot be switched off inside the migration transaction, and renaming a table rewrites the
+-- references to it, so the old tables are renamed first, keep referencing each other, and are
+-- dropped children first once their rows are copied.
+ALTER TABLE sales RENAME TO sales_old;
+ALTER TABLE products RENAME TO products_old;
+ALTER TABLE sellers RENAME TO sellers_old;
+ALTER TABLE admins RENAME TO admins_old;
+ALTER TABLE organizations RENAME TO organizations_old;
+DROP INDEX admins_one_default_per_organization;
+
+CREATE TABLE organizations (
+    id UUID NOT NULL PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    active BOOLEAN NOT NULL DEFAULT true,
+    reporting_currency TEXT NOT NULL DEFAULT 'USD',
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    deleted_at TEXT,
+    version INTEGER NOT NULL DEFAULT 1
+);
+
+CREATE TABLE admins (
+    id UUID NOT NULL PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    email TEXT NOT NULL UNIQUE,
+    password_hash TEXT NOT NULL,
+    is_default BOOLEAN NOT NULL DEFAULT false,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    deleted_at TEXT,
+    version INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY (organization_id) REFERENCES organizations(id)
+);
+
+CREATE UNIQUE INDEX admins_one_default_per_organization ON admins (organization_id) WHERE is_default;
+
+CREATE TABLE sellers (
+    id UUID NOT NULL PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    email TEXT NOT NULL UNIQUE,
+    password_hash TEXT NOT NULL,
+    active BOOLEAN NOT NULL DEFAULT true,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    deleted_at TEXT,
+    version INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY (organization_id) REFERENCES organizations(id)
+);
+
+CREATE TABLE products (
+    id UUID NOT NULL PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    price INTEGER NOT NULL,
+    currency TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    deleted_at TEXT,
+    version INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY (organization_id) REFERENCES organizations(id)
+);
+
+CREATE TABLE sales (
+    id UUID NOT NULL PRIMARY KEY,
+    product_id UUID NOT NULL,
+    seller_id UUID NOT NULL,
+    amount INTEGER NOT NULL,
+    total_price INTEGER NOT NULL,
+    currency TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    deleted_at TEXT,
+    version INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY (product_id) REFERENCES products(id),
+    FOREIGN KEY (seller_id) REFERENCES sellers(id)
+);
+
+INSERT INTO organizations (id, name, active, reporting_currency, deleted_at, version)
+    SELECT id, name, active, reporting_currency, strftime('%Y-%m-%dT%H:%M:%S.000000Z', deleted_at, 'unixepoch'), version
+    FROM organizations_old;
+INSERT INTO admins (id, organization_id, email, password_hash, is_default, deleted_at, version)
+    SELECT id, organization_id, email, password_hash, is_default, strftime('%Y-%m-%dT%H:%M:%S.000000Z', deleted_at, 'unixepoch'), version
+    FROM admins_old;
+INSERT INTO sellers (id, organization_id, email, password_hash, active, created_at, updated_at, deleted_at, version)
+    SELECT id, organization_id, email, password_hash, active,
+        strftime('%Y-%m-%dT%H:%M:%S.000000Z', coalesce(created_at, unixepoch('now')), 'unixepoch'),
+        strftime('%Y-%m-%dT%H:%M:%S.000000Z', coalesce(created_at, unixepoch('now')), 'unixepoch'),
+        strftime('%Y-%m-%dT%H:%M:%S.000000Z', deleted_at, 'unixepoch'), version
+    FROM sellers_old;
+INSERT INTO products (id, organization_id, name, description, amount, price, currency, created_at, updated_at, deleted_at, version)
+    SELECT id, organization_id, name, description, amount, price, currency,
+        strftime('%Y-%m-%dT%H:%M:%S.000000Z', coalesce(created_at, updated_at, unixepoch('now')), 'unixepoch'),
+        strftime('%Y-%m-%dT%H:%M:%S.000000Z', coalesce(updated_at, created_at, unixepoch('now')), 'unixepoch'),
+        strftime('%Y-%m-%dT%H:%M:%S.000000Z', deleted_at, 'unixepoch'), version
+    FROM products_old;
+INSERT INTO sales (id, product_id, seller_id, amount, total_price, currency, created_at, updated_at, deleted_at, version)
+    SELECT id, product_id, seller_id, amount, total_price, currency,
+        strftime('%Y-%m-%dT%H:%M:%S.000000Z', coalesce(created_at, updated_at, unixepoch('now')), 'unixepoch'),
+        strftime('%Y-%m-%dT%H:%M:%S.000000Z', coalesce(updated_at, created_at, unixepoch('now')), 'unixepoch'),
+        strftime('%Y-%m-%dT%H:%M:%S.000000Z', deleted_at, 'unixepoch'), version
+    FROM sales_old;
+
+DROP TABLE sales_old;
+DROP TABLE products_old;
+DROP TABLE sellers_old;
+DROP TABLE admins_old;
+DROP TABLE organizations_old;
+
+ALTER TABLE audit_events RENAME TO audit_events_old;
+DROP INDEX audit_events_entity;
+DROP INDEX audit_events_created_at;
+CREATE TABLE audit_events (
+    id UUID NOT NULL PRIMARY KEY,
+    actor_id UUID,
+    organization_id UUID,
+    entity_type TEXT NOT NULL,
+    entity_id UUID NOT NULL,
+    operation TEXT NOT NULL,
+    before_json TEXT,
+    after_json TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'))
+);
+INSERT INTO audit_events (id, actor_id, organization_id, entity_type, entity_id, operation, before_json, after_json, created_at)
+    SELECT id, actor_id, organization_id, entity_type, entity_id, operation, before_json, after_json,
+        strftime('%Y-%m-%dT%H:%M:%S.000000Z', created_at, 'unixepoch')
+    FROM audit_events_old;
+DROP TABLE audit_events_old;
+CREATE INDEX audit_events_entity ON audit_events (entity_type, entity_id, created_at);
+CREATE INDEX audit_events_created_at ON audit_events (created_at);
+
+ALTER TABLE exchange_rates RENAME TO exchange_rates_old;
+CREATE TABLE exchange_rates (
+    from_currency TEXT NOT NULL,
+    to_currency TEXT NOT NULL,
+    effective_at TEXT NOT NULL,
+    rate INTEGER NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    PRIMARY KEY (from_currency, to_currency, effective_at)
+);
+INSERT INTO exchange_rates (from_currency, to_currency, effective_at, rate, created_at)
+    SELECT from_currency, to_currency, strftime('%Y-%m-%dT%H:%M:%S.000000Z', effective_at, 'unixepoch'), rate,
+        strftime('%Y-%m-%dT%H:%M:%S.000000Z', created_at, 'unixepoch')
+    FROM exchange_rates_old;
+DROP TABLE exchange_rates_old;
+
+-- The repository sets updated_at in its own statements, as RETURNING does not see changes made
+-- by triggers. The triggers cover every other update.
+CREATE TRIGGER organizations_updated_at AFTER UPDATE ON organizations
+    FOR EACH ROW WHEN NEW.updated_at IS OLD.updated_at
+    BEGIN
+        UPDATE organizations SET updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now') WHERE id = NEW.id;
+    END;
+CREATE TRIGGER admins_updated_at AFTER UPDATE ON admins
+    FOR EACH ROW WHEN NEW.updated_at IS OLD.updated_at
+    BEGIN
+        UPDATE admins SET updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now') WHERE id = NEW.id;
+    END;
+CREATE TRIGGER sellers_updated_at AFTER UPDATE ON sellers
+    FOR EACH ROW WHEN NEW.updated_at IS OLD.updated_at
+    BEGIN
+        UPDATE sellers SET updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now') WHERE id = NEW.id;
+    END;
+CREATE TRIGGER products_updated_at AFTER UPDATE ON products
+    FOR EACH ROW WHEN NEW.updated_at IS OLD.updated_at
+    BEGIN
+        UPDATE products SET updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now') WHERE id = NEW.id;
+    END;
+CREATE TRIGGER sales_updated_at AFTER UPDATE ON sales
+    FOR EACH ROW WHEN NEW.updated_at IS OLD.updated_at
+    BEGIN
+        UPDATE sales SET updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now') WHERE id = NEW.id;
+    END;
@@ -0,0 +1,55 @@
+-- Stock and sale quantities are unsigned in the repositories. The migration fails on a negative
+-- amount already stored, which has to be corrected by hand first.
+--
+-- Adding a CHECK constraint needs a rebuild; sales references products, so both are renamed
+-- first as in 20230710120000_timestamps.
+DROP TRIGGER products_updated_at;
+DROP TRIGGER sales_updated_at;
+ALTER TABLE sales RENAME TO sales_old;
+ALTER TABLE products RENAME TO products_old;
+
+CREATE TABLE products (
+    id UUID NOT NULL PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    amount INTEGER NOT NULL CONSTRAINT products_amount_non_negative CHECK (amount >= 0),
+    price INTEGER NOT NULL,
+    currency TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    deleted_at TEXT,
+    version INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY (organization_id) REFERENCES organizations(id)
+);
+
+CREATE TABLE sales (
+    id UUID NOT NULL PRIMARY KEY,
+    product_id UUID NOT NULL,
+    seller_id UUID NOT NULL,
+    amount INTEGER NOT NULL CONSTRAINT sales_amount_non_negative CHECK (amount >= 0),
+    total_price INTEGER NOT NULL,
+    currency TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    deleted_at TEXT,
+    version INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY (product_id) REFERENCES products(id),
+    FOREIGN KEY (seller_id) REFERENCES sellers(id)
+);
+
+INSERT INTO products SELECT id, organization_id, name, description, amount, price, currency, created_at, updated_at, deleted_at, version FROM products_old;
+INSERT INTO sales SELECT id, product_id, seller_id, amount, total_price, currency, created_at, updated_at, deleted_at, version FROM sales_old;
+DROP TABLE sales_old;
+DROP TABLE products_old;
+
+CREATE TRIGGER products_updated_at AFTER UPDATE ON products
+    FOR EACH ROW WHEN NEW.updated_at IS OLD.updated_at
+    BEGIN
+        UPDATE products SET updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now') WHERE id = NEW.id;
+    END;
+CREATE TRIGGER sales_updated_at AFTER UPDATE ON sales
+    FOR EACH ROW WHEN NEW.updated_at IS OLD.updated_at
+    BEGIN
+        UPDATE sales SET updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now') WHERE id = NEW.id;
+    END;
@@ -0,0 +1,29 @@
+DROP TRIGGER sales_updated_at;
+ALTER TABLE sales RENAME TO sales_old;
+
+CREATE TABLE sales (
+    id UUID NOT NULL PRIMARY KEY,
+    product_id UUID NOT NULL,
+    seller_id UUID NOT NULL,
+    amount INTEGER NOT NULL CONSTRAINT sales_amount_non_negative CHECK (amount >= 0),
+    total_price INTEGER NOT NULL,
+    currency TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    deleted_at TEXT,
+    version INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY (product_id) REFERENCES products(id),
+    FOREIGN KEY (seller_id) REFERENCES sellers(id)
+);
+
+INSERT INTO sales SELECT id, product_id, seller_id, amount, total_price, currency, created_at, updated_at, deleted_at, version FROM sales_old;
+DROP TABLE sales_old;
+
+CREATE TRIGGER sales_updated_at AFTER UPDATE ON sales
+    FOR EACH ROW WHEN NEW.updated_at IS OLD.updated_at
+    BEGIN
+        UPDATE sales SET updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now') WHERE id = NEW.id;
+    END;
+
+DROP INDEX sellers_id_organization;
+DROP INDEX products_id_organization;
@@ -0,0 +1,46 @@
+-- Sales reference their organization directly. Composite foreign keys on (id, organization_id)
+-- make SQLite reject a sale whose product or seller belongs to another organization. Existing
+-- sales take the organization of their product; the migration fails if a sale has no product
+-- or seller, or if they belong to different organizations.
+CREATE TEMPORARY TABLE inconsistent_sales (id UUID, CONSTRAINT sale_organization_unresolved CHECK (0));
+INSERT INTO inconsistent_sales SELECT sales.id FROM sales
+    LEFT JOIN products ON products.id = sales.product_id
+    LEFT JOIN sellers ON sellers.id = sales.seller_id
+    WHERE products.organization_id IS NOT sellers.organization_id OR products.id IS NULL;
+DROP TABLE inconsistent_sales;
+
+CREATE UNIQUE INDEX products_id_organization ON products (id, organization_id);
+CREATE UNIQUE INDEX sellers_id_organization ON sellers (id, organization_id);
+
+-- Nothing references sales, but the table is rebuilt like the others for the same reasons.
+DROP TRIGGER sales_updated_at;
+ALTER TABLE sales RENAME TO sales_old;
+
+CREATE TABLE sales (
+    id UUID NOT NULL PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    product_id UUID NOT NULL,
+    seller_id UUID NOT NULL,
+    amount INTEGER NOT NULL CONSTRAINT sales_amount_non_negative CHECK (amount >= 0),
+    total_price INTEGER NOT NULL,
+    currency TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    deleted_at TEXT,
+    version INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY (organization_id) REFERENCES organizations(id),
+    FOREIGN KEY (product_id, organization_id) REFERENCES products(id, organization_id),
+    FOREIGN KEY (seller_id, organization_id) REFERENCES sellers(id, organization_id)
+);
+
+INSERT INTO sales (id, organization_id, product_id, seller_id, amount, total_price, currency, created_at, updated_at, deleted_at, version)
+    SELECT sales_old.id, products.organization_id, product_id, seller_id, sales_old.amount, total_price, sales_old.currency,
+        sales_old.created_at, sales_old.updated_at, sales_old.deleted_at, sales_old.version
+    FROM sales_old JOIN products ON products.id = sales_old.product_id;
+DROP TABLE sales_old;
+
+CREATE TRIGGER sales_updated_at AFTER UPDATE ON sales
+    FOR EACH ROW WHEN NEW.updated_at IS OLD.updated_at
+    BEGIN
+        UPDATE sales SET updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now') WHERE id = NEW.id;
+    END;
@@ -0,0 +1,9 @@
+DROP INDEX audit_events_actor_created_at;
+DROP INDEX audit_events_organization_created_at;
+DROP INDEX sales_seller_created_at;
+DROP INDEX sales_product_created_at;
+DROP INDEX sales_organization_created_at;
+DROP INDEX products_organization_created_at;
+DROP INDEX sellers_organization_email;
+DROP INDEX admins_organization_email;
+DROP INDEX organizations_active_name;
@@ -0,0 +1,95 @@
+-- Emails become globally unique again. The migration fails if an email is used in more than
+-- one organization, which has to be resolved by hand first.
+CREATE TEMPORARY TABLE shared_emails (email TEXT, CONSTRAINT email_used_in_several_organizations CHECK (0));
+INSERT INTO shared_emails SELECT email FROM admins GROUP BY email HAVING count(*) > 1;
+INSERT INTO shared_emails SELECT email FROM sellers GROUP BY email HAVING count(*) > 1;
+DROP TABLE shared_emails;
+
+DROP TRIGGER sales_updated_at;
+DROP TRIGGER sellers_updated_at;
+DROP TRIGGER admins_updated_at;
+DROP INDEX sales_organization_created_at;
+DROP INDEX sales_product_created_at;
+DROP INDEX sales_seller_created_at;
+DROP INDEX sellers_organization_email;
+DROP INDEX sellers_id_organization;
+DROP INDEX admins_organization_email;
+DROP INDEX admins_one_default_per_organization;
+ALTER TABLE sales RENAME TO sales_old;
+ALTER TABLE sellers RENAME TO sellers_old;
+ALTER TABLE admins RENAME TO admins_old;
+
+CREATE TABLE admins (
+    id UUID NOT NULL PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    email TEXT NOT NULL UNIQUE,
+    password_hash TEXT NOT NULL,
+    is_default BOOLEAN NOT NULL DEFAULT false,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    deleted_at TEXT,
+    version INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY (organization_id) REFERENCES organizations(id)
+);
+
+CREATE TABLE sellers (
+    id UUID NOT NULL PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    email TEXT NOT NULL UNIQUE,
+    password_hash TEXT NOT NULL,
+    active BOOLEAN NOT NULL DEFAULT true,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    deleted_at TEXT,
+    version INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY (organization_id) REFERENCES organizations(id)
+);
+
+CREATE TABLE sales (
+    id UUID NOT NULL PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    product_id UUID NOT NULL,
+    seller_id UUID NOT NULL,
+    amount INTEGER NOT NULL CONSTRAINT sales_amount_non_negative CHECK (amount >= 0),
+    total_price INTEGER NOT NULL,
+    currency TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    deleted_at TEXT,
+    version INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY (organization_id) REFERENCES organizations(id),
+    FOREIGN KEY (product_id, organization_id) REFERENCES products(id, organization_id),
+    FOREIGN KEY (seller_id, organization_id) REFERENCES sellers(id, organization_id)
+);
+
+CREATE INDEX admins_organization_email ON admins (organization_id, email);
+CREATE UNIQUE INDEX admins_one_default_per_organization ON admins (organization_id) WHERE is_default;
+CREATE INDEX sellers_organization_email ON sellers (organization_id, email);
+CREATE UNIQUE INDEX sellers_id_organization ON sellers (id, organization_id);
+
+INSERT INTO admins SELECT id, organization_id, email, password_hash, is_default, created_at, updated_at, deleted_at, version FROM admins_old;
+INSERT INTO sellers SELECT id, organization_id, email, password_hash, active, created_at, updated_at, deleted_at, version FROM sellers_old;
+INSERT INTO sales SELECT id, organization_id, product_id, seller_id, amount, total_price, currency, created_at, updated_at, deleted_at, version FROM sales_old;
+DROP TABLE sales_old;
+DROP TABLE sellers_old;
+DROP TABLE admins_old;
+
+CREATE INDEX sales_organization_created_at ON sales (organization_id, created_at);
+CREATE INDEX sales_product_created_at ON sales (product_id, created_at);
+CREATE INDEX sales_seller_created_at ON sales (seller_id, created_at);
+
+CREATE TRIGGER admins_updated_at AFTER UPDATE ON admins
+    FOR EACH ROW WHEN NEW.updated_at IS OLD.updated_at
+    BEGIN
+        UPDATE admins SET updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now') WHERE id = NEW.id;
+    END;
+CREATE TRIGGER sellers_updated_at AFTER UPDATE ON sellers
+    FOR EACH ROW WHEN NEW.updated_at IS OLD.updated_at
+    BEGIN
+        UPDATE sellers SET updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now') WHERE id = NEW.id;
+    END;
+CREATE TRIGGER sales_updated_at AFTER UPDATE ON sales
+    FOR EACH ROW WHEN NEW.updated_at IS OLD.updated_at
+    BEGIN
+        UPDATE sales SET updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now') WHERE id = NEW.id;
+    END;
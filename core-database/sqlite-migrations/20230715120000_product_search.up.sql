@@ -0,0 +1,28 @@
+-- Full-text index over product names and descriptions, kept in sync by triggers. The index
+-- holds its own copy of the text under the product id rather than using products as external
+-- content: products has no INTEGER PRIMARY KEY, so its rowids are not stable across VACUUM
+-- or the table rebuilds of earlier migrations.
+CREATE VIRTUAL TABLE products_search USING fts5(
+    product_id UNINDEXED,
+    name,
+    description,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+INSERT INTO products_search (product_id, name, description) SELECT id, name, description FROM products;
+
+CREATE TRIGGER products_search_insert AFTER INSERT ON products
+    FOR EACH ROW
+    BEGIN
+        INSERT INTO products_search (product_id, name, description) VALUES (NEW.id, NEW.name, NEW.description);
+    END;
+CREATE TRIGGER products_search_update AFTER UPDATE OF name, description ON products
+    FOR EACH ROW
+    BEGIN
+        UPDATE products_search SET name = NEW.name, description = NEW.description WHERE product_id = NEW.id;
+    END;
+CREATE TRIGGER products_search_delete AFTER DELETE ON products
+    FOR EACH ROW
+    BEGIN
+        DELETE FROM products_search WHERE product_id = OLD.id;
+    END;
@@ -0,0 +1,145 @@
+-- Product catalog: optional SKUs and barcodes, unique within an organization, a category tree
+-- and tags. Composite foreign keys on (id, organization_id) keep a product's category, a
+-- category's parent and a product's tags within the product's organization.
+CREATE TABLE categories (
+    id UUID NOT NULL PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    parent_id UUID,
+    name TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    version INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY (organization_id) REFERENCES organizations(id),
+    FOREIGN KEY (parent_id, organization_id) REFERENCES categories(id, organization_id)
+);
+
+CREATE TABLE tags (
+    id UUID NOT NULL PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    name TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    version INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY (organization_id) REFERENCES organizations(id)
+);
+
+CREATE UNIQUE INDEX categories_id_organization ON categories (id, organization_id);
+CREATE INDEX categories_organization_name ON categories (organization_id, name);
+CREATE INDEX categories_parent_name ON categories (parent_id, name);
+CREATE UNIQUE INDEX tags_id_organization ON tags (id, organization_id);
+CREATE UNIQUE INDEX tags_organization_name ON tags (organization_id, name);
+
+-- The category reference needs a rebuild of products; sales references products, so both
+-- tables are renamed first as in 20230710120000_timestamps. The search triggers are dropped
+-- too, the search index itself is keyed by product id and stays as it is.
+DROP TRIGGER sales_updated_at;
+DROP TRIGGER products_updated_at;
+DROP TRIGGER products_search_insert;
+DROP TRIGGER products_search_update;
+DROP TRIGGER products_search_delete;
+DROP INDEX sales_organization_created_at;
+DROP INDEX sales_product_created_at;
+DROP INDEX sales_seller_created_at;
+DROP INDEX products_id_organization;
+DROP INDEX products_organization_created_at;
+ALTER TABLE sales RENAME TO sales_old;
+ALTER TABLE products RENAME TO products_old;
+
+CREATE TABLE products (
+    id UUID NOT NULL PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    category_id UUID,
+    sku TEXT,
+    barcode TEXT,
+    name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    amount INTEGER NOT NULL CONSTRAINT products_amount_non_negative CHECK (amount >= 0),
+    price INTEGER NOT NULL,
+    currency TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    deleted_at TEXT,
+    version INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY (organization_id) REFERENCES organizations(id),
+    FOREIGN KEY (category_id, organization_id) REFERENCES categories(id, organization_id)
+);
+
+CREATE TABLE sales (
+    id UUID NOT NULL PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    product_id UUID NOT NULL,
+    seller_id UUID NOT NULL,
+    amount INTEGER NOT NULL CONSTRAINT sales_amount_non_negative CHECK (amount >= 0),
+    total_price INTEGER NOT NULL,
+    currency TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f000Z', 'now')),
+    deleted_at TEXT,
+    version INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY (organization_id) REFERENCES organizations(id),
+    FOREIGN KEY (product_id, organization_id) REFERENCES products(id, organization_id),
+    FOREIGN KEY (seller_id, organization_id) REFERENCES sellers(id, organization_id)
+);
+
+CREATE UNIQUE INDEX products_id_organization ON products (id, organization_id);
+CREATE UNIQUE INDEX products_organization_sku ON products (organization_id, sku);
+CREATE UNIQUE INDEX products_organization_barcode ON products (organization_id, barcode);
+
+INSERT INTO products (id, organization_id, name, description, amount, price, currency, created_at, updated_at, deleted_at, version)
+    SELECT id, organization_id, name, description, amount, price, currency, created_at, updated_at, deleted_at, version FROM products_old;
+INSERT INTO sales SELECT id, organization_id, product_id, seller_id, amount, total_price, currency, created_at, updated_at, deleted_at, version FROM sales_old;
+DROP TABLE sales_old;
+DROP TABLE products_old;
+
+-- Tags follow the product's organization; removing a product or a tag removes its taggings.
+CREATE TABLE product_tags (
+    product_id UUID NOT NULL,
+    tag_id UUID NOT NULL,
+    organization_id UUID NOT NULL,
+    PRIMARY KEY (product_id, tag_id),
+    FOREIGN KEY (product_id, organization_id) REFERENCES products(id, organization_id) ON DELETE CASCADE,
+    FOREIGN KEY (tag_id, organization_id) REFERENCES tags(id, organization_id) ON DELETE CASCADE
+);
+
+CREATE INDEX product_tags_tag ON product_tags (tag_id, product_id);
+CREATE INDEX products_organization_created_at ON products (organization_id, created_at);
+CREATE INDEX products_category_created_at ON products (category_id, created_at);
+CREATE INDEX sales_organization_created_at ON sales (organization_id, created_at);
+CREATE INDEX sales_product_created_at ON sales (product_id, created_at);
+CREATE INDEX sales_seller_created_at ON sales (seller_id, created_at);
+
+CREATE TRIGGER products_updated_at AFTER UPDATE ON products
+    FOR EACH ROW WHEN NEW.updated_at IS OLD.updated_at
+    BEGIN
+        UPDATE products SET updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now') WHERE id = NEW.id;
+    END;
+CREATE TRIGGER sales_updated_at AFTER UPDATE ON sales
+    FOR EACH ROW WHEN NEW.updated_at IS OLD.updated_at
+    BEGIN
+        UPDATE sales SET updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now') WHERE id = NEW.id;
+    END;
+CREATE TRIGGER categories_updated_at AFTER UPDATE ON categories
+    FOR EACH ROW WHEN NEW.updated_at IS OLD.updated_at
+    BEGIN
+        UPDATE categories SET updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now') WHERE id = NEW.id;
+    END;
+CREATE TRIGGER tags_updated_at AFTER UPDATE ON tags
+    FOR EACH ROW WHEN NEW.updated_at IS OLD.updated_at
+    BEGIN
+        UPDATE tags SET updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now') WHERE id = NEW.id;
+    END;
+CREATE TRIGGER products_search_insert AFTER INSERT ON products
+    FOR EACH ROW
+    BEGIN
+        INSERT INTO products_search (product_id, name, description) VALUES (NEW.id, NEW.name, NEW.description);
+    END;
+CREATE TRIGGER products_search_update AFTER UPDATE OF name, description ON products
+    FOR EACH ROW
+    BEGIN
+        UPDATE products_search SET name = NEW.name, description = NEW.description WHERE product_id = NEW.id;
+    END;
+CREATE TRIGGER products_search_delete AFTER DELETE ON products
+    FOR EACH ROW
+    BEGIN
+        DELETE FROM products_search WHERE product_id = OLD.id;
+    END;
@@ -0,0 +1,20 @@
+DROP TRIGGER products_search_insert;
+DROP TRIGGER products_search_update;
+DROP TRIGGER products_search_delete;
+CREATE TRIGGER products_search_insert AFTER INSERT ON products
+    FOR EACH ROW
+    BEGIN
+        INSERT INTO products_search (product_id, name, description) VALUES (NEW.id, NEW.name, NEW.description);
+    END;
+CREATE TRIGGER products_search_update AFTER UPDATE OF name, description ON products
+    FOR EACH ROW
+    BEGIN
+        UPDATE products_search SET name = NEW.name, description = NEW.description WHERE product_id = NEW.id;
+    END;
+CREATE TRIGGER products_search_delete AFTER DELETE ON products
+    FOR EACH ROW
+    BEGIN
+        DELETE FROM products_search WHERE product_id = OLD.id;
+    END;
+
+DROP TABLE products_search_ids;
@@ -0,0 +1,30 @@
+-- FTS5 only finds rows by rowid or MATCH, so looking up the search row of a product by its
+-- UNINDEXED product_id column read the whole index on every update and delete of a product.
+-- Each product now keeps the rowid of its search row here.
+CREATE TABLE products_search_ids (
+    search_rowid INTEGER PRIMARY KEY,
+    product_id UUID NOT NULL UNIQUE
+);
+
+INSERT INTO products_search_ids (search_rowid, product_id) SELECT rowid, product_id FROM products_search;
+
+DROP TRIGGER products_search_insert;
+DROP TRIGGER products_search_update;
+DROP TRIGGER products_search_delete;
+CREATE TRIGGER products_search_insert AFTER INSERT ON products
+    FOR EACH ROW
+    BEGIN
+        INSERT INTO products_search_ids (product_id) VALUES (NEW.id);
+        INSERT INTO products_search (rowid, product_id, name, description) VALUES ((SELECT search_rowid FROM products_search_ids WHERE product_id = NEW.id), NEW.id, NEW.name, NEW.description);
+    END;
+CREATE TRIGGER products_search_update AFTER UPDATE OF name, description ON products
+    FOR EACH ROW
+    BEGIN
+        UPDATE products_search SET name = NEW.name, description = NEW.description WHERE rowid = (SELECT search_rowid FROM products_search_ids WHERE product_id = NEW.id);
+    END;
+CREATE TRIGGER products_search_delete AFTER DELETE ON products
+    FOR EACH ROW
+    BEGIN
+        DELETE FROM products_search WHERE rowid = (SELECT search_rowid FROM products_search_ids WHERE product_id = OLD.id);
+        DELETE FROM products_search_ids WHERE product_id = OLD.id;
+    END;
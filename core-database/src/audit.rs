@@ -0,0 +1,1242 @@
+use std::{fmt, marker::PhantomData, str::FromStr};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{Acquire, Database, QueryBuilder, Sqlite, Transaction};
+use uuid::Uuid;
+
+use crate::{
+    entities::{
+        admin::{
+            AdminBy, AdminDAO, AdminRepository, AdminsWhere, DefaultAdminTransfer, NewAdminDAO,
+            UpdateAdminDAO,
+        },
+        category::{
+            CategoriesWhere, CategoryBy, CategoryDAO, CategoryRepository, NewCategoryDAO,
+            UpdateCategoryDAO,
+        },
+        organization::{DeletePolicy, OrganizationBy, OrganizationDAO, OrganizationRemover},
+        product::{
+            NewProductDAO, ProductBy, ProductDAO, ProductRepository, ProductsWhere,
+            UpdateProductDAO,
+        },
+        sales::{
+            NewSalesDAO, RecordSaleDAO, SalesBy, SalesDAO, SalesRecorder, SalesRepository,
+            SalesWhere, UpdateSalesDAO,
+        },
+        seller::{
+            NewSellerDAO, SellerBy, SellerDAO, SellerRepository, SellersWhere, UpdateSellerDAO,
+        },
+        tag::{NewTagDAO, TagBy, TagDAO, TagRepository, TagsWhere, UpdateTagDAO},
+        SortOrder,
+    },
+    pagination::{push_after_sqlite, Cursor, CursorKey, Page},
+    sqlite,
+    traits::{DatabaseError, EntityRepository, SoftDeleteRepository},
+};
+
+#[cfg(feature = "postgres")]
+pub mod postgres;
+
+/// Mutation recorded by an audit event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditOperation {
+    Insert,
+    Update,
+    Delete,
+    Restore,
+    Purge,
+}
+
+impl AuditOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditOperation::Insert => "insert",
+            AuditOperation::Update => "update",
+            AuditOperation::Delete => "delete",
+            AuditOperation::Restore => "restore",
+            AuditOperation::Purge => "purge",
+        }
+    }
+}
+
+impl fmt::Display for AuditOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AuditOperation {
+    type Err = DatabaseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "insert" => Ok(AuditOperation::Insert),
+            "update" => Ok(AuditOperation::Update),
+            "delete" => Ok(AuditOperation::Delete),
+            "restore" => Ok(AuditOperation::Restore),
+            "purge" => Ok(AuditOperation::Purge),
+            other => Err(DatabaseError::DatabaseInconsistence(format!(
+                "unknown audit operation {other}"
+            ))),
+        }
+    }
+}
+
+/// Rows whose mutations can be recorded in the audit log.
+pub trait Auditable: Serialize {
+    const ENTITY_TYPE: &'static str;
+
+    fn audit_id(&self) -> Uuid;
+    fn audit_organization_id(&self) -> Option<Uuid>;
+}
+
+impl Auditable for OrganizationDAO {
+    const ENTITY_TYPE: &'static str = "organization";
+
+    fn audit_id(&self) -> Uuid {
+        self.id
+    }
+
+    fn audit_organization_id(&self) -> Option<Uuid> {
+        Some(self.id)
+    }
+}
+
+impl Auditable for AdminDAO {
+    const ENTITY_TYPE: &'static str = "admin";
+
+    fn audit_id(&self) -> Uuid {
+        self.id
+    }
+
+    fn audit_organization_id(&self) -> Option<Uuid> {
+        Some(self.organization_id)
+    }
+}
+
+impl Auditable for SellerDAO {
+    const ENTITY_TYPE: &'static str = "seller";
+
+    fn audit_id(&self) -> Uuid {
+        self.id
+    }
+
+    fn audit_organization_id(&self) -> Option<Uuid> {
+        Some(self.organization_id)
+    }
+}
+
+impl Auditable for ProductDAO {
+    const ENTITY_TYPE: &'static str = "product";
+
+    fn audit_id(&self) -> Uuid {
+        self.id
+    }
+
+    fn audit_organization_id(&self) -> Option<Uuid> {
+        Some(self.organization_id)
+    }
+}
+
+impl Auditable for SalesDAO {
+    const ENTITY_TYPE: &'static str = "sales";
+
+    fn audit_id(&self) -> Uuid {
+        self.id
+    }
+
+    fn audit_organization_id(&self) -> Option<Uuid> {
+        Some(self.organization_id)
+    }
+}
+
+impl Auditable for CategoryDAO {
+    const ENTITY_TYPE: &'static str = "category";
+
+    fn audit_id(&self) -> Uuid {
+        self.id
+    }
+
+    fn audit_organization_id(&self) -> Option<Uuid> {
+        Some(self.organization_id)
+    }
+}
+
+impl Auditable for TagDAO {
+    const ENTITY_TYPE: &'static str = "tag";
+
+    fn audit_id(&self) -> Uuid {
+        self.id
+    }
+
+    fn audit_organization_id(&self) -> Option<Uuid> {
+        Some(self.organization_id)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewAuditEventDAO {
+    pub actor_id: Option<Uuid>,
+    pub organization_id: Option<Uuid>,
+    pub entity_type: String,
+    pub entity_id: Uuid,
+    pub operation: AuditOperation,
+    /// Row before the mutation, `None` for inserts and restores.
+    pub before: Option<Value>,
+    /// Row after the mutation, `None` for deletes and purges.
+    pub after: Option<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEventDAO {
+    pub id: Uuid,
+    pub actor_id: Option<Uuid>,
+    pub organization_id: Option<Uuid>,
+    pub entity_type: String,
+    pub entity_id: Uuid,
+    pub operation: AuditOperation,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Row as stored, with the snapshots as JSON text.
+#[derive(sqlx::FromRow, Debug)]
+pub(crate) struct AuditEventRow {
+    pub id: Uuid,
+    pub actor_id: Option<Uuid>,
+    pub organization_id: Option<Uuid>,
+    pub entity_type: String,
+    pub entity_id: Uuid,
+    pub operation: String,
+    pub before_json: Option<String>,
+    pub after_json: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+fn parse_snapshot(json: Option<String>) -> Result<Option<Value>, DatabaseError> {
+    json.map(|json| serde_json::from_str(&json))
+        .transpose()
+        .map_err(|e| DatabaseError::DatabaseInconsistence(format!("invalid audit snapshot: {e}")))
+}
+
+impl TryFrom<AuditEventRow> for AuditEventDAO {
+    type Error = DatabaseError;
+
+    fn try_from(value: AuditEventRow) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: value.id,
+            actor_id: value.actor_id,
+            organization_id: value.organization_id,
+            entity_type: value.entity_type,
+            entity_id: value.entity_id,
+            operation: value.operation.parse()?,
+            before: parse_snapshot(value.before_json)?,
+            after: parse_snapshot(value.after_json)?,
+            created_at: value.created_at,
+        })
+    }
+}
+
+/// Filters used to browse the audit log, ordered by time. Filters left as `None` are not
+/// applied.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AuditEventsWhere {
+    pub entity_type: Option<String>,
+    pub entity_id: Option<Uuid>,
+    pub organization_id: Option<Uuid>,
+    pub actor_id: Option<Uuid>,
+    /// Inclusive lower bound of the event time.
+    pub created_after: Option<DateTime<Utc>>,
+    /// Exclusive upper bound of the event time.
+    pub created_before: Option<DateTime<Utc>>,
+    pub order: SortOrder,
+    pub limit: i32,
+    /// Resume after the last row of a previous page.
+    pub after: Option<Cursor>,
+}
+
+impl Default for AuditEventsWhere {
+    fn default() -> Self {
+        Self {
+            entity_type: None,
+            entity_id: None,
+            organization_id: None,
+            actor_id: None,
+            created_after: None,
+            created_before: None,
+            order: SortOrder::default(),
+            limit: 100,
+            after: None,
+        }
+    }
+}
+
+pub(crate) fn audit_cursor(order: SortOrder, event: &AuditEventDAO) -> Cursor {
+    Cursor::new(
+        "created_at",
+        order,
+        CursorKey::Timestamp(event.created_at),
+        event.id,
+    )
+}
+
+#[async_trait::async_trait]
+pub trait AuditLog<DB: Database> {
+    async fn record<'c, A>(db: A, event: NewAuditEventDAO) -> Result<AuditEventDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = DB> + Send;
+
+    async fn events<'c, A>(
+        db: A,
+        key: AuditEventsWhere,
+    ) -> Result<Page<AuditEventDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = DB> + Send;
+}
+
+#[derive(Debug)]
+pub struct AuditRepository;
+
+/// Builds the query of `events`; kept apart so the query plan tests can check it.
+pub(crate) fn events_query(
+    key: &AuditEventsWhere,
+) -> Result<QueryBuilder<'static, Sqlite>, DatabaseError> {
+    let mut query = QueryBuilder::<Sqlite>::new(
+        "SELECT id, actor_id, organization_id, entity_type, entity_id, operation, before_json, after_json, created_at FROM audit_events WHERE 1 = 1",
+    );
+    if let Some(entity_type) = &key.entity_type {
+        query
+            .push(" AND entity_type = ")
+            .push_bind(entity_type.clone());
+    }
+    if let Some(entity_id) = key.entity_id {
+        query.push(" AND entity_id = ").push_bind(entity_id);
+    }
+    if let Some(organization_id) = key.organization_id {
+        query
+            .push(" AND organization_id = ")
+            .push_bind(organization_id);
+    }
+    if let Some(actor_id) = key.actor_id {
+        query.push(" AND actor_id = ").push_bind(actor_id);
+    }
+    if let Some(created_after) = key.created_after {
+        query
+            .push(" AND created_at >= ")
+            .push_bind(sqlite::timestamp(created_after));
+    }
+    if let Some(created_before) = key.created_before {
+        query
+            .push(" AND created_at < ")
+            .push_bind(sqlite::timestamp(created_before));
+    }
+    if let Some(after) = &key.after {
+        push_after_sqlite(&mut query, "created_at", key.order, after)?;
+    }
+    query
+        .push(" ORDER BY created_at ")
+        .push(key.order.keyword())
+        .push(", id ")
+        .push(key.order.keyword())
+        .push(" LIMIT ")
+        .push_bind(i64::from(key.limit) + 1);
+    Ok(query)
+}
+
+#[async_trait::async_trait]
+impl AuditLog<Sqlite> for AuditRepository {
+    #[tracing::instrument(
+        name = "repository",
+        level = "debug",
+        skip_all,
+        fields(entity = "audit_event", operation = "record"),
+        err(Debug, level = "warn")
+    )]
+    async fn record<'c, A>(db: A, event: NewAuditEventDAO) -> Result<AuditEventDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        sqlx::query_as::<_, AuditEventRow>(
+            "INSERT INTO audit_events (id, actor_id, organization_id, entity_type, entity_id, operation, before_json, after_json) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING id, actor_id, organization_id, entity_type, entity_id, operation, before_json, after_json, created_at",
+        )
+        .bind(Uuid::new_v4())
+        .bind(event.actor_id)
+        .bind(event.organization_id)
+        .bind(event.entity_type)
+        .bind(event.entity_id)
+        .bind(event.operation.as_str())
+        .bind(event.before.map(|before| before.to_string()))
+        .bind(event.after.map(|after| after.to_string()))
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)?
+        .try_into()
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "audit_event", operation = "events", key = ?key), err(Debug, level = "warn"))]
+    async fn events<'c, A>(
+        db: A,
+        key: AuditEventsWhere,
+    ) -> Result<Page<AuditEventDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let mut query = events_query(&key)?;
+        let rows = query
+            .build_query_as::<AuditEventRow>()
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?
+            .into_iter()
+            .map(AuditEventDAO::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Page::from_rows(rows, key.limit, |event| {
+            audit_cursor(key.order, event)
+        }))
+    }
+}
+
+fn snapshot<Entity: Serialize>(entity: Option<&Entity>) -> Result<Option<Value>, DatabaseError> {
+    entity
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(|e| DatabaseError::Unknown(format!("could not serialize audit snapshot: {e}")))
+}
+
+/// Runs the mutations of the repository `R` on behalf of an actor and records each of them in
+/// the audit log, within the same transaction as the mutation itself. Password rehashes made by
+/// `CredentialsRepository::verify_password` or at migration time are not audited: the
+/// snapshots leave the hash out, so they would record no change.
+#[derive(Debug, Clone, Copy)]
+pub struct Audited<R> {
+    actor_id: Option<Uuid>,
+    repository: PhantomData<R>,
+}
+
+impl<R> Audited<R> {
+    /// `actor_id` is the admin or seller making the changes, `None` for system tasks.
+    pub fn new(actor_id: Option<Uuid>) -> Self {
+        Self {
+            actor_id,
+            repository: PhantomData,
+        }
+    }
+
+    async fn record<'c, DB, A, Entity>(
+        &self,
+        db: A,
+        operation: AuditOperation,
+        before: Option<&Entity>,
+        after: Option<&Entity>,
+    ) -> Result<(), DatabaseError>
+    where
+        DB: Database,
+        A: Acquire<'c, Database = DB> + Send,
+        Entity: Auditable,
+        AuditRepository: AuditLog<DB>,
+    {
+        let Some(entity) = after.or(before) else {
+            return Ok(());
+        };
+        AuditRepository::record(
+            db,
+            NewAuditEventDAO {
+                actor_id: self.actor_id,
+                organization_id: entity.audit_organization_id(),
+                entity_type: Entity::ENTITY_TYPE.to_string(),
+                entity_id: entity.audit_id(),
+                operation,
+                before: snapshot(before)?,
+                after: snapshot(after)?,
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn insert<'c, DB, A, Entity, CreateInput, UpdateInput, QueryOne, QueryMany>(
+        &self,
+        db: A,
+        input: CreateInput,
+    ) -> Result<Entity, DatabaseError>
+    where
+        DB: Database,
+        A: Acquire<'c, Database = DB> + Send,
+        for<'t> &'t mut Transaction<'c, DB>: Acquire<'t, Database = DB> + Send,
+        R: EntityRepository<DB, Entity, CreateInput, UpdateInput, QueryOne, QueryMany>,
+        AuditRepository: AuditLog<DB>,
+        Entity: Auditable + Send,
+        CreateInput: Send,
+        UpdateInput: Send,
+        QueryOne: Send + Sync,
+        QueryMany: Send + Sync,
+    {
+        let mut tx = db.begin().await.map_err(DatabaseError::from)?;
+        let entity = R::insert(&mut tx, input).await?;
+        self.record(&mut tx, AuditOperation::Insert, None, Some(&entity))
+            .await?;
+        tx.commit().await.map_err(DatabaseError::from)?;
+        Ok(entity)
+    }
+
+    pub async fn update<'c, DB, A, Entity, CreateInput, UpdateInput, QueryOne, QueryMany>(
+        &self,
+        db: A,
+        key: QueryOne,
+        input: UpdateInput,
+    ) -> Result<Entity, DatabaseError>
+    where
+        DB: Database,
+        A: Acquire<'c, Database = DB> + Send,
+        for<'t> &'t mut Transaction<'c, DB>: Acquire<'t, Database = DB> + Send,
+        R: EntityRepository<DB, Entity, CreateInput, UpdateInput, QueryOne, QueryMany>,
+        AuditRepository: AuditLog<DB>,
+        Entity: Auditable + Send,
+        CreateInput: Send,
+        UpdateInput: Send,
+        QueryOne: Clone + Send + Sync,
+        QueryMany: Send + Sync,
+    {
+        let mut tx = db.begin().await.map_err(DatabaseError::from)?;
+        let before = R::try_get(&mut tx, key.clone()).await?;
+        let entity = R::update(&mut tx, key, input).await?;
+        self.record(
+            &mut tx,
+            AuditOperation::Update,
+            before.as_ref(),
+            Some(&entity),
+        )
+        .await?;
+        tx.commit().await.map_err(DatabaseError::from)?;
+        Ok(entity)
+    }
+
+    pub async fn delete<'c, DB, A, Entity, CreateInput, UpdateInput, QueryOne, QueryMany>(
+        &self,
+        db: A,
+        key: QueryOne,
+    ) -> Result<Entity, DatabaseError>
+    where
+        DB: Database,
+        A: Acquire<'c, Database = DB> + Send,
+        for<'t> &'t mut Transaction<'c, DB>: Acquire<'t, Database = DB> + Send,
+        R: EntityRepository<DB, Entity, CreateInput, UpdateInput, QueryOne, QueryMany>,
+        AuditRepository: AuditLog<DB>,
+        Entity: Auditable + Send,
+        CreateInput: Send,
+        UpdateInput: Send,
+        QueryOne: Send + Sync,
+        QueryMany: Send + Sync,
+    {
+        let mut tx = db.begin().await.map_err(DatabaseError::from)?;
+        let entity = R::delete(&mut tx, key).await?;
+        self.record(&mut tx, AuditOperation::Delete, Some(&entity), None)
+            .await?;
+        tx.commit().await.map_err(DatabaseError::from)?;
+        Ok(entity)
+    }
+
+    pub async fn restore<'c, DB, A, Entity, QueryOne>(
+        &self,
+        db: A,
+        key: QueryOne,
+    ) -> Result<Entity, DatabaseError>
+    where
+        DB: Database,
+        A: Acquire<'c, Database = DB> + Send,
+        for<'t> &'t mut Transaction<'c, DB>: Acquire<'t, Database = DB> + Send,
+        R: SoftDeleteRepository<DB, Entity, QueryOne>,
+        AuditRepository: AuditLog<DB>,
+        Entity: Auditable + Send,
+        QueryOne: Send,
+    {
+        let mut tx = db.begin().await.map_err(DatabaseError::from)?;
+        let entity = R::restore(&mut tx, key).await?;
+        self.record(&mut tx, AuditOperation::Restore, None, Some(&entity))
+            .await?;
+        tx.commit().await.map_err(DatabaseError::from)?;
+        Ok(entity)
+    }
+
+    pub async fn purge<'c, DB, A, Entity, QueryOne>(
+        &self,
+        db: A,
+        key: QueryOne,
+    ) -> Result<Entity, DatabaseError>
+    where
+        DB: Database,
+        A: Acquire<'c, Database = DB> + Send,
+        for<'t> &'t mut Transaction<'c, DB>: Acquire<'t, Database = DB> + Send,
+        R: SoftDeleteRepository<DB, Entity, QueryOne>,
+        AuditRepository: AuditLog<DB>,
+        Entity: Auditable + Send,
+        QueryOne: Send,
+    {
+        let mut tx = db.begin().await.map_err(DatabaseError::from)?;
+        let entity = R::purge(&mut tx, key).await?;
+        self.record(&mut tx, AuditOperation::Purge, Some(&entity), None)
+            .await?;
+        tx.commit().await.map_err(DatabaseError::from)?;
+        Ok(entity)
+    }
+
+    /// Records the sale along with the stock it takes out of the product.
+    pub async fn record_sale<'c, DB, A>(
+        &self,
+        db: A,
+        input: RecordSaleDAO,
+    ) -> Result<SalesDAO, DatabaseError>
+    where
+        DB: Database,
+        A: Acquire<'c, Database = DB> + Send,
+        for<'t> &'t mut Transaction<'c, DB>: Acquire<'t, Database = DB> + Send,
+        R: SalesRecorder<DB>,
+        ProductRepository: EntityRepository<
+            DB,
+            ProductDAO,
+            NewProductDAO,
+            UpdateProductDAO,
+            ProductBy,
+            ProductsWhere,
+        >,
+        AuditRepository: AuditLog<DB>,
+    {
+        let mut tx = db.begin().await.map_err(DatabaseError::from)?;
+        let product_id = input.product_id;
+        let before = ProductRepository::try_get(&mut tx, ProductBy::Id(product_id)).await?;
+        let sale = R::record_sale(&mut tx, input).await?;
+        let product = ProductRepository::get(&mut tx, ProductBy::Id(product_id)).await?;
+        self.record(&mut tx, AuditOperation::Insert, None, Some(&sale))
+            .await?;
+        self.record(
+            &mut tx,
+            AuditOperation::Update,
+            before.as_ref(),
+            Some(&product),
+        )
+        .await?;
+        tx.commit().await.map_err(DatabaseError::from)?;
+        Ok(sale)
+    }
+
+    /// Records the promotion of `to` and the demotion of the previous default admin.
+    pub async fn transfer_default<'c, DB, A>(
+        &self,
+        db: A,
+        to: AdminBy,
+    ) -> Result<AdminDAO, DatabaseError>
+    where
+        DB: Database,
+        A: Acquire<'c, Database = DB> + Send,
+        for<'t> &'t mut Transaction<'c, DB>: Acquire<'t, Database = DB> + Send,
+        R: DefaultAdminTransfer<DB>
+            + EntityRepository<DB, AdminDAO, NewAdminDAO, UpdateAdminDAO, AdminBy, AdminsWhere>,
+        AuditRepository: AuditLog<DB>,
+    {
+        let mut tx = db.begin().await.map_err(DatabaseError::from)?;
+        let before = R::get(&mut tx, to).await?;
+        let previous = R::get_all(
+            &mut tx,
+            AdminsWhere {
+                organization_id: Some(before.organization_id),
+                is_default: Some(true),
+                ..Default::default()
+            },
+        )
+        .await?
+        .items;
+        let admin = R::transfer_default(&mut tx, AdminBy::Id(before.id)).await?;
+        for previous in previous.iter().filter(|previous| previous.id != admin.id) {
+            let demoted = R::get(&mut tx, AdminBy::Id(previous.id)).await?;
+            self.record(
+                &mut tx,
+                AuditOperation::Update,
+                Some(previous),
+                Some(&demoted),
+            )
+            .await?;
+        }
+        self.record(&mut tx, AuditOperation::Update, Some(&before), Some(&admin))
+            .await?;
+        tx.commit().await.map_err(DatabaseError::from)?;
+        Ok(admin)
+    }
+
+    /// Records the purge of the organization and, with [`DeletePolicy::Cascade`], of every row
+    /// deleted along with it, soft-deleted ones included.
+    pub async fn delete_with<'c, DB, A>(
+        &self,
+        db: A,
+        key: OrganizationBy,
+        policy: DeletePolicy,
+    ) -> Result<OrganizationDAO, DatabaseError>
+    where
+        DB: Database,
+        A: Acquire<'c, Database = DB> + Send,
+        for<'t> &'t mut Transaction<'c, DB>: Acquire<'t, Database = DB> + Send,
+        R: OrganizationRemover<DB>,
+        SalesRepository:
+            EntityRepository<DB, SalesDAO, NewSalesDAO, UpdateSalesDAO, SalesBy, SalesWhere>,
+        ProductRepository: EntityRepository<
+            DB,
+            ProductDAO,
+            NewProductDAO,
+            UpdateProductDAO,
+            ProductBy,
+            ProductsWhere,
+        >,
+        CategoryRepository: EntityRepository<
+            DB,
+            CategoryDAO,
+            NewCategoryDAO,
+            UpdateCategoryDAO,
+            CategoryBy,
+            CategoriesWhere,
+        >,
+        TagRepository: EntityRepository<DB, TagDAO, NewTagDAO, UpdateTagDAO, TagBy, TagsWhere>,
+        SellerRepository:
+            EntityRepository<DB, SellerDAO, NewSellerDAO, UpdateSellerDAO, SellerBy, SellersWhere>,
+        AdminRepository:
+            EntityRepository<DB, AdminDAO, NewAdminDAO, UpdateAdminDAO, AdminBy, AdminsWhere>,
+        AuditRepository: AuditLog<DB>,
+    {
+        let mut tx = db.begin().await.map_err(DatabaseError::from)?;
+        let organization = R::get_removable(&mut tx, key).await?;
+        let id = Some(organization.id);
+        if policy == DeletePolicy::Cascade {
+            // Listed before the deletion, recorded once it went through.
+            let mut sales = Vec::new();
+            let mut products = Vec::new();
+            let mut sellers = Vec::new();
+            let mut admins = Vec::new();
+            for deleted in [false, true] {
+                sales.extend(
+                    every::<SalesRepository, _, _, _, _, _, _>(&mut tx, |after| SalesWhere {
+                        organization_id: id,
+                        deleted,
+                        after,
+                        ..Default::default()
+                    })
+                    .await?,
+                );
+                products.extend(
+                    every::<ProductRepository, _, _, _, _, _, _>(&mut tx, |after| ProductsWhere {
+                        organization_id: id,
+                        deleted,
+                        after,
+                        ..Default::default()
+                    })
+                    .await?,
+                );
+                sellers.extend(
+                    every::<SellerRepository, _, _, _, _, _, _>(&mut tx, |after| SellersWhere {
+                        organization_id: id,
+                        deleted,
+                        after,
+                        ..Default::default()
+                    })
+                    .await?,
+                );
+                admins.extend(
+                    every::<AdminRepository, _, _, _, _, _, _>(&mut tx, |after| AdminsWhere {
+                        organization_id: id,
+                        deleted,
+                        after,
+                        ..Default::default()
+                    })
+                    .await?,
+                );
+            }
+            let categories =
+                every::<CategoryRepository, _, _, _, _, _, _>(&mut tx, |after| CategoriesWhere {
+                    organization_id: id,
+                    after,
+                    ..Default::default()
+                })
+                .await?;
+            let tags = every::<TagRepository, _, _, _, _, _, _>(&mut tx, |after| TagsWhere {
+                organization_id: id,
+                after,
+                ..Default::default()
+            })
+            .await?;
+
+            R::delete_with(&mut tx, OrganizationBy::Id(organization.id), policy).await?;
+            for sale in &sales {
+                self.record(&mut tx, AuditOperation::Purge, Some(sale), None)
+                    .await?;
+            }
+            for product in &products {
+                self.record(&mut tx, AuditOperation::Purge, Some(product), None)
+                    .await?;
+            }
+            for category in &categories {
+                self.record(&mut tx, AuditOperation::Purge, Some(category), None)
+                    .await?;
+            }
+            for tag in &tags {
+                self.record(&mut tx, AuditOperation::Purge, Some(tag), None)
+                    .await?;
+            }
+            for seller in &sellers {
+                self.record(&mut tx, AuditOperation::Purge, Some(seller), None)
+                    .await?;
+            }
+            for admin in &admins {
+                self.record(&mut tx, AuditOperation::Purge, Some(admin), None)
+                    .await?;
+            }
+        } else {
+            R::delete_with(&mut tx, OrganizationBy::Id(organization.id), policy).await?;
+        }
+        self.record(&mut tx, AuditOperation::Purge, Some(&organization), None)
+            .await?;
+        tx.commit().await.map_err(DatabaseError::from)?;
+        Ok(organization)
+    }
+}
+
+/// Reads every page of the rows matched by the filter `key` builds for a cursor.
+async fn every<'c, R, DB, Entity, CreateInput, UpdateInput, QueryOne, QueryMany>(
+    tx: &mut Transaction<'c, DB>,
+    key: impl Fn(Option<Cursor>) -> QueryMany,
+) -> Result<Vec<Entity>, DatabaseError>
+where
+    DB: Database,
+    for<'t> &'t mut Transaction<'c, DB>: Acquire<'t, Database = DB> + Send,
+    R: EntityRepository<DB, Entity, CreateInput, UpdateInput, QueryOne, QueryMany>,
+    Entity: Send,
+    CreateInput: Send,
+    UpdateInput: Send,
+    QueryOne: Send + Sync,
+    QueryMany: Send + Sync,
+{
+    let mut rows = Vec::new();
+    let mut after = None;
+    loop {
+        let page = R::get_all(&mut *tx, key(after)).await?;
+        rows.extend(page.items);
+        match page.next_cursor {
+            Some(cursor) => after = Some(cursor),
+            None => return Ok(rows),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        entities::organization::{
+            NewOrganizationDAO, OrganizationRepository, OrganizationsWhere, UpdateOrganizationDAO,
+        },
+        money::{Currency, Money},
+        sqlite::DatabaseRepository,
+    };
+    use sqlx::Pool;
+
+    async fn audit_trail<DB: Database>(db: &Pool<DB>)
+    where
+        for<'c, 't> &'t mut Transaction<'c, DB>: Acquire<'t, Database = DB> + Send,
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+        ProductRepository: EntityRepository<
+                DB,
+                ProductDAO,
+                NewProductDAO,
+                UpdateProductDAO,
+                ProductBy,
+                ProductsWhere,
+            > + SoftDeleteRepository<DB, ProductDAO, ProductBy>,
+        AuditRepository: AuditLog<DB>,
+    {
+        let actor = Uuid::new_v4();
+        let organization = Audited::<OrganizationRepository>::new(None)
+            .insert(
+                db,
+                NewOrganizationDAO {
+                    name: "audited".to_string(),
+                    reporting_currency: Currency::USD,
+                },
+            )
+            .await
+            .expect("Could not create organization");
+
+        let products = Audited::<ProductRepository>::new(Some(actor));
+        let product = products
+            .insert(
+                db,
+                NewProductDAO {
+                    organization_id: organization.id,
+                    category_id: None,
+                    sku: None,
+                    barcode: None,
+                    name: "Coffee".to_string(),
+                    description: "Beans".to_string(),
+                    amount: 10,
+                    price: Money::new(1234567890123456789, Currency::USD),
+                },
+            )
+            .await
+            .expect("Could not create product");
+        products
+            .update(
+                db,
+                ProductBy::Id(product.id),
+                UpdateProductDAO {
+                    category_id: None,
+                    sku: None,
+                    barcode: None,
+                    name: "Espresso".to_string(),
+                    description: "Beans".to_string(),
+                    amount: 8,
+                    price: Money::new(3, Currency::USD),
+                    version: product.version,
+                },
+            )
+            .await
+            .expect("Could not update product");
+        products
+            .delete(db, ProductBy::Id(product.id))
+            .await
+            .expect("Could not delete product");
+        products
+            .restore(db, ProductBy::Id(product.id))
+            .await
+            .expect("Could not restore product");
+        products
+            .purge(db, ProductBy::Id(product.id))
+            .await
+            .expect("Could not purge product");
+
+        // A failed mutation leaves no event behind
+        let missing = products.delete(db, ProductBy::Id(product.id)).await;
+        assert!(matches!(missing, Err(DatabaseError::NotFound(_))));
+
+        let trail = AuditRepository::events(
+            db,
+            AuditEventsWhere {
+                entity_type: Some("product".to_string()),
+                entity_id: Some(product.id),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list audit events");
+        assert_eq!(trail.items.len(), 5);
+        assert!(trail.next_cursor.is_none());
+        for event in &trail.items {
+            assert_eq!(event.actor_id, Some(actor));
+            assert_eq!(event.organization_id, Some(organization.id));
+        }
+
+        let event = |operation: AuditOperation| {
+            trail
+                .items
+                .iter()
+                .find(|event| event.operation == operation)
+                .expect("Missing audit event")
+        };
+        let inserted = event(AuditOperation::Insert);
+        assert_eq!(inserted.before, None);
+        let after = inserted.after.as_ref().expect("Missing snapshot");
+        assert_eq!(after["name"], "Coffee");
+        assert_eq!(after["price"]["minor_units"], 1234567890123456789i64);
+        assert_eq!(after["price"]["currency"], "USD");
+        let updated = event(AuditOperation::Update);
+        assert_eq!(updated.before.as_ref().unwrap()["amount"], 10);
+        assert_eq!(updated.after.as_ref().unwrap()["amount"], 8);
+        assert!(event(AuditOperation::Delete).after.is_none());
+        assert!(event(AuditOperation::Restore).before.is_none());
+        let purged = event(AuditOperation::Purge);
+        assert_eq!(purged.before.as_ref().unwrap()["name"], "Espresso");
+
+        let by_organization = AuditRepository::events(
+            db,
+            AuditEventsWhere {
+                organization_id: Some(organization.id),
+                limit: 4,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list audit events");
+        assert_eq!(by_organization.items.len(), 4);
+        let rest = AuditRepository::events(
+            db,
+            AuditEventsWhere {
+                organization_id: Some(organization.id),
+                limit: 4,
+                after: by_organization.next_cursor,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list audit events");
+        assert_eq!(rest.items.len(), 2);
+        assert!(by_organization
+            .items
+            .iter()
+            .chain(&rest.items)
+            .any(|event| event.entity_type == "organization" && event.actor_id.is_none()));
+
+        let future = AuditRepository::events(
+            db,
+            AuditEventsWhere {
+                created_after: Some(Utc::now() + chrono::Duration::days(1)),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list audit events");
+        assert!(future.items.is_empty());
+        let past = AuditRepository::events(
+            db,
+            AuditEventsWhere {
+                created_before: Some(Utc::now() + chrono::Duration::days(1)),
+                actor_id: Some(actor),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list audit events");
+        assert_eq!(past.items.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn sqlite_audit_trail() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        audit_trail(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_audit_trail() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            audit_trail(&db.connection).await;
+        }
+    }
+
+    async fn sales_and_cascades<DB: Database>(db: &Pool<DB>)
+    where
+        for<'c, 't> &'t mut Transaction<'c, DB>: Acquire<'t, Database = DB> + Send,
+        OrganizationRepository: EntityRepository<
+                DB,
+                OrganizationDAO,
+                NewOrganizationDAO,
+                UpdateOrganizationDAO,
+                OrganizationBy,
+                OrganizationsWhere,
+            > + OrganizationRemover<DB>,
+        AdminRepository: EntityRepository<DB, AdminDAO, NewAdminDAO, UpdateAdminDAO, AdminBy, AdminsWhere>
+            + DefaultAdminTransfer<DB>,
+        SellerRepository:
+            EntityRepository<DB, SellerDAO, NewSellerDAO, UpdateSellerDAO, SellerBy, SellersWhere>,
+        ProductRepository: EntityRepository<
+            DB,
+            ProductDAO,
+            NewProductDAO,
+            UpdateProductDAO,
+            ProductBy,
+            ProductsWhere,
+        >,
+        SalesRepository: EntityRepository<DB, SalesDAO, NewSalesDAO, UpdateSalesDAO, SalesBy, SalesWhere>
+            + SalesRecorder<DB>,
+        CategoryRepository: EntityRepository<
+            DB,
+            CategoryDAO,
+            NewCategoryDAO,
+            UpdateCategoryDAO,
+            CategoryBy,
+            CategoriesWhere,
+        >,
+        TagRepository: EntityRepository<DB, TagDAO, NewTagDAO, UpdateTagDAO, TagBy, TagsWhere>,
+        AuditRepository: AuditLog<DB>,
+    {
+        let organization = OrganizationRepository::insert(
+            db,
+            NewOrganizationDAO {
+                name: "cascaded".to_string(),
+                reporting_currency: Currency::USD,
+            },
+        )
+        .await
+        .expect("Could not create organization");
+        let new_admin = |email: &str, is_default: bool| NewAdminDAO {
+            organization_id: organization.id,
+            email: email.to_string(),
+            password: "secret".to_string(),
+            is_default,
+        };
+        let owner = AdminRepository::insert(db, new_admin("owner@gmail.com", true))
+            .await
+            .expect("Could not create admin");
+        let helper = AdminRepository::insert(db, new_admin("helper@gmail.com", false))
+            .await
+            .expect("Could not create admin");
+        let seller = SellerRepository::insert(
+            db,
+            NewSellerDAO {
+                organization_id: organization.id,
+                email: "seller@gmail.com".to_string(),
+                password: "secret".to_string(),
+            },
+        )
+        .await
+        .expect("Could not create seller");
+        let product = ProductRepository::insert(
+            db,
+            NewProductDAO {
+                organization_id: organization.id,
+                category_id: None,
+                sku: None,
+                barcode: None,
+                name: "Coffee".to_string(),
+                description: "Beans".to_string(),
+                amount: 10,
+                price: Money::new(5, Currency::USD),
+            },
+        )
+        .await
+        .expect("Could not create product");
+        let tag = TagRepository::insert(
+            db,
+            NewTagDAO {
+                organization_id: organization.id,
+                name: "hot".to_string(),
+            },
+        )
+        .await
+        .expect("Could not create tag");
+
+        let sale = Audited::<SalesRepository>::new(Some(seller.id))
+            .record_sale(
+                db,
+                RecordSaleDAO {
+                    product_id: product.id,
+                    seller_id: seller.id,
+                    amount: 3,
+                },
+            )
+            .await
+            .expect("Could not record sale");
+        let sold = AuditRepository::events(
+            db,
+            AuditEventsWhere {
+                actor_id: Some(seller.id),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list audit events");
+        assert_eq!(sold.items.len(), 2);
+        let inserted = sold
+            .items
+            .iter()
+            .find(|event| event.entity_type == "sales")
+            .expect("Missing sale event");
+        assert_eq!(inserted.operation, AuditOperation::Insert);
+        assert_eq!(inserted.entity_id, sale.id);
+        let restocked = sold
+            .items
+            .iter()
+            .find(|event| event.entity_type == "product")
+            .expect("Missing product event");
+        assert_eq!(restocked.operation, AuditOperation::Update);
+        assert_eq!(restocked.before.as_ref().unwrap()["amount"], 10);
+        assert_eq!(restocked.after.as_ref().unwrap()["amount"], 7);
+
+        Audited::<AdminRepository>::new(Some(owner.id))
+            .transfer_default(db, AdminBy::Id(helper.id))
+            .await
+            .expect("Could not transfer default admin");
+        let transferred = AuditRepository::events(
+            db,
+            AuditEventsWhere {
+                actor_id: Some(owner.id),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list audit events");
+        assert_eq!(transferred.items.len(), 2);
+        for event in &transferred.items {
+            let promoted = event.entity_id == helper.id;
+            assert_eq!(event.before.as_ref().unwrap()["is_default"], !promoted);
+            assert_eq!(event.after.as_ref().unwrap()["is_default"], promoted);
+        }
+
+        // Soft-deleted rows go away with the organization too
+        SellerRepository::delete(db, SellerBy::Id(seller.id))
+            .await
+            .expect("Could not delete seller");
+        Audited::<OrganizationRepository>::new(Some(helper.id))
+            .delete_with(
+                db,
+                OrganizationBy::Id(organization.id),
+                DeletePolicy::Cascade,
+            )
+            .await
+            .expect("Could not delete organization");
+        let purged = AuditRepository::events(
+            db,
+            AuditEventsWhere {
+                organization_id: Some(organization.id),
+                actor_id: Some(helper.id),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list audit events");
+        assert!(purged
+            .items
+            .iter()
+            .all(|event| event.operation == AuditOperation::Purge && event.after.is_none()));
+        let mut purged = purged
+            .items
+            .iter()
+            .map(|event| (event.entity_type.as_str(), event.entity_id))
+            .collect::<Vec<_>>();
+        purged.sort();
+        let mut expected = vec![
+            ("admin", owner.id),
+            ("admin", helper.id),
+            ("organization", organization.id),
+            ("product", product.id),
+            ("sales", sale.id),
+            ("seller", seller.id),
+            ("tag", tag.id),
+        ];
+        expected.sort();
+        assert_eq!(purged, expected);
+    }
+
+    #[tokio::test]
+    async fn sqlite_sales_and_cascades() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        sales_and_cascades(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_sales_and_cascades() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            sales_and_cascades(&db.connection).await;
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use sqlx::{Acquire, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use super::{
+    audit_cursor, AuditEventDAO, AuditEventRow, AuditEventsWhere, AuditLog, AuditRepository,
+    NewAuditEventDAO,
+};
+use crate::{
+    pagination::{push_after_postgres, Page},
+    traits::DatabaseError,
+};
+
+#[async_trait::async_trait]
+impl AuditLog<Postgres> for AuditRepository {
+    #[tracing::instrument(
+        name = "repository",
+        level = "debug",
+        skip_all,
+        fields(entity = "audit_event", operation = "record"),
+        err(Debug, level = "warn")
+    )]
+    async fn record<'c, A>(db: A, event: NewAuditEventDAO) -> Result<AuditEventDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        sqlx::query_as::<_, AuditEventRow>(
+            "INSERT INTO audit_events (id, actor_id, organization_id, entity_type, entity_id, operation, before_json, after_json) VALUES ($1, $2, $3, $4, $5, $6, $7::jsonb, $8::jsonb) RETURNING id, actor_id, organization_id, entity_type, entity_id, operation, before_json::text AS before_json, after_json::text AS after_json, created_at",
+        )
+        .bind(Uuid::new_v4())
+        .bind(event.actor_id)
+        .bind(event.organization_id)
+        .bind(event.entity_type)
+        .bind(event.entity_id)
+        .bind(event.operation.as_str())
+        .bind(event.before.map(|before| before.to_string()))
+        .bind(event.after.map(|after| after.to_string()))
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)?
+        .try_into()
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "audit_event", operation = "events", key = ?key), err(Debug, level = "warn"))]
+    async fn events<'c, A>(
+        db: A,
+        key: AuditEventsWhere,
+    ) -> Result<Page<AuditEventDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let mut query = QueryBuilder::<Postgres>::new(
+            "SELECT id, actor_id, organization_id, entity_type, entity_id, operation, before_json::text AS before_json, after_json::text AS after_json, created_at FROM audit_events WHERE 1 = 1",
+        );
+        if let Some(entity_type) = &key.entity_type {
+            query
+                .push(" AND entity_type = ")
+                .push_bind(entity_type.clone());
+        }
+        if let Some(entity_id) = key.entity_id {
+            query.push(" AND entity_id = ").push_bind(entity_id);
+        }
+        if let Some(organization_id) = key.organization_id {
+            query
+                .push(" AND organization_id = ")
+                .push_bind(organization_id);
+        }
+        if let Some(actor_id) = key.actor_id {
+            query.push(" AND actor_id = ").push_bind(actor_id);
+        }
+        if let Some(created_after) = key.created_after {
+            query.push(" AND created_at >= ").push_bind(created_after);
+        }
+        if let Some(created_before) = key.created_before {
+            query.push(" AND created_at < ").push_bind(created_before);
+        }
+        if let Some(after) = &key.after {
+            push_after_postgres(&mut query, "created_at", key.order, after)?;
+        }
+        query
+            .push(" ORDER BY created_at ")
+            .push(key.order.keyword())
+            .push(", id ")
+            .push(key.order.keyword())
+            .push(" LIMIT ")
+            .push_bind(i64::from(key.limit) + 1);
+
+        let rows = query
+            .build_query_as::<AuditEventRow>()
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?
+            .into_iter()
+            .map(AuditEventDAO::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Page::from_rows(rows, key.limit, |event| {
+            audit_cursor(key.order, event)
+        }))
+    }
+}
@@ -1,5 +1,72 @@
+use crate::traits::DatabaseError;
+
 pub mod admin;
+pub mod category;
+pub mod exchange_rate;
 pub mod organization;
 pub mod product;
 pub mod sales;
 pub mod seller;
+pub mod tag;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+impl SortOrder {
+    pub(crate) fn keyword(&self) -> &'static str {
+        match self {
+            SortOrder::Ascending => "ASC",
+            SortOrder::Descending => "DESC",
+        }
+    }
+}
+
+/// Stands in for personal data, such as emails, in `Debug` output. Repository keys and filters
+/// are recorded in tracing spans and error messages, which end up in the service logs.
+pub(crate) const REDACTED: &str = "<redacted>";
+
+/// Builds a case-insensitive `LIKE` pattern (used with `ESCAPE '\'`) matching values that
+/// start with `prefix`.
+pub(crate) fn like_prefix(prefix: &str) -> String {
+    let mut pattern = String::with_capacity(prefix.len() + 1);
+    for c in prefix.to_lowercase().chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
+
+/// Emails are stored trimmed and lowercased, so lookups by email are case-insensitive.
+pub(crate) fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+/// Quantities are `u32` in the DAOs but stored in a signed 32-bit INTEGER column.
+pub(crate) fn quantity_to_column(quantity: u32) -> Result<i32, DatabaseError> {
+    i32::try_from(quantity).map_err(|_| DatabaseError::InvalidQuantity(quantity.into()))
+}
+
+/// Rejects a negative stored quantity rather than reading it back as a different number.
+pub(crate) fn quantity_from_column(value: i32) -> Result<u32, DatabaseError> {
+    u32::try_from(value).map_err(|_| DatabaseError::InvalidQuantity(value.into()))
+}
+
+/// Explains why an update guarded by the expected version matched no row, given the version
+/// of the row now stored under the same key.
+pub(crate) fn version_rejection(
+    current: Result<Option<i64>, DatabaseError>,
+    missing: String,
+) -> DatabaseError {
+    match current {
+        Err(e) => e,
+        Ok(None) => DatabaseError::NotFound(missing),
+        Ok(Some(current_version)) => DatabaseError::Conflict { current_version },
+    }
+}
@@ -1,125 +1,514 @@
-use sqlx::{Pool, Sqlite};
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use sqlx::{Acquire, Database, QueryBuilder, Sqlite, SqliteConnection};
 use uuid::Uuid;
 
-use crate::traits::{DatabaseError, EntityRepository};
+use crate::{
+    entities::{like_prefix, normalize_email, SortOrder, REDACTED},
+    pagination::{push_after_sqlite, Cursor, CursorKey, Page},
+    password::{self, Verification},
+    traits::{CredentialsRepository, DatabaseError, EntityRepository, SoftDeleteRepository},
+};
 
-#[derive(Debug)]
+#[cfg(feature = "postgres")]
+pub mod postgres;
+
+#[derive(Clone)]
 pub enum AdminBy {
     Id(Uuid),
+    /// Emails are unique within an organization; matched case-insensitively.
+    Email {
+        organization_id: Uuid,
+        email: String,
+    },
 }
 
-#[derive(sqlx::FromRow, Debug, PartialEq, Eq, Clone)]
+/// Leaves the email out: keys are recorded in tracing spans and error messages.
+impl fmt::Debug for AdminBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AdminBy::Id(id) => f.debug_tuple("Id").field(id).finish(),
+            AdminBy::Email {
+                organization_id, ..
+            } => f
+                .debug_struct("Email")
+                .field("organization_id", organization_id)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AdminsOrderBy {
+    #[default]
+    Email,
+}
+
+impl AdminsOrderBy {
+    pub(crate) fn column(&self) -> &'static str {
+        match self {
+            AdminsOrderBy::Email => "email",
+        }
+    }
+
+    pub(crate) fn cursor(&self, order: SortOrder, admin: &AdminDAO) -> Cursor {
+        let key = match self {
+            AdminsOrderBy::Email => CursorKey::Text(admin.email.clone()),
+        };
+        Cursor::new(self.column(), order, key, admin.id)
+    }
+}
+
+/// Filters used to list admins. Filters left as `None` are not applied.
+#[derive(PartialEq, Eq, Clone)]
+pub struct AdminsWhere {
+    pub organization_id: Option<Uuid>,
+    pub is_default: Option<bool>,
+    /// Case-insensitive prefix of the admin email.
+    pub email_prefix: Option<String>,
+    pub order_by: AdminsOrderBy,
+    pub order: SortOrder,
+    pub limit: i32,
+    /// Resume after the last row of a previous page.
+    pub after: Option<Cursor>,
+    /// List soft-deleted rows instead of live ones.
+    pub deleted: bool,
+}
+
+/// Leaves the email prefix out: filters are recorded in tracing spans.
+impl fmt::Debug for AdminsWhere {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AdminsWhere")
+            .field("organization_id", &self.organization_id)
+            .field("is_default", &self.is_default)
+            .field(
+                "email_prefix",
+                &self.email_prefix.as_ref().map(|_| REDACTED),
+            )
+            .field("order_by", &self.order_by)
+            .field("order", &self.order)
+            .field("limit", &self.limit)
+            .field("after", &self.after)
+            .field("deleted", &self.deleted)
+            .finish()
+    }
+}
+
+impl Default for AdminsWhere {
+    fn default() -> Self {
+        Self {
+            organization_id: None,
+            is_default: None,
+            email_prefix: None,
+            order_by: AdminsOrderBy::default(),
+            order: SortOrder::default(),
+            limit: 100,
+            after: None,
+            deleted: false,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow, Debug, PartialEq, Eq, Clone, serde::Serialize)]
 pub struct AdminDAO {
     pub id: Uuid,
     pub organization_id: Uuid,
     pub email: String,
-    pub password: String,
     pub is_default: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub version: i64,
 }
 
 #[derive(sqlx::FromRow, Debug, PartialEq, Eq, Clone)]
 pub struct NewAdminDAO {
     pub organization_id: Uuid,
     pub email: String,
+    /// Plaintext password; only its Argon2id hash is stored.
     pub password: String,
     pub is_default: bool,
 }
 
 #[derive(sqlx::FromRow, Debug, PartialEq, Eq, Clone)]
 pub struct UpdateAdminDAO {
-    pub password: String,
+    /// New plaintext password, or `None` to keep the current one.
+    pub password: Option<String>,
+    /// Must match the current value; use [`DefaultAdminTransfer::transfer_default`] to move
+    /// the default admin role.
     pub is_default: bool,
-}
-
-impl From<AdminDAO> for NewAdminDAO {
-    fn from(value: AdminDAO) -> Self {
-        Self {
-            organization_id: value.organization_id,
-            email: value.email,
-            password: value.password,
-            is_default: value.is_default,
-        }
-    }
+    /// Version of the row the update is based on; the update fails with
+    /// [`DatabaseError::Conflict`] when the row changed since.
+    pub version: i64,
 }
 
 #[derive(Debug)]
 pub struct AdminRepository;
 
+/// Builds the query of `get_all`; kept apart so the query plan tests can check it.
+pub(crate) fn list_query(
+    key: &AdminsWhere,
+) -> Result<QueryBuilder<'static, Sqlite>, DatabaseError> {
+    let mut query = QueryBuilder::<Sqlite>::new(
+        "SELECT id, organization_id, email, is_default, created_at, updated_at, version FROM admins WHERE 1 = 1",
+    );
+    query.push(if key.deleted {
+        " AND deleted_at IS NOT NULL"
+    } else {
+        " AND deleted_at IS NULL"
+    });
+    if let Some(organization_id) = key.organization_id {
+        query
+            .push(" AND organization_id = ")
+            .push_bind(organization_id);
+    }
+    if let Some(is_default) = key.is_default {
+        query.push(" AND is_default = ").push_bind(is_default);
+    }
+    if let Some(prefix) = &key.email_prefix {
+        query
+            .push(" AND lower(email) LIKE ")
+            .push_bind(like_prefix(prefix))
+            .push(" ESCAPE '\\'");
+    }
+    if let Some(after) = &key.after {
+        push_after_sqlite(&mut query, key.order_by.column(), key.order, after)?;
+    }
+    query
+        .push(" ORDER BY ")
+        .push(key.order_by.column())
+        .push(" ")
+        .push(key.order.keyword())
+        .push(", id ")
+        .push(key.order.keyword())
+        .push(" LIMIT ")
+        .push_bind(i64::from(key.limit) + 1);
+    Ok(query)
+}
+
 #[async_trait::async_trait]
-impl EntityRepository<Sqlite, AdminDAO, NewAdminDAO, UpdateAdminDAO, AdminBy, AdminBy>
+impl EntityRepository<Sqlite, AdminDAO, NewAdminDAO, UpdateAdminDAO, AdminBy, AdminsWhere>
     for AdminRepository
 {
-    async fn insert(db: &Pool<Sqlite>, input: NewAdminDAO) -> Result<AdminDAO, DatabaseError> {
+    #[tracing::instrument(
+        name = "repository",
+        level = "debug",
+        skip_all,
+        fields(entity = "admin", operation = "insert"),
+        err(Debug, level = "warn")
+    )]
+    async fn insert<'c, A>(db: A, input: NewAdminDAO) -> Result<AdminDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let password_hash = password::hash(input.password).await?;
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
         let uuid = Uuid::new_v4();
         sqlx::query_as::<_, AdminDAO>(
-            "INSERT INTO admins (id, organization_id, email, password, is_default) VALUES ($1, $2, $3, $4, $5) RETURNING id, organization_id, email, password, is_default",
+            "INSERT INTO admins (id, organization_id, email, password_hash, is_default) VALUES ($1, $2, $3, $4, $5) RETURNING id, organization_id, email, is_default, created_at, updated_at, version",
         )
         .bind(uuid)
         .bind(input.organization_id)
-        .bind(input.email)
-        .bind(input.password)
+        .bind(normalize_email(&input.email))
+        .bind(password_hash)
         .bind(input.is_default)
-        .fetch_one(db)
+        .fetch_one(&mut *conn)
         .await
         .map_err(DatabaseError::from)
     }
 
-    async fn get(db: &Pool<Sqlite>, key: AdminBy) -> Result<AdminDAO, DatabaseError> {
-        match key {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "admin", operation = "get", key = ?key), err(Debug, level = "warn"))]
+    async fn get<'c, A>(db: A, key: AdminBy) -> Result<AdminDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
             AdminBy::Id(uuid) => sqlx::query_as::<_, AdminDAO>(
-                "SELECT id, organization_id, email, password, is_default FROM admins WHERE id = $1 LIMIT 1",
+                "SELECT id, organization_id, email, is_default, created_at, updated_at, version FROM admins WHERE id = $1 AND deleted_at IS NULL LIMIT 1",
             )
-            .bind(uuid)
-            .fetch_one(db)
+            .bind(uuid),
+            AdminBy::Email { organization_id, email } => sqlx::query_as::<_, AdminDAO>(
+                "SELECT id, organization_id, email, is_default, created_at, updated_at, version FROM admins WHERE organization_id = $1 AND email = $2 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(organization_id)
+            .bind(normalize_email(&email)),
+        };
+        query
+            .fetch_one(&mut *conn)
             .await
-            .map_err(DatabaseError::from),
-        }
+            .map_err(DatabaseError::from)
     }
 
-    async fn try_get(db: &Pool<Sqlite>, key: AdminBy) -> Result<Option<AdminDAO>, DatabaseError> {
-        match key {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "admin", operation = "try_get", key = ?key), err(Debug, level = "warn"))]
+    async fn try_get<'c, A>(db: A, key: AdminBy) -> Result<Option<AdminDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
             AdminBy::Id(uuid) => sqlx::query_as::<_, AdminDAO>(
-                "SELECT id, organization_id, email, password, is_default FROM admins WHERE id = $1 LIMIT 1",
+                "SELECT id, organization_id, email, is_default, created_at, updated_at, version FROM admins WHERE id = $1 AND deleted_at IS NULL LIMIT 1",
             )
-            .bind(uuid)
-            .fetch_optional(db)
+            .bind(uuid),
+            AdminBy::Email { organization_id, email } => sqlx::query_as::<_, AdminDAO>(
+                "SELECT id, organization_id, email, is_default, created_at, updated_at, version FROM admins WHERE organization_id = $1 AND email = $2 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(organization_id)
+            .bind(normalize_email(&email)),
+        };
+        query
+            .fetch_optional(&mut *conn)
             .await
-            .map_err(DatabaseError::from),
-        }
+            .map_err(DatabaseError::from)
     }
 
-    async fn get_all(_db: &Pool<Sqlite>, _key: AdminBy) -> Result<Vec<AdminDAO>, DatabaseError> {
-        Err(DatabaseError::NotImplemented)
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "admin", operation = "get_all", key = ?key), err(Debug, level = "warn"))]
+    async fn get_all<'c, A>(db: A, key: AdminsWhere) -> Result<Page<AdminDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let mut query = list_query(&key)?;
+        let rows = query
+            .build_query_as::<AdminDAO>()
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?;
+        Ok(Page::from_rows(rows, key.limit, |admin| {
+            key.order_by.cursor(key.order, admin)
+        }))
     }
 
-    async fn update(
-        db: &Pool<Sqlite>,
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "admin", operation = "update", key = ?key), err(Debug, level = "warn"))]
+    async fn update<'c, A>(
+        db: A,
         key: AdminBy,
         input: UpdateAdminDAO,
-    ) -> Result<AdminDAO, DatabaseError> {
-        match key {
-            AdminBy::Id(uuid) => {
-                sqlx::query_as::<_, AdminDAO>("UPDATE admins SET password = $2, is_default = $3 WHERE id = $1 RETURNING id, organization_id, email, password, is_default")
-                    .bind(uuid)
-                    .bind(input.password)
-                    .bind(input.is_default)
-                    .fetch_one(db)
-                    .await
-                    .map_err(DatabaseError::from)
-            }
+    ) -> Result<AdminDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let password_hash = match input.password {
+            Some(password) => Some(password::hash(password).await?),
+            None => None,
+        };
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        // Changing `is_default` is left to `transfer_default`, so the guard only matches rows
+        // whose flag stays the same.
+        let query = match &key {
+            AdminBy::Id(uuid) => sqlx::query_as::<_, AdminDAO>("UPDATE admins SET password_hash = COALESCE($2, password_hash), updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE id = $1 AND deleted_at IS NULL AND is_default = $3 AND version = $4 RETURNING id, organization_id, email, is_default, created_at, updated_at, version")
+                .bind(*uuid),
+            AdminBy::Email { organization_id, email } => sqlx::query_as::<_, AdminDAO>("UPDATE admins SET password_hash = COALESCE($3, password_hash), updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE organization_id = $1 AND email = $2 AND deleted_at IS NULL AND is_default = $4 AND version = $5 RETURNING id, organization_id, email, is_default, created_at, updated_at, version")
+                .bind(*organization_id)
+                .bind(normalize_email(email)),
+        };
+        let updated = query
+            .bind(password_hash)
+            .bind(input.is_default)
+            .bind(input.version)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?;
+        match updated {
+            Some(admin) => Ok(admin),
+            None => Err(admin_rejection(&mut conn, key, Some(input.version)).await),
         }
     }
 
-    async fn delete(db: &Pool<Sqlite>, key: AdminBy) -> Result<AdminDAO, DatabaseError> {
-        match key {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "admin", operation = "delete", key = ?key), err(Debug, level = "warn"))]
+    async fn delete<'c, A>(db: A, key: AdminBy) -> Result<AdminDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match &key {
             AdminBy::Id(uuid) => sqlx::query_as::<_, AdminDAO>(
-                "DELETE FROM admins WHERE id = $1 RETURNING id, organization_id, email, password, is_default",
+                "UPDATE admins SET deleted_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE id = $1 AND deleted_at IS NULL AND NOT is_default RETURNING id, organization_id, email, is_default, created_at, updated_at, version",
             )
-            .bind(uuid)
-            .fetch_one(db)
+            .bind(*uuid),
+            AdminBy::Email { organization_id, email } => sqlx::query_as::<_, AdminDAO>(
+                "UPDATE admins SET deleted_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE organization_id = $1 AND email = $2 AND deleted_at IS NULL AND NOT is_default RETURNING id, organization_id, email, is_default, created_at, updated_at, version",
+            )
+            .bind(*organization_id)
+            .bind(normalize_email(email)),
+        };
+        let deleted = query
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?;
+        match deleted {
+            Some(admin) => Ok(admin),
+            None => Err(admin_rejection(&mut conn, key, None).await),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SoftDeleteRepository<Sqlite, AdminDAO, AdminBy> for AdminRepository {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "admin", operation = "restore", key = ?key), err(Debug, level = "warn"))]
+    async fn restore<'c, A>(db: A, key: AdminBy) -> Result<AdminDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            AdminBy::Id(uuid) => sqlx::query_as::<_, AdminDAO>(
+                "UPDATE admins SET deleted_at = NULL, updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE id = $1 AND deleted_at IS NOT NULL RETURNING id, organization_id, email, is_default, created_at, updated_at, version",
+            )
+            .bind(uuid),
+            AdminBy::Email { organization_id, email } => sqlx::query_as::<_, AdminDAO>(
+                "UPDATE admins SET deleted_at = NULL, updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE organization_id = $1 AND email = $2 AND deleted_at IS NOT NULL RETURNING id, organization_id, email, is_default, created_at, updated_at, version",
+            )
+            .bind(organization_id)
+            .bind(normalize_email(&email)),
+        };
+        query
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "admin", operation = "purge", key = ?key), err(Debug, level = "warn"))]
+    async fn purge<'c, A>(db: A, key: AdminBy) -> Result<AdminDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match &key {
+            AdminBy::Id(uuid) => sqlx::query_as::<_, AdminDAO>(
+                "DELETE FROM admins WHERE id = $1 AND NOT is_default RETURNING id, organization_id, email, is_default, created_at, updated_at, version",
+            )
+            .bind(*uuid),
+            AdminBy::Email { organization_id, email } => sqlx::query_as::<_, AdminDAO>(
+                "DELETE FROM admins WHERE organization_id = $1 AND email = $2 AND NOT is_default RETURNING id, organization_id, email, is_default, created_at, updated_at, version",
+            )
+            .bind(*organization_id)
+            .bind(normalize_email(email)),
+        };
+        let deleted = query
+            .fetch_optional(&mut *conn)
             .await
-            .map_err(DatabaseError::from),
+            .map_err(DatabaseError::from)?;
+        match deleted {
+            Some(admin) => Ok(admin),
+            None => Err(admin_rejection(&mut conn, key, None).await),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+pub trait DefaultAdminTransfer<DB: Database> {
+    /// Makes `to` the default admin of its organization and demotes the previous one, in a
+    /// single transaction.
+    async fn transfer_default<'c, A>(db: A, to: AdminBy) -> Result<AdminDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = DB> + Send;
+}
+
+/// Explains why a guarded update or delete of the admin matched by `key` matched no row;
+/// `version` is the version an update was based on.
+async fn admin_rejection(
+    conn: &mut SqliteConnection,
+    key: AdminBy,
+    version: Option<i64>,
+) -> DatabaseError {
+    let missing = format!("admin {key:?}");
+    match AdminRepository::try_get(&mut *conn, key).await {
+        Err(e) => e,
+        Ok(None) => DatabaseError::NotFound(missing),
+        Ok(Some(admin)) if version.is_some_and(|version| version != admin.version) => {
+            DatabaseError::Conflict {
+                current_version: admin.version,
+            }
         }
+        Ok(Some(_)) => DatabaseError::DefaultAdminProtected,
+    }
+}
+
+#[async_trait::async_trait]
+impl CredentialsRepository<Sqlite, AdminDAO> for AdminRepository {
+    #[tracing::instrument(
+        name = "repository",
+        level = "debug",
+        skip_all,
+        fields(entity = "admin", operation = "verify_password"),
+        err(Debug, level = "warn")
+    )]
+    async fn verify_password<'c, A>(
+        db: A,
+        organization_id: Uuid,
+        email: &str,
+        candidate: &str,
+    ) -> Result<Option<AdminDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let stored = sqlx::query_as::<_, (Uuid, String)>(
+            "SELECT id, password_hash FROM admins WHERE organization_id = $1 AND email = $2 AND deleted_at IS NULL LIMIT 1",
+        )
+        .bind(organization_id)
+        .bind(normalize_email(email))
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)?;
+        let Some((id, stored)) = stored else {
+            password::verify_nothing(candidate.to_string()).await?;
+            return Ok(None);
+        };
+
+        match password::verify(stored.clone(), candidate.to_string()).await? {
+            Verification::Invalid => return Ok(None),
+            Verification::Valid => {}
+            Verification::ValidNeedsRehash => {
+                let rehashed = password::hash(candidate.to_string()).await?;
+                // Same password, new hash: not a change callers need to see in `version`
+                sqlx::query(
+                    "UPDATE admins SET password_hash = $2, updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now') WHERE id = $1 AND password_hash = $3",
+                )
+                .bind(id)
+                .bind(rehashed)
+                .bind(stored)
+                .execute(&mut *conn)
+                .await
+                .map_err(DatabaseError::from)?;
+            }
+        }
+
+        AdminRepository::get(&mut *conn, AdminBy::Id(id))
+            .await
+            .map(Some)
+    }
+}
+
+#[async_trait::async_trait]
+impl DefaultAdminTransfer<Sqlite> for AdminRepository {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "admin", operation = "transfer_default", key = ?to), err(Debug, level = "warn"))]
+    async fn transfer_default<'c, A>(db: A, to: AdminBy) -> Result<AdminDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut tx = db.begin().await.map_err(DatabaseError::from)?;
+        let admin = AdminRepository::get(&mut tx, to).await?;
+        // Demote first, the partial unique index allows a single default per organization.
+        sqlx::query(
+            "UPDATE admins SET is_default = false, updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE organization_id = $1 AND is_default AND id <> $2",
+        )
+        .bind(admin.organization_id)
+        .bind(admin.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(DatabaseError::from)?;
+        let admin = sqlx::query_as::<_, AdminDAO>(
+            "UPDATE admins SET is_default = true, updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE id = $1 RETURNING id, organization_id, email, is_default, created_at, updated_at, version",
+        )
+        .bind(admin.id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(DatabaseError::from)?;
+        tx.commit().await.map_err(DatabaseError::from)?;
+        Ok(admin)
     }
 }
 
@@ -127,30 +516,56 @@ impl EntityRepository<Sqlite, AdminDAO, NewAdminDAO, UpdateAdminDAO, AdminBy, Ad
 mod tests {
     use super::*;
     use crate::{
-        entities::organization::{NewOrganizationDAO, OrganizationDAO, OrganizationRepository},
+        entities::organization::{
+            NewOrganizationDAO, OrganizationBy, OrganizationDAO, OrganizationRepository,
+            OrganizationsWhere, UpdateOrganizationDAO,
+        },
+        money::Currency,
         sqlite::DatabaseRepository,
     };
+    use sqlx::{
+        database::HasArguments, Database, Encode, Executor, FromRow, IntoArguments, Pool,
+        Transaction, Type,
+    };
 
-    async fn create_organization(conection: &Pool<Sqlite>, name: &str) -> OrganizationDAO {
+    async fn create_organization<DB: Database>(conection: &Pool<DB>, name: &str) -> OrganizationDAO
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+    {
         OrganizationRepository::insert(
             conection,
             NewOrganizationDAO {
                 name: name.to_string(),
+                reporting_currency: Currency::USD,
             },
         )
         .await
         .expect("Could not create organization")
     }
 
-    #[tokio::test]
-    async fn queries() {
-        let db = DatabaseRepository::new()
-            .await
-            .expect("Could not initialize db");
-
-        let organization = create_organization(&db.connection, "dev").await;
+    async fn queries<DB: Database>(db: &Pool<DB>)
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+        AdminRepository:
+            EntityRepository<DB, AdminDAO, NewAdminDAO, UpdateAdminDAO, AdminBy, AdminsWhere>,
+    {
+        let organization = create_organization(db, "dev").await;
         let result = AdminRepository::insert(
-            &db.connection,
+            db,
             NewAdminDAO {
                 organization_id: organization.id,
                 email: "admin@gmail.com".to_string(),
@@ -162,33 +577,33 @@ mod tests {
         .expect("Could not insert admin");
 
         assert_eq!(result.email, "admin@gmail.com");
-        assert_eq!(result.password, "test1");
         assert_eq!(result.organization_id, organization.id);
         assert!(!result.is_default);
 
-        let admin = AdminRepository::get(&db.connection, AdminBy::Id(result.id))
+        let admin = AdminRepository::get(db, AdminBy::Id(result.id))
             .await
             .expect("Admin not found");
         assert_eq!(result.email, admin.email);
 
-        let admin = AdminRepository::try_get(&db.connection, AdminBy::Id(result.id))
+        let admin = AdminRepository::try_get(db, AdminBy::Id(result.id))
             .await
             .expect("Admin not found");
 
         assert!(admin.is_some());
 
-        let maybe_admin = AdminRepository::try_get(&db.connection, AdminBy::Id(Uuid::default()))
+        let maybe_admin = AdminRepository::try_get(db, AdminBy::Id(Uuid::default()))
             .await
             .expect("Could not get admin info");
 
         assert!(maybe_admin.is_none());
 
         let updated = AdminRepository::update(
-            &db.connection,
+            db,
             AdminBy::Id(result.id),
             UpdateAdminDAO {
-                password: "test34".to_string(),
-                is_default: true,
+                password: Some("test34".to_string()),
+                is_default: false,
+                version: result.version,
             },
         )
         .await
@@ -196,17 +611,651 @@ mod tests {
 
         assert_eq!(updated.id, result.id);
         assert_eq!(updated.email, "admin@gmail.com");
-        assert_ne!(updated.password, result.password);
-        assert!(updated.is_default);
+        assert!(!updated.is_default);
 
-        let _ = AdminRepository::delete(&db.connection, AdminBy::Id(result.id))
+        let _ = AdminRepository::delete(db, AdminBy::Id(result.id))
             .await
             .expect("Could not delete admin");
 
-        let admin = AdminRepository::try_get(&db.connection, AdminBy::Id(result.id))
+        let admin = AdminRepository::try_get(db, AdminBy::Id(result.id))
             .await
             .expect("Admin not found");
 
         assert!(admin.is_none());
     }
+
+    async fn unit_of_work<DB: Database>(db: &Pool<DB>)
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+        AdminRepository:
+            EntityRepository<DB, AdminDAO, NewAdminDAO, UpdateAdminDAO, AdminBy, AdminsWhere>,
+        for<'t> &'t mut Transaction<'static, DB>: Acquire<'t, Database = DB>,
+    {
+        let existing = create_organization(db, "existing").await;
+        AdminRepository::insert(
+            db,
+            NewAdminDAO {
+                organization_id: existing.id,
+                email: "owner@gmail.com".to_string(),
+                password: "test1".to_string(),
+                is_default: true,
+            },
+        )
+        .await
+        .expect("Could not insert admin");
+
+        // The admin email is taken in the existing organization, so the organization created
+        // alongside the admin is rolled back
+        let mut tx = db.begin().await.expect("Could not start transaction");
+        let organization = OrganizationRepository::insert(
+            &mut tx,
+            NewOrganizationDAO {
+                name: "rolled back".to_string(),
+                reporting_currency: Currency::USD,
+            },
+        )
+        .await
+        .expect("Could not create organization");
+        let result = AdminRepository::insert(
+            &mut tx,
+            NewAdminDAO {
+                organization_id: existing.id,
+                email: "owner@gmail.com".to_string(),
+                password: "test1".to_string(),
+                is_default: false,
+            },
+        )
+        .await;
+        assert!(matches!(result, Err(DatabaseError::UniqueViolation { .. })));
+        tx.rollback().await.expect("Could not rollback");
+
+        let maybe_organization =
+            OrganizationRepository::try_get(db, OrganizationBy::Id(organization.id))
+                .await
+                .expect("Could not find organization");
+        assert!(maybe_organization.is_none());
+
+        let mut tx = db.begin().await.expect("Could not start transaction");
+        let organization = OrganizationRepository::insert(
+            &mut tx,
+            NewOrganizationDAO {
+                name: "committed".to_string(),
+                reporting_currency: Currency::USD,
+            },
+        )
+        .await
+        .expect("Could not create organization");
+        let admin = AdminRepository::insert(
+            &mut tx,
+            NewAdminDAO {
+                organization_id: organization.id,
+                email: "default@gmail.com".to_string(),
+                password: "test1".to_string(),
+                is_default: true,
+            },
+        )
+        .await
+        .expect("Could not insert admin");
+        tx.commit().await.expect("Could not commit");
+
+        let admin = AdminRepository::get(db, AdminBy::Id(admin.id))
+            .await
+            .expect("Admin not found");
+        assert_eq!(admin.organization_id, organization.id);
+    }
+
+    async fn stored_hash<DB: Database>(db: &Pool<DB>, id: Uuid) -> String
+    where
+        for<'c> &'c Pool<DB>: Executor<'c, Database = DB>,
+        for<'q> <DB as HasArguments<'q>>::Arguments: IntoArguments<'q, DB>,
+        for<'q> Uuid: Encode<'q, DB> + Type<DB>,
+        for<'r> (String,): FromRow<'r, DB::Row>,
+    {
+        sqlx::query_as::<_, (String,)>("SELECT password_hash FROM admins WHERE id = $1")
+            .bind(id)
+            .fetch_one(db)
+            .await
+            .expect("Could not read password hash")
+            .0
+    }
+
+    async fn store_hash<DB: Database>(db: &Pool<DB>, id: Uuid, hash: String)
+    where
+        for<'c> &'c Pool<DB>: Executor<'c, Database = DB>,
+        for<'q> <DB as HasArguments<'q>>::Arguments: IntoArguments<'q, DB>,
+        for<'q> Uuid: Encode<'q, DB> + Type<DB>,
+        for<'q> String: Encode<'q, DB> + Type<DB>,
+    {
+        sqlx::query("UPDATE admins SET password_hash = $2 WHERE id = $1")
+            .bind(id)
+            .bind(hash)
+            .execute(db)
+            .await
+            .expect("Could not write password hash");
+    }
+
+    async fn credentials<DB: Database>(db: &Pool<DB>)
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+        AdminRepository: EntityRepository<DB, AdminDAO, NewAdminDAO, UpdateAdminDAO, AdminBy, AdminsWhere>
+            + CredentialsRepository<DB, AdminDAO>,
+        for<'c> &'c Pool<DB>: Executor<'c, Database = DB>,
+        for<'q> <DB as HasArguments<'q>>::Arguments: IntoArguments<'q, DB>,
+        for<'q> Uuid: Encode<'q, DB> + Type<DB>,
+        for<'q> String: Encode<'q, DB> + Type<DB>,
+        for<'r> (String,): FromRow<'r, DB::Row>,
+    {
+        let organization = create_organization(db, "dev").await;
+        let admin = AdminRepository::insert(
+            db,
+            NewAdminDAO {
+                organization_id: organization.id,
+                email: "admin@gmail.com".to_string(),
+                password: "secret".to_string(),
+                is_default: false,
+            },
+        )
+        .await
+        .expect("Could not insert admin");
+
+        let hash = stored_hash(db, admin.id).await;
+        assert!(hash.starts_with("$argon2id$"));
+
+        let verified =
+            AdminRepository::verify_password(db, organization.id, "admin@gmail.com", "secret")
+                .await
+                .expect("Could not verify password");
+        assert_eq!(verified, Some(admin.clone()));
+        assert_eq!(stored_hash(db, admin.id).await, hash);
+
+        let wrong =
+            AdminRepository::verify_password(db, organization.id, "admin@gmail.com", "Secret")
+                .await
+                .expect("Could not verify password");
+        assert_eq!(wrong, None);
+
+        let unknown =
+            AdminRepository::verify_password(db, organization.id, "nobody@gmail.com", "secret")
+                .await
+                .expect("Could not verify password");
+        assert_eq!(unknown, None);
+
+        // Keeping the password untouched on update
+        AdminRepository::update(
+            db,
+            AdminBy::Id(admin.id),
+            UpdateAdminDAO {
+                password: None,
+                is_default: false,
+                version: admin.version,
+            },
+        )
+        .await
+        .expect("Could not update admin");
+        assert_eq!(stored_hash(db, admin.id).await, hash);
+
+        // Plaintext left by older versions is hashed by `migrate_up`, never compared as is
+        store_hash(db, admin.id, "legacy".to_string()).await;
+        let verified =
+            AdminRepository::verify_password(db, organization.id, "admin@gmail.com", "legacy")
+                .await
+                .expect("Could not verify password");
+        assert_eq!(verified, None);
+
+        // Hashes made with weaker parameters are upgraded on the next successful login
+        let weak = crate::password::hash_with_params(
+            "secret",
+            argon2::Params::new(1024, 1, 1, None).unwrap(),
+        );
+        store_hash(db, admin.id, weak.clone()).await;
+        let verified =
+            AdminRepository::verify_password(db, organization.id, "admin@gmail.com", "secret")
+                .await
+                .expect("Could not verify password");
+        assert!(verified.is_some());
+        let upgraded = stored_hash(db, admin.id).await;
+        assert_ne!(upgraded, weak);
+        assert!(upgraded.contains(&format!("m={}", argon2::Params::DEFAULT_M_COST)));
+    }
+
+    async fn default_admin<DB: Database>(db: &Pool<DB>)
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+        AdminRepository: EntityRepository<DB, AdminDAO, NewAdminDAO, UpdateAdminDAO, AdminBy, AdminsWhere>
+            + DefaultAdminTransfer<DB>,
+    {
+        let organization = create_organization(db, "dev").await;
+        let other_organization = create_organization(db, "ops").await;
+        let new_admin = |organization_id: Uuid, email: &str, is_default: bool| NewAdminDAO {
+            organization_id,
+            email: email.to_string(),
+            password: "secret".to_string(),
+            is_default,
+        };
+        let owner =
+            AdminRepository::insert(db, new_admin(organization.id, "owner@gmail.com", true))
+                .await
+                .expect("Could not insert default admin");
+        let helper =
+            AdminRepository::insert(db, new_admin(organization.id, "helper@gmail.com", false))
+                .await
+                .expect("Could not insert admin");
+        AdminRepository::insert(db, new_admin(other_organization.id, "ops@gmail.com", true))
+            .await
+            .expect("Each organization has its own default admin");
+
+        let second_default =
+            AdminRepository::insert(db, new_admin(organization.id, "second@gmail.com", true)).await;
+        assert!(matches!(
+            second_default,
+            Err(DatabaseError::UniqueViolation { .. })
+        ));
+
+        let deleted = AdminRepository::delete(db, AdminBy::Id(owner.id)).await;
+        assert!(matches!(deleted, Err(DatabaseError::DefaultAdminProtected)));
+        let deleted = AdminRepository::delete(db, AdminBy::Id(Uuid::new_v4())).await;
+        assert!(matches!(deleted, Err(DatabaseError::NotFound(_))));
+
+        let demoted = AdminRepository::update(
+            db,
+            AdminBy::Id(owner.id),
+            UpdateAdminDAO {
+                password: None,
+                is_default: false,
+                version: owner.version,
+            },
+        )
+        .await;
+        assert!(matches!(demoted, Err(DatabaseError::DefaultAdminProtected)));
+        let promoted = AdminRepository::update(
+            db,
+            AdminBy::Id(helper.id),
+            UpdateAdminDAO {
+                password: None,
+                is_default: true,
+                version: helper.version,
+            },
+        )
+        .await;
+        assert!(matches!(
+            promoted,
+            Err(DatabaseError::DefaultAdminProtected)
+        ));
+        let stale = AdminRepository::update(
+            db,
+            AdminBy::Id(helper.id),
+            UpdateAdminDAO {
+                password: None,
+                is_default: false,
+                version: helper.version + 1,
+            },
+        )
+        .await;
+        assert_eq!(
+            stale,
+            Err(DatabaseError::Conflict {
+                current_version: helper.version
+            })
+        );
+        let unknown = AdminRepository::update(
+            db,
+            AdminBy::Id(Uuid::new_v4()),
+            UpdateAdminDAO {
+                password: None,
+                is_default: false,
+                version: 1,
+            },
+        )
+        .await;
+        assert!(matches!(unknown, Err(DatabaseError::NotFound(_))));
+
+        let transferred = AdminRepository::transfer_default(db, AdminBy::Id(helper.id))
+            .await
+            .expect("Could not transfer default admin");
+        assert_eq!(transferred.id, helper.id);
+        assert!(transferred.is_default);
+
+        let defaults = AdminRepository::get_all(
+            db,
+            AdminsWhere {
+                organization_id: Some(organization.id),
+                is_default: Some(true),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list admins");
+        assert_eq!(defaults.items, vec![transferred]);
+
+        let transferred = AdminRepository::transfer_default(db, AdminBy::Id(Uuid::new_v4())).await;
+        assert!(matches!(transferred, Err(DatabaseError::NotFound(_))));
+
+        let previous = AdminRepository::delete(db, AdminBy::Id(owner.id))
+            .await
+            .expect("Former default admin can be deleted");
+        assert!(!previous.is_default);
+    }
+
+    async fn lookups<DB: Database>(db: &Pool<DB>)
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+        AdminRepository:
+            EntityRepository<DB, AdminDAO, NewAdminDAO, UpdateAdminDAO, AdminBy, AdminsWhere>,
+    {
+        let organization = create_organization(db, "dev").await;
+        let owner = AdminRepository::insert(
+            db,
+            NewAdminDAO {
+                organization_id: organization.id,
+                email: "Owner@Gmail.com".to_string(),
+                password: "secret".to_string(),
+                is_default: true,
+            },
+        )
+        .await
+        .expect("Could not insert admin");
+        let helper = AdminRepository::insert(
+            db,
+            NewAdminDAO {
+                organization_id: organization.id,
+                email: "Helper@Gmail.com".to_string(),
+                password: "secret".to_string(),
+                is_default: false,
+            },
+        )
+        .await
+        .expect("Could not insert admin");
+        assert_eq!(owner.email, "owner@gmail.com");
+
+        let admin = AdminRepository::get(
+            db,
+            AdminBy::Email {
+                organization_id: organization.id,
+                email: "OWNER@gmail.com".to_string(),
+            },
+        )
+        .await
+        .expect("Could not find admin by email");
+        assert_eq!(admin, owner);
+        // An organization's admins are listed rather than looked up
+        let admins = AdminRepository::get_all(
+            db,
+            AdminsWhere {
+                organization_id: Some(organization.id),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list admins of the organization");
+        assert_eq!(admins.items, vec![helper.clone(), owner.clone()]);
+
+        let deleted = AdminRepository::delete(
+            db,
+            AdminBy::Email {
+                organization_id: organization.id,
+                email: "owner@gmail.com".to_string(),
+            },
+        )
+        .await;
+        assert_eq!(deleted, Err(DatabaseError::DefaultAdminProtected));
+        let updated = AdminRepository::update(
+            db,
+            AdminBy::Email {
+                organization_id: organization.id,
+                email: "nobody@gmail.com".to_string(),
+            },
+            UpdateAdminDAO {
+                password: None,
+                is_default: false,
+                version: 1,
+            },
+        )
+        .await;
+        assert!(matches!(updated, Err(DatabaseError::NotFound(_))));
+
+        let deleted = AdminRepository::delete(
+            db,
+            AdminBy::Email {
+                organization_id: organization.id,
+                email: "HELPER@gmail.com".to_string(),
+            },
+        )
+        .await
+        .expect("Could not delete admin by email");
+        assert!(deleted.updated_at >= helper.updated_at);
+        assert_eq!(
+            deleted,
+            AdminDAO {
+                version: helper.version + 1,
+                updated_at: deleted.updated_at,
+                ..helper
+            }
+        );
+    }
+
+    async fn organization_emails<DB: Database>(db: &Pool<DB>)
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+        AdminRepository: EntityRepository<DB, AdminDAO, NewAdminDAO, UpdateAdminDAO, AdminBy, AdminsWhere>
+            + CredentialsRepository<DB, AdminDAO>,
+    {
+        let dev = create_organization(db, "dev").await;
+        let ops = create_organization(db, "ops").await;
+        let new_admin = |organization_id: Uuid, password: &str| NewAdminDAO {
+            organization_id,
+            email: "consultant@gmail.com".to_string(),
+            password: password.to_string(),
+            is_default: false,
+        };
+        let in_dev = AdminRepository::insert(db, new_admin(dev.id, "dev secret"))
+            .await
+            .expect("Could not insert admin");
+        let in_ops = AdminRepository::insert(db, new_admin(ops.id, "ops secret"))
+            .await
+            .expect("The same email can be used in another organization");
+        let duplicate = AdminRepository::insert(db, new_admin(dev.id, "secret")).await;
+        assert!(matches!(
+            duplicate,
+            Err(DatabaseError::UniqueViolation { .. })
+        ));
+
+        let admin = AdminRepository::get(
+            db,
+            AdminBy::Email {
+                organization_id: ops.id,
+                email: "Consultant@gmail.com".to_string(),
+            },
+        )
+        .await
+        .expect("Could not find admin by email");
+        assert_eq!(admin, in_ops);
+
+        let verified =
+            AdminRepository::verify_password(db, dev.id, "consultant@gmail.com", "dev secret")
+                .await
+                .expect("Could not verify password");
+        assert_eq!(verified, Some(in_dev.clone()));
+        let verified =
+            AdminRepository::verify_password(db, dev.id, "consultant@gmail.com", "ops secret")
+                .await
+                .expect("Could not verify password");
+        assert_eq!(verified, None);
+
+        let deleted = AdminRepository::delete(
+            db,
+            AdminBy::Email {
+                organization_id: dev.id,
+                email: "consultant@gmail.com".to_string(),
+            },
+        )
+        .await
+        .expect("Could not delete admin by email");
+        assert_eq!(deleted.id, in_dev.id);
+        let remaining = AdminRepository::try_get(db, AdminBy::Id(in_ops.id))
+            .await
+            .expect("Could not look up admin");
+        assert_eq!(remaining, Some(in_ops));
+    }
+
+    #[tokio::test]
+    async fn sqlite_queries() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        queries(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_queries() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            queries(&db.connection).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn sqlite_unit_of_work() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        unit_of_work(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_unit_of_work() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            unit_of_work(&db.connection).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn sqlite_credentials() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        credentials(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_credentials() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            credentials(&db.connection).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn sqlite_default_admin() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        default_admin(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_default_admin() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            default_admin(&db.connection).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn sqlite_lookups() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        lookups(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_lookups() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            lookups(&db.connection).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn sqlite_organization_emails() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        organization_emails(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_organization_emails() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            organization_emails(&db.connection).await;
+        }
+    }
+
+    #[test]
+    fn debug_output_leaves_out_emails() {
+        let organization_id = Uuid::new_v4();
+        let key = format!(
+            "{:?}",
+            AdminBy::Email {
+                organization_id,
+                email: "bob@shop.com".to_string(),
+            }
+        );
+        assert!(key.contains(&organization_id.to_string()), "{key}");
+        assert!(!key.contains("bob"), "{key}");
+
+        let cursor = Cursor::new(
+            "email",
+            SortOrder::Ascending,
+            CursorKey::Text("bob@shop.com".to_string()),
+            Uuid::new_v4(),
+        );
+        let filters = format!(
+            "{:?}",
+            AdminsWhere {
+                email_prefix: Some("bob".to_string()),
+                order_by: AdminsOrderBy::Email,
+                after: Some(cursor),
+                ..Default::default()
+            }
+        );
+        assert!(!filters.contains("bob"), "{filters}");
+    }
 }
@@ -0,0 +1,364 @@
+use sqlx::{Acquire, PgConnection, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use super::{
+    AdminBy, AdminDAO, AdminRepository, AdminsWhere, DefaultAdminTransfer, NewAdminDAO,
+    UpdateAdminDAO,
+};
+use crate::{
+    entities::{like_prefix, normalize_email},
+    pagination::{push_after_postgres, Page},
+    password::{self, Verification},
+    traits::{CredentialsRepository, DatabaseError, EntityRepository, SoftDeleteRepository},
+};
+
+#[async_trait::async_trait]
+impl EntityRepository<Postgres, AdminDAO, NewAdminDAO, UpdateAdminDAO, AdminBy, AdminsWhere>
+    for AdminRepository
+{
+    #[tracing::instrument(
+        name = "repository",
+        level = "debug",
+        skip_all,
+        fields(entity = "admin", operation = "insert"),
+        err(Debug, level = "warn")
+    )]
+    async fn insert<'c, A>(db: A, input: NewAdminDAO) -> Result<AdminDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let password_hash = password::hash(input.password).await?;
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let uuid = Uuid::new_v4();
+        sqlx::query_as::<_, AdminDAO>(
+            "INSERT INTO admins (id, organization_id, email, password_hash, is_default) VALUES ($1, $2, $3, $4, $5) RETURNING id, organization_id, email, is_default, created_at, updated_at, version",
+        )
+        .bind(uuid)
+        .bind(input.organization_id)
+        .bind(normalize_email(&input.email))
+        .bind(password_hash)
+        .bind(input.is_default)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "admin", operation = "get", key = ?key), err(Debug, level = "warn"))]
+    async fn get<'c, A>(db: A, key: AdminBy) -> Result<AdminDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            AdminBy::Id(uuid) => sqlx::query_as::<_, AdminDAO>(
+                "SELECT id, organization_id, email, is_default, created_at, updated_at, version FROM admins WHERE id = $1 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(uuid),
+            AdminBy::Email { organization_id, email } => sqlx::query_as::<_, AdminDAO>(
+                "SELECT id, organization_id, email, is_default, created_at, updated_at, version FROM admins WHERE organization_id = $1 AND email = $2 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(organization_id)
+            .bind(normalize_email(&email)),
+        };
+        query
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "admin", operation = "try_get", key = ?key), err(Debug, level = "warn"))]
+    async fn try_get<'c, A>(db: A, key: AdminBy) -> Result<Option<AdminDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            AdminBy::Id(uuid) => sqlx::query_as::<_, AdminDAO>(
+                "SELECT id, organization_id, email, is_default, created_at, updated_at, version FROM admins WHERE id = $1 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(uuid),
+            AdminBy::Email { organization_id, email } => sqlx::query_as::<_, AdminDAO>(
+                "SELECT id, organization_id, email, is_default, created_at, updated_at, version FROM admins WHERE organization_id = $1 AND email = $2 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(organization_id)
+            .bind(normalize_email(&email)),
+        };
+        query
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "admin", operation = "get_all", key = ?key), err(Debug, level = "warn"))]
+    async fn get_all<'c, A>(db: A, key: AdminsWhere) -> Result<Page<AdminDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let mut query = QueryBuilder::<Postgres>::new(
+            "SELECT id, organization_id, email, is_default, created_at, updated_at, version FROM admins WHERE 1 = 1",
+        );
+        query.push(if key.deleted {
+            " AND deleted_at IS NOT NULL"
+        } else {
+            " AND deleted_at IS NULL"
+        });
+        if let Some(organization_id) = key.organization_id {
+            query
+                .push(" AND organization_id = ")
+                .push_bind(organization_id);
+        }
+        if let Some(is_default) = key.is_default {
+            query.push(" AND is_default = ").push_bind(is_default);
+        }
+        if let Some(prefix) = &key.email_prefix {
+            query
+                .push(" AND lower(email) LIKE ")
+                .push_bind(like_prefix(prefix))
+                .push(" ESCAPE '\\'");
+        }
+        if let Some(after) = &key.after {
+            push_after_postgres(&mut query, key.order_by.column(), key.order, after)?;
+        }
+        query
+            .push(" ORDER BY ")
+            .push(key.order_by.column())
+            .push(" ")
+            .push(key.order.keyword())
+            .push(", id ")
+            .push(key.order.keyword())
+            .push(" LIMIT ")
+            .push_bind(i64::from(key.limit) + 1);
+
+        let rows = query
+            .build_query_as::<AdminDAO>()
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?;
+        Ok(Page::from_rows(rows, key.limit, |admin| {
+            key.order_by.cursor(key.order, admin)
+        }))
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "admin", operation = "update", key = ?key), err(Debug, level = "warn"))]
+    async fn update<'c, A>(
+        db: A,
+        key: AdminBy,
+        input: UpdateAdminDAO,
+    ) -> Result<AdminDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let password_hash = match input.password {
+            Some(password) => Some(password::hash(password).await?),
+            None => None,
+        };
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        // Changing `is_default` is left to `transfer_default`, so the guard only matches rows
+        // whose flag stays the same.
+        let query = match &key {
+            AdminBy::Id(uuid) => sqlx::query_as::<_, AdminDAO>("UPDATE admins SET password_hash = COALESCE($2, password_hash), version = version + 1 WHERE id = $1 AND deleted_at IS NULL AND is_default = $3 AND version = $4 RETURNING id, organization_id, email, is_default, created_at, updated_at, version")
+                .bind(*uuid),
+            AdminBy::Email { organization_id, email } => sqlx::query_as::<_, AdminDAO>("UPDATE admins SET password_hash = COALESCE($3, password_hash), version = version + 1 WHERE organization_id = $1 AND email = $2 AND deleted_at IS NULL AND is_default = $4 AND version = $5 RETURNING id, organization_id, email, is_default, created_at, updated_at, version")
+                .bind(*organization_id)
+                .bind(normalize_email(email)),
+        };
+        let updated = query
+            .bind(password_hash)
+            .bind(input.is_default)
+            .bind(input.version)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?;
+        match updated {
+            Some(admin) => Ok(admin),
+            None => Err(admin_rejection(&mut conn, key, Some(input.version)).await),
+        }
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "admin", operation = "delete", key = ?key), err(Debug, level = "warn"))]
+    async fn delete<'c, A>(db: A, key: AdminBy) -> Result<AdminDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match &key {
+            AdminBy::Id(uuid) => sqlx::query_as::<_, AdminDAO>(
+                "UPDATE admins SET deleted_at = now(), version = version + 1 WHERE id = $1 AND deleted_at IS NULL AND NOT is_default RETURNING id, organization_id, email, is_default, created_at, updated_at, version",
+            )
+            .bind(*uuid),
+            AdminBy::Email { organization_id, email } => sqlx::query_as::<_, AdminDAO>(
+                "UPDATE admins SET deleted_at = now(), version = version + 1 WHERE organization_id = $1 AND email = $2 AND deleted_at IS NULL AND NOT is_default RETURNING id, organization_id, email, is_default, created_at, updated_at, version",
+            )
+            .bind(*organization_id)
+            .bind(normalize_email(email)),
+        };
+        let deleted = query
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?;
+        match deleted {
+            Some(admin) => Ok(admin),
+            None => Err(admin_rejection(&mut conn, key, None).await),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SoftDeleteRepository<Postgres, AdminDAO, AdminBy> for AdminRepository {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "admin", operation = "restore", key = ?key), err(Debug, level = "warn"))]
+    async fn restore<'c, A>(db: A, key: AdminBy) -> Result<AdminDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            AdminBy::Id(uuid) => sqlx::query_as::<_, AdminDAO>(
+                "UPDATE admins SET deleted_at = NULL, version = version + 1 WHERE id = $1 AND deleted_at IS NOT NULL RETURNING id, organization_id, email, is_default, created_at, updated_at, version",
+            )
+            .bind(uuid),
+            AdminBy::Email { organization_id, email } => sqlx::query_as::<_, AdminDAO>(
+                "UPDATE admins SET deleted_at = NULL, version = version + 1 WHERE organization_id = $1 AND email = $2 AND deleted_at IS NOT NULL RETURNING id, organization_id, email, is_default, created_at, updated_at, version",
+            )
+            .bind(organization_id)
+            .bind(normalize_email(&email)),
+        };
+        query
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "admin", operation = "purge", key = ?key), err(Debug, level = "warn"))]
+    async fn purge<'c, A>(db: A, key: AdminBy) -> Result<AdminDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match &key {
+            AdminBy::Id(uuid) => sqlx::query_as::<_, AdminDAO>(
+                "DELETE FROM admins WHERE id = $1 AND NOT is_default RETURNING id, organization_id, email, is_default, created_at, updated_at, version",
+            )
+            .bind(*uuid),
+            AdminBy::Email { organization_id, email } => sqlx::query_as::<_, AdminDAO>(
+                "DELETE FROM admins WHERE organization_id = $1 AND email = $2 AND NOT is_default RETURNING id, organization_id, email, is_default, created_at, updated_at, version",
+            )
+            .bind(*organization_id)
+            .bind(normalize_email(email)),
+        };
+        let deleted = query
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?;
+        match deleted {
+            Some(admin) => Ok(admin),
+            None => Err(admin_rejection(&mut conn, key, None).await),
+        }
+    }
+}
+
+/// Explains why a guarded update or delete of the admin matched by `key` matched no row;
+/// `version` is the version an update was based on.
+async fn admin_rejection(
+    conn: &mut PgConnection,
+    key: AdminBy,
+    version: Option<i64>,
+) -> DatabaseError {
+    let missing = format!("admin {key:?}");
+    match AdminRepository::try_get(&mut *conn, key).await {
+        Err(e) => e,
+        Ok(None) => DatabaseError::NotFound(missing),
+        Ok(Some(admin)) if version.is_some_and(|version| version != admin.version) => {
+            DatabaseError::Conflict {
+                current_version: admin.version,
+            }
+        }
+        Ok(Some(_)) => DatabaseError::DefaultAdminProtected,
+    }
+}
+
+#[async_trait::async_trait]
+impl CredentialsRepository<Postgres, AdminDAO> for AdminRepository {
+    #[tracing::instrument(
+        name = "repository",
+        level = "debug",
+        skip_all,
+        fields(entity = "admin", operation = "verify_password"),
+        err(Debug, level = "warn")
+    )]
+    async fn verify_password<'c, A>(
+        db: A,
+        organization_id: Uuid,
+        email: &str,
+        candidate: &str,
+    ) -> Result<Option<AdminDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let stored = sqlx::query_as::<_, (Uuid, String)>(
+            "SELECT id, password_hash FROM admins WHERE organization_id = $1 AND email = $2 AND deleted_at IS NULL LIMIT 1",
+        )
+        .bind(organization_id)
+        .bind(normalize_email(email))
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)?;
+        let Some((id, stored)) = stored else {
+            password::verify_nothing(candidate.to_string()).await?;
+            return Ok(None);
+        };
+
+        match password::verify(stored.clone(), candidate.to_string()).await? {
+            Verification::Invalid => return Ok(None),
+            Verification::Valid => {}
+            Verification::ValidNeedsRehash => {
+                let rehashed = password::hash(candidate.to_string()).await?;
+                // Same password, new hash: not a change callers need to see in `version`
+                sqlx::query(
+                    "UPDATE admins SET password_hash = $2 WHERE id = $1 AND password_hash = $3",
+                )
+                .bind(id)
+                .bind(rehashed)
+                .bind(stored)
+                .execute(&mut *conn)
+                .await
+                .map_err(DatabaseError::from)?;
+            }
+        }
+
+        AdminRepository::get(&mut *conn, AdminBy::Id(id))
+            .await
+            .map(Some)
+    }
+}
+
+#[async_trait::async_trait]
+impl DefaultAdminTransfer<Postgres> for AdminRepository {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "admin", operation = "transfer_default", key = ?to), err(Debug, level = "warn"))]
+    async fn transfer_default<'c, A>(db: A, to: AdminBy) -> Result<AdminDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut tx = db.begin().await.map_err(DatabaseError::from)?;
+        let admin = AdminRepository::get(&mut tx, to).await?;
+        // Demote first, the partial unique index allows a single default per organization.
+        sqlx::query(
+            "UPDATE admins SET is_default = false, version = version + 1 WHERE organization_id = $1 AND is_default AND id <> $2",
+        )
+        .bind(admin.organization_id)
+        .bind(admin.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(DatabaseError::from)?;
+        let admin = sqlx::query_as::<_, AdminDAO>(
+            "UPDATE admins SET is_default = true, version = version + 1 WHERE id = $1 RETURNING id, organization_id, email, is_default, created_at, updated_at, version",
+        )
+        .bind(admin.id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(DatabaseError::from)?;
+        tx.commit().await.map_err(DatabaseError::from)?;
+        Ok(admin)
+    }
+}
@@ -0,0 +1,531 @@
+use chrono::{DateTime, Utc};
+use sqlx::{Acquire, QueryBuilder, Sqlite};
+use uuid::Uuid;
+
+use crate::{
+    entities::{version_rejection, SortOrder},
+    pagination::{push_after_sqlite, Cursor, CursorKey, Page},
+    traits::{DatabaseError, EntityRepository},
+};
+
+#[cfg(feature = "postgres")]
+pub mod postgres;
+
+#[derive(Debug, Clone)]
+pub enum CategoryBy {
+    Id(Uuid),
+}
+
+/// Filters used to list categories, by name. Filters left as `None` are not applied.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CategoriesWhere {
+    pub organization_id: Option<Uuid>,
+    /// Only the direct subcategories of this category.
+    pub parent_id: Option<Uuid>,
+    /// Only the categories without a parent.
+    pub roots: bool,
+    pub limit: i32,
+    /// Resume after the last row of a previous page.
+    pub after: Option<Cursor>,
+}
+
+impl Default for CategoriesWhere {
+    fn default() -> Self {
+        Self {
+            organization_id: None,
+            parent_id: None,
+            roots: false,
+            limit: 100,
+            after: None,
+        }
+    }
+}
+
+pub(crate) fn category_cursor(category: &CategoryDAO) -> Cursor {
+    Cursor::new(
+        "name",
+        SortOrder::Ascending,
+        CursorKey::Text(category.name.clone()),
+        category.id,
+    )
+}
+
+#[derive(sqlx::FromRow, Debug, PartialEq, Eq, Clone, serde::Serialize)]
+pub struct CategoryDAO {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    /// The category this one is a subcategory of; `None` for a top-level category.
+    pub parent_id: Option<Uuid>,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub version: i64,
+}
+
+#[derive(sqlx::FromRow, Debug, PartialEq, Eq, Clone)]
+pub struct NewCategoryDAO {
+    pub organization_id: Uuid,
+    /// A category of the same organization.
+    pub parent_id: Option<Uuid>,
+    pub name: String,
+}
+
+#[derive(sqlx::FromRow, Debug, PartialEq, Eq, Clone)]
+pub struct UpdateCategoryDAO {
+    /// Moving a category under itself or one of its subcategories fails with
+    /// [`DatabaseError::CategoryCycle`].
+    pub parent_id: Option<Uuid>,
+    pub name: String,
+    /// Version of the row the update is based on; the update fails with
+    /// [`DatabaseError::Conflict`] when the row changed since.
+    pub version: i64,
+}
+
+/// Categories are not soft-deleted: `delete` removes the row, and fails with
+/// [`DatabaseError::ForeignKeyViolation`] while products or subcategories still reference it.
+#[derive(Debug)]
+pub struct CategoryRepository;
+
+/// Builds the query of `get_all`; kept apart so the query plan tests can check it.
+pub(crate) fn list_query(
+    key: &CategoriesWhere,
+) -> Result<QueryBuilder<'static, Sqlite>, DatabaseError> {
+    let mut query = QueryBuilder::<Sqlite>::new(
+        "SELECT id, organization_id, parent_id, name, created_at, updated_at, version FROM categories WHERE 1 = 1",
+    );
+    if let Some(organization_id) = key.organization_id {
+        query
+            .push(" AND organization_id = ")
+            .push_bind(organization_id);
+    }
+    if let Some(parent_id) = key.parent_id {
+        query.push(" AND parent_id = ").push_bind(parent_id);
+    }
+    if key.roots {
+        query.push(" AND parent_id IS NULL");
+    }
+    if let Some(after) = &key.after {
+        push_after_sqlite(&mut query, "name", SortOrder::Ascending, after)?;
+    }
+    query
+        .push(" ORDER BY name, id LIMIT ")
+        .push_bind(i64::from(key.limit) + 1);
+    Ok(query)
+}
+
+#[async_trait::async_trait]
+impl
+    EntityRepository<
+        Sqlite,
+        CategoryDAO,
+        NewCategoryDAO,
+        UpdateCategoryDAO,
+        CategoryBy,
+        CategoriesWhere,
+    > for CategoryRepository
+{
+    #[tracing::instrument(
+        name = "repository",
+        level = "debug",
+        skip_all,
+        fields(entity = "category", operation = "insert"),
+        err(Debug, level = "warn")
+    )]
+    async fn insert<'c, A>(db: A, input: NewCategoryDAO) -> Result<CategoryDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let uuid = Uuid::new_v4();
+        sqlx::query_as::<_, CategoryDAO>(
+            "INSERT INTO categories (id, organization_id, parent_id, name) VALUES ($1, $2, $3, $4) RETURNING id, organization_id, parent_id, name, created_at, updated_at, version",
+        )
+        .bind(uuid)
+        .bind(input.organization_id)
+        .bind(input.parent_id)
+        .bind(input.name)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "category", operation = "get", key = ?key), err(Debug, level = "warn"))]
+    async fn get<'c, A>(db: A, key: CategoryBy) -> Result<CategoryDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        match key {
+            CategoryBy::Id(uuid) => sqlx::query_as::<_, CategoryDAO>(
+                "SELECT id, organization_id, parent_id, name, created_at, updated_at, version FROM categories WHERE id = $1 LIMIT 1",
+            )
+            .bind(uuid)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from),
+        }
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "category", operation = "try_get", key = ?key), err(Debug, level = "warn"))]
+    async fn try_get<'c, A>(db: A, key: CategoryBy) -> Result<Option<CategoryDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        match key {
+            CategoryBy::Id(uuid) => sqlx::query_as::<_, CategoryDAO>(
+                "SELECT id, organization_id, parent_id, name, created_at, updated_at, version FROM categories WHERE id = $1 LIMIT 1",
+            )
+            .bind(uuid)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(DatabaseError::from),
+        }
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "category", operation = "get_all", key = ?key), err(Debug, level = "warn"))]
+    async fn get_all<'c, A>(db: A, key: CategoriesWhere) -> Result<Page<CategoryDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let mut query = list_query(&key)?;
+        let rows = query
+            .build_query_as::<CategoryDAO>()
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?;
+        Ok(Page::from_rows(rows, key.limit, category_cursor))
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "category", operation = "update", key = ?key), err(Debug, level = "warn"))]
+    async fn update<'c, A>(
+        db: A,
+        key: CategoryBy,
+        input: UpdateCategoryDAO,
+    ) -> Result<CategoryDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        // The cycle check and the update see the same tree.
+        let mut tx = db.begin().await.map_err(DatabaseError::from)?;
+        let CategoryBy::Id(uuid) = &key;
+        if let Some(parent_id) = input.parent_id {
+            let (cycle,) = sqlx::query_as::<_, (bool,)>(
+                "SELECT EXISTS (WITH RECURSIVE ancestors(id, parent_id) AS (SELECT id, parent_id FROM categories WHERE id = $2 UNION ALL SELECT categories.id, categories.parent_id FROM categories JOIN ancestors ON categories.id = ancestors.parent_id) SELECT 1 FROM ancestors WHERE id = $1)",
+            )
+            .bind(*uuid)
+            .bind(parent_id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(DatabaseError::from)?;
+            if cycle {
+                return Err(DatabaseError::CategoryCycle);
+            }
+        }
+        let updated = sqlx::query_as::<_, CategoryDAO>(
+            "UPDATE categories SET parent_id = $2, name = $3, updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE id = $1 AND version = $4 RETURNING id, organization_id, parent_id, name, created_at, updated_at, version",
+        )
+        .bind(*uuid)
+        .bind(input.parent_id)
+        .bind(input.name)
+        .bind(input.version)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(DatabaseError::from)?;
+        match updated {
+            Some(category) => {
+                tx.commit().await.map_err(DatabaseError::from)?;
+                Ok(category)
+            }
+            None => {
+                let missing = format!("category {key:?}");
+                let current = Self::try_get(&mut *tx, key).await;
+                Err(version_rejection(
+                    current.map(|category| category.map(|category| category.version)),
+                    missing,
+                ))
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "category", operation = "delete", key = ?key), err(Debug, level = "warn"))]
+    async fn delete<'c, A>(db: A, key: CategoryBy) -> Result<CategoryDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        // sqlx steps a statement again after SQLite reports an error, which re-runs the DELETE;
+        // rolling back keeps a category still in use from being removed later on.
+        let mut tx = db.begin().await.map_err(DatabaseError::from)?;
+        let category = match key {
+            CategoryBy::Id(uuid) => sqlx::query_as::<_, CategoryDAO>(
+                "DELETE FROM categories WHERE id = $1 RETURNING id, organization_id, parent_id, name, created_at, updated_at, version",
+            )
+            .bind(uuid)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(DatabaseError::from)?,
+        };
+        tx.commit().await.map_err(DatabaseError::from)?;
+        Ok(category)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::{Database, Pool};
+
+    use super::*;
+    use crate::{
+        entities::{
+            organization::{
+                NewOrganizationDAO, OrganizationBy, OrganizationDAO, OrganizationRepository,
+                OrganizationsWhere, UpdateOrganizationDAO,
+            },
+            product::{
+                NewProductDAO, ProductBy, ProductDAO, ProductRepository, ProductsWhere,
+                UpdateProductDAO,
+            },
+        },
+        money::{Currency, Money},
+        sqlite::DatabaseRepository,
+    };
+
+    async fn create_organization<DB: Database>(pool: &Pool<DB>, name: &str) -> OrganizationDAO
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+    {
+        OrganizationRepository::insert(
+            pool,
+            NewOrganizationDAO {
+                name: name.to_string(),
+                reporting_currency: Currency::USD,
+            },
+        )
+        .await
+        .expect("Could not create organization")
+    }
+
+    async fn tree<DB: Database>(db: &Pool<DB>)
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+        CategoryRepository: EntityRepository<
+            DB,
+            CategoryDAO,
+            NewCategoryDAO,
+            UpdateCategoryDAO,
+            CategoryBy,
+            CategoriesWhere,
+        >,
+        ProductRepository: EntityRepository<
+            DB,
+            ProductDAO,
+            NewProductDAO,
+            UpdateProductDAO,
+            ProductBy,
+            ProductsWhere,
+        >,
+    {
+        let organization = create_organization(db, "shop").await;
+        let other = create_organization(db, "other").await;
+        let mut categories = vec![];
+        for (name, parent) in [
+            ("Electronics", None),
+            ("Phones", Some(0)),
+            ("Accessories", Some(1)),
+            ("Books", None),
+        ] {
+            let category = CategoryRepository::insert(
+                db,
+                NewCategoryDAO {
+                    organization_id: organization.id,
+                    parent_id: parent.map(|index: usize| categories[index]),
+                    name: name.to_string(),
+                },
+            )
+            .await
+            .expect("Could not create a category");
+            categories.push(category.id);
+        }
+        let [electronics, phones, accessories, books] = categories[..] else {
+            unreachable!()
+        };
+
+        let category = CategoryRepository::get(db, CategoryBy::Id(accessories))
+            .await
+            .expect("Could not find category");
+        assert_eq!(category.parent_id, Some(phones));
+        assert_eq!(category.name, "Accessories");
+
+        let names = |page: Page<CategoryDAO>| {
+            page.items
+                .into_iter()
+                .map(|category| category.name)
+                .collect::<Vec<String>>()
+        };
+        let roots = CategoryRepository::get_all(
+            db,
+            CategoriesWhere {
+                organization_id: Some(organization.id),
+                roots: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list categories");
+        assert_eq!(names(roots), vec!["Books", "Electronics"]);
+        let first = CategoryRepository::get_all(
+            db,
+            CategoriesWhere {
+                organization_id: Some(organization.id),
+                limit: 3,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list categories");
+        let after = first.next_cursor.clone();
+        assert_eq!(names(first), vec!["Accessories", "Books", "Electronics"]);
+        let second = CategoryRepository::get_all(
+            db,
+            CategoriesWhere {
+                organization_id: Some(organization.id),
+                limit: 3,
+                after,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list categories");
+        assert_eq!(second.next_cursor, None);
+        assert_eq!(names(second), vec!["Phones"]);
+
+        // Parents belong to the same organization
+        let foreign = CategoryRepository::insert(
+            db,
+            NewCategoryDAO {
+                organization_id: other.id,
+                parent_id: Some(electronics),
+                name: "Phones".to_string(),
+            },
+        )
+        .await;
+        assert!(matches!(
+            foreign,
+            Err(DatabaseError::ForeignKeyViolation { .. })
+        ));
+
+        // A category cannot end up below itself
+        let electronics_row = CategoryRepository::get(db, CategoryBy::Id(electronics))
+            .await
+            .expect("Could not find category");
+        for parent_id in [electronics, accessories] {
+            let cycle = CategoryRepository::update(
+                db,
+                CategoryBy::Id(electronics),
+                UpdateCategoryDAO {
+                    parent_id: Some(parent_id),
+                    name: "Electronics".to_string(),
+                    version: electronics_row.version,
+                },
+            )
+            .await;
+            assert_eq!(cycle, Err(DatabaseError::CategoryCycle));
+        }
+        let moved = CategoryRepository::update(
+            db,
+            CategoryBy::Id(accessories),
+            UpdateCategoryDAO {
+                parent_id: Some(electronics),
+                name: "Gadgets".to_string(),
+                version: category.version,
+            },
+        )
+        .await
+        .expect("Could not move category");
+        assert_eq!(moved.parent_id, Some(electronics));
+        assert_eq!(moved.version, category.version + 1);
+        let stale = CategoryRepository::update(
+            db,
+            CategoryBy::Id(accessories),
+            UpdateCategoryDAO {
+                parent_id: None,
+                name: "Gadgets".to_string(),
+                version: category.version,
+            },
+        )
+        .await;
+        assert_eq!(
+            stale,
+            Err(DatabaseError::Conflict {
+                current_version: moved.version
+            })
+        );
+
+        // Categories in use cannot be removed
+        let product = ProductRepository::insert(
+            db,
+            NewProductDAO {
+                organization_id: organization.id,
+                category_id: Some(books),
+                sku: None,
+                barcode: None,
+                name: "Novel".to_string(),
+                description: "paperback".to_string(),
+                amount: 1,
+                price: Money::new(1500, Currency::USD),
+            },
+        )
+        .await
+        .expect("Could not create a product");
+        for category in [electronics, books] {
+            let in_use = CategoryRepository::delete(db, CategoryBy::Id(category)).await;
+            assert!(matches!(
+                in_use,
+                Err(DatabaseError::ForeignKeyViolation { .. })
+            ));
+        }
+        let deleted = CategoryRepository::delete(db, CategoryBy::Id(accessories))
+            .await
+            .expect("Could not delete category");
+        assert_eq!(deleted, moved);
+        let missing = CategoryRepository::try_get(db, CategoryBy::Id(accessories))
+            .await
+            .expect("Could not look up category");
+        assert!(missing.is_none());
+        let product = ProductRepository::get(db, ProductBy::Id(product.id))
+            .await
+            .expect("Could not find product");
+        assert_eq!(product.category_id, Some(books));
+    }
+
+    #[tokio::test]
+    async fn sqlite_tree() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        tree(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_tree() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            tree(&db.connection).await;
+        }
+    }
+}
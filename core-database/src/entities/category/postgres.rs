@@ -0,0 +1,186 @@
+use sqlx::{Acquire, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use super::{
+    category_cursor, CategoriesWhere, CategoryBy, CategoryDAO, CategoryRepository, NewCategoryDAO,
+    UpdateCategoryDAO,
+};
+use crate::{
+    entities::{version_rejection, SortOrder},
+    pagination::{push_after_postgres, Page},
+    traits::{DatabaseError, EntityRepository},
+};
+
+#[async_trait::async_trait]
+impl
+    EntityRepository<
+        Postgres,
+        CategoryDAO,
+        NewCategoryDAO,
+        UpdateCategoryDAO,
+        CategoryBy,
+        CategoriesWhere,
+    > for CategoryRepository
+{
+    #[tracing::instrument(
+        name = "repository",
+        level = "debug",
+        skip_all,
+        fields(entity = "category", operation = "insert"),
+        err(Debug, level = "warn")
+    )]
+    async fn insert<'c, A>(db: A, input: NewCategoryDAO) -> Result<CategoryDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let uuid = Uuid::new_v4();
+        sqlx::query_as::<_, CategoryDAO>(
+            "INSERT INTO categories (id, organization_id, parent_id, name) VALUES ($1, $2, $3, $4) RETURNING id, organization_id, parent_id, name, created_at, updated_at, version",
+        )
+        .bind(uuid)
+        .bind(input.organization_id)
+        .bind(input.parent_id)
+        .bind(input.name)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "category", operation = "get", key = ?key), err(Debug, level = "warn"))]
+    async fn get<'c, A>(db: A, key: CategoryBy) -> Result<CategoryDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        match key {
+            CategoryBy::Id(uuid) => sqlx::query_as::<_, CategoryDAO>(
+                "SELECT id, organization_id, parent_id, name, created_at, updated_at, version FROM categories WHERE id = $1 LIMIT 1",
+            )
+            .bind(uuid)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from),
+        }
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "category", operation = "try_get", key = ?key), err(Debug, level = "warn"))]
+    async fn try_get<'c, A>(db: A, key: CategoryBy) -> Result<Option<CategoryDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        match key {
+            CategoryBy::Id(uuid) => sqlx::query_as::<_, CategoryDAO>(
+                "SELECT id, organization_id, parent_id, name, created_at, updated_at, version FROM categories WHERE id = $1 LIMIT 1",
+            )
+            .bind(uuid)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(DatabaseError::from),
+        }
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "category", operation = "get_all", key = ?key), err(Debug, level = "warn"))]
+    async fn get_all<'c, A>(db: A, key: CategoriesWhere) -> Result<Page<CategoryDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let mut query = QueryBuilder::<Postgres>::new(
+            "SELECT id, organization_id, parent_id, name, created_at, updated_at, version FROM categories WHERE 1 = 1",
+        );
+        if let Some(organization_id) = key.organization_id {
+            query
+                .push(" AND organization_id = ")
+                .push_bind(organization_id);
+        }
+        if let Some(parent_id) = key.parent_id {
+            query.push(" AND parent_id = ").push_bind(parent_id);
+        }
+        if key.roots {
+            query.push(" AND parent_id IS NULL");
+        }
+        if let Some(after) = &key.after {
+            push_after_postgres(&mut query, "name", SortOrder::Ascending, after)?;
+        }
+        query
+            .push(" ORDER BY name, id LIMIT ")
+            .push_bind(i64::from(key.limit) + 1);
+
+        let rows = query
+            .build_query_as::<CategoryDAO>()
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?;
+        Ok(Page::from_rows(rows, key.limit, category_cursor))
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "category", operation = "update", key = ?key), err(Debug, level = "warn"))]
+    async fn update<'c, A>(
+        db: A,
+        key: CategoryBy,
+        input: UpdateCategoryDAO,
+    ) -> Result<CategoryDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        // The cycle check and the update see the same tree.
+        let mut tx = db.begin().await.map_err(DatabaseError::from)?;
+        let CategoryBy::Id(uuid) = &key;
+        if let Some(parent_id) = input.parent_id {
+            let (cycle,) = sqlx::query_as::<_, (bool,)>(
+                "SELECT EXISTS (WITH RECURSIVE ancestors(id, parent_id) AS (SELECT id, parent_id FROM categories WHERE id = $2 UNION ALL SELECT categories.id, categories.parent_id FROM categories JOIN ancestors ON categories.id = ancestors.parent_id) SELECT 1 FROM ancestors WHERE id = $1)",
+            )
+            .bind(*uuid)
+            .bind(parent_id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(DatabaseError::from)?;
+            if cycle {
+                return Err(DatabaseError::CategoryCycle);
+            }
+        }
+        let updated = sqlx::query_as::<_, CategoryDAO>(
+            "UPDATE categories SET parent_id = $2, name = $3, version = version + 1 WHERE id = $1 AND version = $4 RETURNING id, organization_id, parent_id, name, created_at, updated_at, version",
+        )
+        .bind(*uuid)
+        .bind(input.parent_id)
+        .bind(input.name)
+        .bind(input.version)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(DatabaseError::from)?;
+        match updated {
+            Some(category) => {
+                tx.commit().await.map_err(DatabaseError::from)?;
+                Ok(category)
+            }
+            None => {
+                let missing = format!("category {key:?}");
+                let current = Self::try_get(&mut *tx, key).await;
+                Err(version_rejection(
+                    current.map(|category| category.map(|category| category.version)),
+                    missing,
+                ))
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "category", operation = "delete", key = ?key), err(Debug, level = "warn"))]
+    async fn delete<'c, A>(db: A, key: CategoryBy) -> Result<CategoryDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        match key {
+            CategoryBy::Id(uuid) => sqlx::query_as::<_, CategoryDAO>(
+                "DELETE FROM categories WHERE id = $1 RETURNING id, organization_id, parent_id, name, created_at, updated_at, version",
+            )
+            .bind(uuid)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from),
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use chrono::{DateTime, Utc};
+use sqlx::{Acquire, Database, Sqlite};
+
+use crate::{
+    money::{Currency, Rate},
+    sqlite,
+    traits::DatabaseError,
+};
+
+#[cfg(feature = "postgres")]
+pub mod postgres;
+
+#[derive(sqlx::FromRow, Debug, PartialEq, Eq, Clone, serde::Serialize)]
+pub struct ExchangeRateDAO {
+    pub from_currency: Currency,
+    pub to_currency: Currency,
+    /// The rate applies from this instant until the next rate of the same currency pair.
+    pub effective_at: DateTime<Utc>,
+    pub rate: Rate,
+}
+
+#[derive(Debug)]
+pub struct ExchangeRateRepository;
+
+/// Exchange rates are only ever added: a rate is superseded by a newer one of the same pair,
+/// so reports over past sales keep converting at the rates of their time.
+#[async_trait::async_trait]
+pub trait ExchangeRates<DB: Database> {
+    /// Records a rate. Fails with `UniqueViolation` when the pair already has a rate effective
+    /// at the same instant.
+    async fn insert<'c, A>(db: A, input: ExchangeRateDAO) -> Result<ExchangeRateDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = DB> + Send;
+
+    /// The rate of the pair in effect at `at`, i.e. the latest one effective at or before it.
+    async fn rate_at<'c, A>(
+        db: A,
+        from_currency: Currency,
+        to_currency: Currency,
+        at: DateTime<Utc>,
+    ) -> Result<Option<ExchangeRateDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = DB> + Send;
+
+    /// Every rate of the pair, newest first.
+    async fn history<'c, A>(
+        db: A,
+        from_currency: Currency,
+        to_currency: Currency,
+    ) -> Result<Vec<ExchangeRateDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = DB> + Send;
+}
+
+#[async_trait::async_trait]
+impl ExchangeRates<Sqlite> for ExchangeRateRepository {
+    #[tracing::instrument(
+        name = "repository",
+        level = "debug",
+        skip_all,
+        fields(entity = "exchange_rate", operation = "insert"),
+        err(Debug, level = "warn")
+    )]
+    async fn insert<'c, A>(db: A, input: ExchangeRateDAO) -> Result<ExchangeRateDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        sqlx::query_as::<_, ExchangeRateDAO>(
+            "INSERT INTO exchange_rates (from_currency, to_currency, effective_at, rate) VALUES ($1, $2, $3, $4) RETURNING from_currency, to_currency, effective_at, rate",
+        )
+        .bind(input.from_currency)
+        .bind(input.to_currency)
+        .bind(sqlite::timestamp(input.effective_at))
+        .bind(input.rate)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "exchange_rate", operation = "rate_at", key = ?(from_currency, to_currency, at)), err(Debug, level = "warn"))]
+    async fn rate_at<'c, A>(
+        db: A,
+        from_currency: Currency,
+        to_currency: Currency,
+        at: DateTime<Utc>,
+    ) -> Result<Option<ExchangeRateDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        sqlx::query_as::<_, ExchangeRateDAO>(
+            "SELECT from_currency, to_currency, effective_at, rate FROM exchange_rates WHERE from_currency = $1 AND to_currency = $2 AND effective_at <= $3 ORDER BY effective_at DESC LIMIT 1",
+        )
+        .bind(from_currency)
+        .bind(to_currency)
+        .bind(sqlite::timestamp(at))
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "exchange_rate", operation = "history", key = ?(from_currency, to_currency)), err(Debug, level = "warn"))]
+    async fn history<'c, A>(
+        db: A,
+        from_currency: Currency,
+        to_currency: Currency,
+    ) -> Result<Vec<ExchangeRateDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        sqlx::query_as::<_, ExchangeRateDAO>(
+            "SELECT from_currency, to_currency, effective_at, rate FROM exchange_rates WHERE from_currency = $1 AND to_currency = $2 ORDER BY effective_at DESC",
+        )
+        .bind(from_currency)
+        .bind(to_currency)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone};
+    use sqlx::Pool;
+
+    use super::*;
+    use crate::sqlite::DatabaseRepository;
+
+    async fn rates<DB: Database>(db: &Pool<DB>)
+    where
+        ExchangeRateRepository: ExchangeRates<DB>,
+    {
+        let start =
+            Utc.with_ymd_and_hms(2023, 7, 1, 0, 0, 0).unwrap() + Duration::microseconds(123_456);
+        for (days, rate) in [(0, "1.08"), (10, "1.1"), (20, "1.09")] {
+            ExchangeRateRepository::insert(
+                db,
+                ExchangeRateDAO {
+                    from_currency: Currency::EUR,
+                    to_currency: Currency::USD,
+                    effective_at: start + Duration::days(days),
+                    rate: rate.parse().unwrap(),
+                },
+            )
+            .await
+            .expect("Could not insert rate");
+        }
+
+        let rate_at = |at| ExchangeRateRepository::rate_at(db, Currency::EUR, Currency::USD, at);
+        let rate = |rate: Option<ExchangeRateDAO>| rate.map(|r| r.rate.to_string());
+        assert_eq!(
+            rate(rate_at(start - Duration::seconds(1)).await.unwrap()),
+            None
+        );
+        assert_eq!(
+            rate(rate_at(start).await.unwrap()),
+            Some("1.08".to_string())
+        );
+        assert_eq!(
+            rate(rate_at(start + Duration::days(15)).await.unwrap()),
+            Some("1.1".to_string())
+        );
+        assert_eq!(
+            rate(rate_at(start + Duration::days(400)).await.unwrap()),
+            Some("1.09".to_string())
+        );
+        let inverse =
+            ExchangeRateRepository::rate_at(db, Currency::USD, Currency::EUR, start).await;
+        assert_eq!(inverse, Ok(None));
+
+        let history = ExchangeRateRepository::history(db, Currency::EUR, Currency::USD)
+            .await
+            .expect("Could not list rates");
+        assert_eq!(
+            history.iter().map(|r| r.effective_at).collect::<Vec<_>>(),
+            vec![
+                start + Duration::days(20),
+                start + Duration::days(10),
+                start
+            ]
+        );
+
+        let duplicate = ExchangeRateRepository::insert(
+            db,
+            ExchangeRateDAO {
+                from_currency: Currency::EUR,
+                to_currency: Currency::USD,
+                effective_at: start,
+                rate: Rate::PAR,
+            },
+        )
+        .await;
+        assert!(matches!(
+            duplicate,
+            Err(DatabaseError::UniqueViolation { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn sqlite_rates() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        rates(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_rates() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            rates(&db.connection).await;
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use chrono::{DateTime, Utc};
+use sqlx::{Acquire, Postgres};
+
+use super::{ExchangeRateDAO, ExchangeRateRepository, ExchangeRates};
+use crate::{money::Currency, traits::DatabaseError};
+
+#[async_trait::async_trait]
+impl ExchangeRates<Postgres> for ExchangeRateRepository {
+    #[tracing::instrument(
+        name = "repository",
+        level = "debug",
+        skip_all,
+        fields(entity = "exchange_rate", operation = "insert"),
+        err(Debug, level = "warn")
+    )]
+    async fn insert<'c, A>(db: A, input: ExchangeRateDAO) -> Result<ExchangeRateDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        sqlx::query_as::<_, ExchangeRateDAO>(
+            "INSERT INTO exchange_rates (from_currency, to_currency, effective_at, rate) VALUES ($1, $2, $3, $4) RETURNING from_currency, to_currency, effective_at, rate",
+        )
+        .bind(input.from_currency)
+        .bind(input.to_currency)
+        .bind(input.effective_at)
+        .bind(input.rate)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "exchange_rate", operation = "rate_at", key = ?(from_currency, to_currency, at)), err(Debug, level = "warn"))]
+    async fn rate_at<'c, A>(
+        db: A,
+        from_currency: Currency,
+        to_currency: Currency,
+        at: DateTime<Utc>,
+    ) -> Result<Option<ExchangeRateDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        sqlx::query_as::<_, ExchangeRateDAO>(
+            "SELECT from_currency, to_currency, effective_at, rate FROM exchange_rates WHERE from_currency = $1 AND to_currency = $2 AND effective_at <= $3 ORDER BY effective_at DESC LIMIT 1",
+        )
+        .bind(from_currency)
+        .bind(to_currency)
+        .bind(at)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "exchange_rate", operation = "history", key = ?(from_currency, to_currency)), err(Debug, level = "warn"))]
+    async fn history<'c, A>(
+        db: A,
+        from_currency: Currency,
+        to_currency: Currency,
+    ) -> Result<Vec<ExchangeRateDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        sqlx::query_as::<_, ExchangeRateDAO>(
+            "SELECT from_currency, to_currency, effective_at, rate FROM exchange_rates WHERE from_currency = $1 AND to_currency = $2 ORDER BY effective_at DESC",
+        )
+        .bind(from_currency)
+        .bind(to_currency)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)
+    }
+}
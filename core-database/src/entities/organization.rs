@@ -1,44 +1,99 @@
-use sqlx::{Pool, Sqlite};
+use chrono::{DateTime, Utc};
+use sqlx::{Acquire, Database, QueryBuilder, Sqlite, SqliteConnection};
 use uuid::Uuid;
 
-use crate::traits::{DatabaseError, EntityRepository};
+use crate::{
+    entities::{version_rejection, SortOrder},
+    money::Currency,
+    pagination::{push_after_sqlite, Cursor, CursorKey, Page},
+    traits::{DatabaseError, EntityRepository, SoftDeleteRepository},
+};
 
-#[derive(Debug)]
+#[cfg(feature = "postgres")]
+pub mod postgres;
+
+#[derive(Debug, Clone)]
 pub enum OrganizationBy {
     Id(Uuid),
     Name(String),
 }
 
+/// Organizations are listed by name.
 #[derive(Debug)]
 pub enum OrganizationsWhere {
     Active {
         active: bool,
         limit: i32,
-        offset: i32,
+        /// Resume after the last row of a previous page.
+        after: Option<Cursor>,
     },
 }
 
-#[derive(sqlx::FromRow, Debug, PartialEq, Eq, Clone)]
+pub(crate) fn organization_cursor(organization: &OrganizationDAO) -> Cursor {
+    Cursor::new(
+        "name",
+        SortOrder::Ascending,
+        CursorKey::Text(organization.name.clone()),
+        organization.id,
+    )
+}
+
+#[derive(sqlx::FromRow, Debug, PartialEq, Eq, Clone, serde::Serialize)]
 pub struct OrganizationDAO {
     pub id: Uuid,
     pub name: String,
     pub active: bool,
+    /// Currency the sales reports of the organization are converted into.
+    pub reporting_currency: Currency,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub version: i64,
 }
 
 #[derive(sqlx::FromRow, Debug, PartialEq, Eq, Clone)]
 pub struct NewOrganizationDAO {
     pub name: String,
+    pub reporting_currency: Currency,
 }
 
 #[derive(sqlx::FromRow, Debug, PartialEq, Eq, Clone)]
 pub struct UpdateOrganizationDAO {
     pub name: String,
     pub active: bool,
+    pub reporting_currency: Currency,
+    /// Version of the row the update is based on; the update fails with
+    /// [`DatabaseError::Conflict`] when the row changed since.
+    pub version: i64,
 }
 
 #[derive(Debug)]
 pub struct OrganizationRepository;
 
+/// Builds the query of `get_all`; kept apart so the query plan tests can check it.
+pub(crate) fn list_query(
+    key: &OrganizationsWhere,
+) -> Result<QueryBuilder<'static, Sqlite>, DatabaseError> {
+    match key {
+        OrganizationsWhere::Active {
+            active,
+            limit,
+            after,
+        } => {
+            let mut query = QueryBuilder::<Sqlite>::new(
+                "SELECT id, name, active, reporting_currency, created_at, updated_at, version FROM organizations WHERE deleted_at IS NULL AND active = ",
+            );
+            query.push_bind(*active);
+            if let Some(after) = after {
+                push_after_sqlite(&mut query, "name", SortOrder::Ascending, after)?;
+            }
+            query
+                .push(" ORDER BY name, id LIMIT ")
+                .push_bind(i64::from(*limit) + 1);
+            Ok(query)
+        }
+    }
+}
+
 #[async_trait::async_trait]
 impl
     EntityRepository<
@@ -50,134 +105,374 @@ impl
         OrganizationsWhere,
     > for OrganizationRepository
 {
-    async fn insert(
-        db: &Pool<Sqlite>,
+    #[tracing::instrument(
+        name = "repository",
+        level = "debug",
+        skip_all,
+        fields(entity = "organization", operation = "insert"),
+        err(Debug, level = "warn")
+    )]
+    async fn insert<'c, A>(
+        db: A,
         input: NewOrganizationDAO,
-    ) -> Result<OrganizationDAO, DatabaseError> {
+    ) -> Result<OrganizationDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
         let uuid = Uuid::new_v4();
         sqlx::query_as::<_, OrganizationDAO>(
-            "INSERT INTO organizations (id, name) VALUES ($1, $2) RETURNING id, name, active",
+            "INSERT INTO organizations (id, name, reporting_currency) VALUES ($1, $2, $3) RETURNING id, name, active, reporting_currency, created_at, updated_at, version",
         )
         .bind(uuid)
         .bind(input.name)
-        .fetch_one(db)
+        .bind(input.reporting_currency)
+        .fetch_one(&mut *conn)
         .await
         .map_err(DatabaseError::from)
     }
 
-    async fn get(db: &Pool<Sqlite>, key: OrganizationBy) -> Result<OrganizationDAO, DatabaseError> {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "organization", operation = "get", key = ?key), err(Debug, level = "warn"))]
+    async fn get<'c, A>(db: A, key: OrganizationBy) -> Result<OrganizationDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
         match key {
             OrganizationBy::Id(uuid) => sqlx::query_as::<_, OrganizationDAO>(
-                "SELECT id, name, active FROM organizations WHERE id = $1 LIMIT 1",
+                "SELECT id, name, active, reporting_currency, created_at, updated_at, version FROM organizations WHERE id = $1 AND deleted_at IS NULL LIMIT 1",
             )
             .bind(uuid)
-            .fetch_one(db)
+            .fetch_one(&mut *conn)
             .await
             .map_err(DatabaseError::from),
             OrganizationBy::Name(name) => sqlx::query_as::<_, OrganizationDAO>(
-                "SELECT id, name, active FROM organizations WHERE name = $1 LIMIT 1",
+                "SELECT id, name, active, reporting_currency, created_at, updated_at, version FROM organizations WHERE name = $1 AND deleted_at IS NULL LIMIT 1",
             )
             .bind(name)
-            .fetch_one(db)
+            .fetch_one(&mut *conn)
             .await
             .map_err(DatabaseError::from),
         }
     }
 
-    async fn try_get(
-        db: &Pool<Sqlite>,
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "organization", operation = "try_get", key = ?key), err(Debug, level = "warn"))]
+    async fn try_get<'c, A>(
+        db: A,
         key: OrganizationBy,
-    ) -> Result<Option<OrganizationDAO>, DatabaseError> {
+    ) -> Result<Option<OrganizationDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
         match key {
             OrganizationBy::Id(uuid) => sqlx::query_as::<_, OrganizationDAO>(
-                "SELECT id, name, active FROM organizations WHERE id = $1 LIMIT 1",
+                "SELECT id, name, active, reporting_currency, created_at, updated_at, version FROM organizations WHERE id = $1 AND deleted_at IS NULL LIMIT 1",
             )
             .bind(uuid)
-            .fetch_optional(db)
+            .fetch_optional(&mut *conn)
             .await
             .map_err(DatabaseError::from),
             OrganizationBy::Name(name) => sqlx::query_as::<_, OrganizationDAO>(
-                "SELECT id, name, active FROM organizations WHERE name = $1 LIMIT 1",
+                "SELECT id, name, active, reporting_currency, created_at, updated_at, version FROM organizations WHERE name = $1 AND deleted_at IS NULL LIMIT 1",
             )
             .bind(name)
-            .fetch_optional(db)
+            .fetch_optional(&mut *conn)
             .await
             .map_err(DatabaseError::from),
         }
     }
 
-    async fn get_all(
-        db: &Pool<Sqlite>,
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "organization", operation = "get_all", key = ?key), err(Debug, level = "warn"))]
+    async fn get_all<'c, A>(
+        db: A,
         key: OrganizationsWhere,
-    ) -> Result<Vec<OrganizationDAO>, DatabaseError> {
-        match key {
-            OrganizationsWhere::Active {
-                active,
-                limit,
-                offset,
-            } => sqlx::query_as::<_, OrganizationDAO>(
-                "SELECT id, name, active FROM organizations WHERE active = $1 LIMIT $2 OFFSET $3",
-            )
-            .bind(active)
-            .bind(limit)
-            .bind(offset)
-            .fetch_all(db)
-            .await
-            .map_err(DatabaseError::from),
+    ) -> Result<Page<OrganizationDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        match &key {
+            OrganizationsWhere::Active { limit, .. } => {
+                let rows = list_query(&key)?
+                    .build_query_as::<OrganizationDAO>()
+                    .fetch_all(&mut *conn)
+                    .await
+                    .map_err(DatabaseError::from)?;
+                Ok(Page::from_rows(rows, *limit, |organization| {
+                    organization_cursor(organization)
+                }))
+            }
         }
     }
 
-    async fn update(
-        db: &Pool<Sqlite>,
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "organization", operation = "update", key = ?key), err(Debug, level = "warn"))]
+    async fn update<'c, A>(
+        db: A,
         key: OrganizationBy,
         input: UpdateOrganizationDAO,
-    ) -> Result<OrganizationDAO, DatabaseError> {
-        match key {
-            OrganizationBy::Id(uuid) => {
-                sqlx::query_as::<_, OrganizationDAO>("UPDATE organizations SET name = $2, active = $3 WHERE id = $1 RETURNING id, name, active")
-                    .bind(uuid)
-                    .bind(input.name)
-                    .bind(input.active)
-                    .fetch_one(db)
-                    .await
-                    .map_err(DatabaseError::from)
-            },
-            OrganizationBy::Name(_) => Err(DatabaseError::NotImplemented),
+    ) -> Result<OrganizationDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match &key {
+            OrganizationBy::Id(uuid) => sqlx::query_as::<_, OrganizationDAO>(
+                "UPDATE organizations SET name = $2, active = $3, reporting_currency = $4, updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE id = $1 AND version = $5 AND deleted_at IS NULL RETURNING id, name, active, reporting_currency, created_at, updated_at, version",
+            )
+            .bind(*uuid),
+            OrganizationBy::Name(name) => sqlx::query_as::<_, OrganizationDAO>(
+                "UPDATE organizations SET name = $2, active = $3, reporting_currency = $4, updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE name = $1 AND version = $5 AND deleted_at IS NULL RETURNING id, name, active, reporting_currency, created_at, updated_at, version",
+            )
+            .bind(name.clone()),
+        };
+        let updated = query
+            .bind(input.name)
+            .bind(input.active)
+            .bind(input.reporting_currency)
+            .bind(input.version)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?;
+        match updated {
+            Some(organization) => Ok(organization),
+            None => {
+                let missing = format!("organization {key:?}");
+                let current = Self::try_get(&mut *conn, key).await;
+                Err(version_rejection(
+                    current.map(|organization| organization.map(|o| o.version)),
+                    missing,
+                ))
+            }
         }
     }
 
-    async fn delete(
-        db: &Pool<Sqlite>,
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "organization", operation = "delete", key = ?key), err(Debug, level = "warn"))]
+    async fn delete<'c, A>(db: A, key: OrganizationBy) -> Result<OrganizationDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            OrganizationBy::Id(uuid) => sqlx::query_as::<_, OrganizationDAO>(
+                "UPDATE organizations SET deleted_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE id = $1 AND deleted_at IS NULL RETURNING id, name, active, reporting_currency, created_at, updated_at, version",
+            )
+            .bind(uuid),
+            OrganizationBy::Name(name) => sqlx::query_as::<_, OrganizationDAO>(
+                "UPDATE organizations SET deleted_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE name = $1 AND deleted_at IS NULL RETURNING id, name, active, reporting_currency, created_at, updated_at, version",
+            )
+            .bind(name),
+        };
+        query
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+    }
+}
+
+#[async_trait::async_trait]
+impl SoftDeleteRepository<Sqlite, OrganizationDAO, OrganizationBy> for OrganizationRepository {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "organization", operation = "restore", key = ?key), err(Debug, level = "warn"))]
+    async fn restore<'c, A>(db: A, key: OrganizationBy) -> Result<OrganizationDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            OrganizationBy::Id(uuid) => sqlx::query_as::<_, OrganizationDAO>(
+                "UPDATE organizations SET deleted_at = NULL, updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE id = $1 AND deleted_at IS NOT NULL RETURNING id, name, active, reporting_currency, created_at, updated_at, version",
+            )
+            .bind(uuid),
+            OrganizationBy::Name(name) => sqlx::query_as::<_, OrganizationDAO>(
+                "UPDATE organizations SET deleted_at = NULL, updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE name = $1 AND deleted_at IS NOT NULL RETURNING id, name, active, reporting_currency, created_at, updated_at, version",
+            )
+            .bind(name),
+        };
+        query
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "organization", operation = "purge", key = ?key), err(Debug, level = "warn"))]
+    async fn purge<'c, A>(db: A, key: OrganizationBy) -> Result<OrganizationDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        OrganizationRepository::delete_with(db, key, DeletePolicy::Refuse).await
+    }
+}
+
+/// What happens to the rows of an organization when it is deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeletePolicy {
+    /// Fail with [`DatabaseError::OrganizationInUse`] while admins, sellers, products, sales,
+    /// categories or tags reference the organization.
+    #[default]
+    Refuse,
+    /// Delete the organization's sales, products, categories, tags, sellers and admins along
+    /// with it.
+    Cascade,
+}
+
+#[async_trait::async_trait]
+pub trait OrganizationRemover<DB: Database> {
+    /// Looks up the organization `delete_with` would remove, whether it was soft-deleted or
+    /// not.
+    async fn get_removable<'c, A>(
+        db: A,
         key: OrganizationBy,
-    ) -> Result<OrganizationDAO, DatabaseError> {
-        match key {
+    ) -> Result<OrganizationDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = DB> + Send;
+
+    /// Permanently deletes an organization in a single transaction, handling the rows that
+    /// reference it according to `policy`. `SoftDeleteRepository::purge` uses
+    /// [`DeletePolicy::Refuse`].
+    async fn delete_with<'c, A>(
+        db: A,
+        key: OrganizationBy,
+        policy: DeletePolicy,
+    ) -> Result<OrganizationDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = DB> + Send;
+}
+
+/// Fails with [`DatabaseError::OrganizationInUse`] when any row still references the
+/// organization.
+async fn refuse_if_referenced(conn: &mut SqliteConnection, id: Uuid) -> Result<(), DatabaseError> {
+    let (admins, sellers, products, sales, categories, tags) =
+        sqlx::query_as::<_, (i64, i64, i64, i64, i64, i64)>(
+            "SELECT (SELECT COUNT(*) FROM admins WHERE organization_id = $1), (SELECT COUNT(*) FROM sellers WHERE organization_id = $1), (SELECT COUNT(*) FROM products WHERE organization_id = $1), (SELECT COUNT(*) FROM sales WHERE organization_id = $1), (SELECT COUNT(*) FROM categories WHERE organization_id = $1), (SELECT COUNT(*) FROM tags WHERE organization_id = $1)",
+        )
+    .bind(id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(DatabaseError::from)?;
+    if admins + sellers + products + sales + categories + tags == 0 {
+        return Ok(());
+    }
+    Err(DatabaseError::OrganizationInUse {
+        admins: admins.unsigned_abs(),
+        sellers: sellers.unsigned_abs(),
+        products: products.unsigned_abs(),
+        sales: sales.unsigned_abs(),
+        categories: categories.unsigned_abs(),
+        tags: tags.unsigned_abs(),
+    })
+}
+
+#[async_trait::async_trait]
+impl OrganizationRemover<Sqlite> for OrganizationRepository {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "organization", operation = "get_removable", key = ?key), err(Debug, level = "warn"))]
+    async fn get_removable<'c, A>(
+        db: A,
+        key: OrganizationBy,
+    ) -> Result<OrganizationDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        // Soft-deleted organizations can be purged too, so look them up regardless of `deleted_at`.
+        let query = match key {
             OrganizationBy::Id(uuid) => sqlx::query_as::<_, OrganizationDAO>(
-                "DELETE FROM organizations WHERE id = $1 RETURNING id, name, active",
+                "SELECT id, name, active, reporting_currency, created_at, updated_at, version FROM organizations WHERE id = $1 LIMIT 1",
             )
-            .bind(uuid)
-            .fetch_one(db)
+            .bind(uuid),
+            OrganizationBy::Name(name) => sqlx::query_as::<_, OrganizationDAO>(
+                "SELECT id, name, active, reporting_currency, created_at, updated_at, version FROM organizations WHERE name = $1 LIMIT 1",
+            )
+            .bind(name),
+        };
+        query
+            .fetch_one(&mut *conn)
             .await
-            .map_err(DatabaseError::from),
-            OrganizationBy::Name(_) => Err(DatabaseError::NotImplemented),
+            .map_err(DatabaseError::from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "organization", operation = "delete_with", key = ?key, policy = ?policy), err(Debug, level = "warn"))]
+    async fn delete_with<'c, A>(
+        db: A,
+        key: OrganizationBy,
+        policy: DeletePolicy,
+    ) -> Result<OrganizationDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut tx = db.begin().await.map_err(DatabaseError::from)?;
+        let organization = OrganizationRepository::get_removable(&mut tx, key).await?;
+        match policy {
+            DeletePolicy::Refuse => refuse_if_referenced(&mut tx, organization.id).await?,
+            DeletePolicy::Cascade => {
+                for statement in [
+                    "DELETE FROM sales WHERE organization_id = $1",
+                    "DELETE FROM products WHERE organization_id = $1",
+                    "DELETE FROM categories WHERE organization_id = $1",
+                    "DELETE FROM tags WHERE organization_id = $1",
+                    "DELETE FROM sellers WHERE organization_id = $1",
+                    "DELETE FROM admins WHERE organization_id = $1",
+                ] {
+                    sqlx::query(statement)
+                        .bind(organization.id)
+                        .execute(&mut *tx)
+                        .await
+                        .map_err(DatabaseError::from)?;
+                }
+            }
         }
+        let organization = sqlx::query_as::<_, OrganizationDAO>(
+            "DELETE FROM organizations WHERE id = $1 RETURNING id, name, active, reporting_currency, created_at, updated_at, version",
+        )
+        .bind(organization.id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(DatabaseError::from)?;
+        tx.commit().await.map_err(DatabaseError::from)?;
+        Ok(organization)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::sqlite::DatabaseRepository;
-
-    #[tokio::test]
-    async fn queries() {
-        let db = DatabaseRepository::new()
-            .await
-            .expect("Could not initialize db");
-
+    use crate::money::{Currency, Money};
+    use crate::{
+        entities::{
+            admin::{AdminBy, AdminDAO, AdminRepository, AdminsWhere, NewAdminDAO, UpdateAdminDAO},
+            category::{
+                CategoriesWhere, CategoryBy, CategoryDAO, CategoryRepository, NewCategoryDAO,
+                UpdateCategoryDAO,
+            },
+            product::{
+                NewProductDAO, ProductBy, ProductDAO, ProductRepository, ProductsWhere,
+                UpdateProductDAO,
+            },
+            sales::{NewSalesDAO, SalesBy, SalesDAO, SalesRepository, SalesWhere, UpdateSalesDAO},
+            seller::{
+                NewSellerDAO, SellerBy, SellerDAO, SellerRepository, SellersWhere, UpdateSellerDAO,
+            },
+            tag::{NewTagDAO, ProductTags, TagBy, TagDAO, TagRepository, TagsWhere, UpdateTagDAO},
+        },
+        sqlite::DatabaseRepository,
+    };
+    use sqlx::{Database, Pool};
+
+    async fn queries<DB: Database>(db: &Pool<DB>)
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+    {
         let organization = OrganizationRepository::insert(
-            &db.connection,
+            db,
             NewOrganizationDAO {
                 name: "dev3".to_string(),
+                reporting_currency: Currency::USD,
             },
         )
         .await
@@ -186,42 +481,58 @@ mod tests {
         assert!(organization.active);
         assert_eq!(organization.name, "dev3");
 
-        let organization =
-            OrganizationRepository::get(&db.connection, OrganizationBy::Id(organization.id))
-                .await
-                .expect("Could not find organization");
+        let organization = OrganizationRepository::get(db, OrganizationBy::Id(organization.id))
+            .await
+            .expect("Could not find organization");
 
         assert!(organization.active);
         assert_eq!(organization.name, "dev3");
 
-        let organization =
-            OrganizationRepository::get(&db.connection, OrganizationBy::Name(organization.name))
-                .await
-                .expect("Could not find organization");
+        let organization = OrganizationRepository::get(db, OrganizationBy::Name(organization.name))
+            .await
+            .expect("Could not find organization");
 
         assert!(organization.active);
         assert_eq!(organization.name, "dev3");
 
         let maybe_organization =
-            OrganizationRepository::try_get(&db.connection, OrganizationBy::Id(organization.id))
+            OrganizationRepository::try_get(db, OrganizationBy::Id(organization.id))
                 .await
                 .expect("Could not find organization");
 
         assert!(maybe_organization.is_some());
 
         let maybe_organization =
-            OrganizationRepository::try_get(&db.connection, OrganizationBy::Id(Uuid::default()))
+            OrganizationRepository::try_get(db, OrganizationBy::Id(Uuid::default()))
                 .await
                 .expect("Could not find organization");
 
         assert!(maybe_organization.is_none());
 
+        let missing = OrganizationRepository::get(db, OrganizationBy::Id(Uuid::default()))
+            .await
+            .unwrap_err();
+        assert!(matches!(missing, DatabaseError::NotFound(_)));
+
+        let duplicate = OrganizationRepository::insert(
+            db,
+            NewOrganizationDAO {
+                name: "dev3".to_string(),
+                reporting_currency: Currency::USD,
+            },
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(duplicate, DatabaseError::UniqueViolation { .. }));
+
         let updated = OrganizationRepository::update(
-            &db.connection,
+            db,
             OrganizationBy::Id(organization.id),
             UpdateOrganizationDAO {
                 name: "dev4".to_string(),
                 active: false,
+                reporting_currency: Currency::USD,
+                version: organization.version,
             },
         )
         .await
@@ -232,32 +543,342 @@ mod tests {
         assert!(!updated.active);
 
         let updated = OrganizationRepository::update(
-            &db.connection,
+            db,
             OrganizationBy::Name(updated.name),
             UpdateOrganizationDAO {
                 name: "dev45".to_string(),
                 active: true,
+                reporting_currency: Currency::USD,
+                version: updated.version,
             },
         )
         .await
-        .unwrap_err();
-        assert_eq!(updated, DatabaseError::NotImplemented);
+        .expect("Could not update organization by name");
+
+        assert_eq!(updated.id, organization.id);
+        assert_eq!(updated.name, "dev45");
+        assert!(updated.active);
 
-        let _ = OrganizationRepository::delete(&db.connection, OrganizationBy::Id(organization.id))
+        let _ = OrganizationRepository::delete(db, OrganizationBy::Id(organization.id))
             .await
             .expect("Could not delete organization by id");
 
         let maybe_organization =
-            OrganizationRepository::try_get(&db.connection, OrganizationBy::Id(organization.id))
+            OrganizationRepository::try_get(db, OrganizationBy::Id(organization.id))
                 .await
                 .expect("Could not find organization");
 
         assert!(maybe_organization.is_none());
 
-        let deleted =
-            OrganizationRepository::delete(&db.connection, OrganizationBy::Name(organization.name))
-                .await
-                .unwrap_err();
-        assert_eq!(deleted, DatabaseError::NotImplemented);
+        let deleted = OrganizationRepository::delete(db, OrganizationBy::Name(updated.name))
+            .await
+            .unwrap_err();
+        assert!(matches!(deleted, DatabaseError::NotFound(_)));
+
+        let organization = OrganizationRepository::insert(
+            db,
+            NewOrganizationDAO {
+                name: "dev5".to_string(),
+                reporting_currency: Currency::USD,
+            },
+        )
+        .await
+        .expect("Could not create organization");
+        let deleted = OrganizationRepository::delete(db, OrganizationBy::Name(organization.name))
+            .await
+            .expect("Could not delete organization by name");
+        assert_eq!(deleted.id, organization.id);
+    }
+
+    async fn delete_policy<DB: Database>(db: &Pool<DB>)
+    where
+        OrganizationRepository: EntityRepository<
+                DB,
+                OrganizationDAO,
+                NewOrganizationDAO,
+                UpdateOrganizationDAO,
+                OrganizationBy,
+                OrganizationsWhere,
+            > + OrganizationRemover<DB>
+            + SoftDeleteRepository<DB, OrganizationDAO, OrganizationBy>,
+        AdminRepository:
+            EntityRepository<DB, AdminDAO, NewAdminDAO, UpdateAdminDAO, AdminBy, AdminsWhere>,
+        SellerRepository:
+            EntityRepository<DB, SellerDAO, NewSellerDAO, UpdateSellerDAO, SellerBy, SellersWhere>,
+        ProductRepository: EntityRepository<
+                DB,
+                ProductDAO,
+                NewProductDAO,
+                UpdateProductDAO,
+                ProductBy,
+                ProductsWhere,
+            > + ProductTags<DB>,
+        SalesRepository:
+            EntityRepository<DB, SalesDAO, NewSalesDAO, UpdateSalesDAO, SalesBy, SalesWhere>,
+        CategoryRepository: EntityRepository<
+            DB,
+            CategoryDAO,
+            NewCategoryDAO,
+            UpdateCategoryDAO,
+            CategoryBy,
+            CategoriesWhere,
+        >,
+        TagRepository: EntityRepository<DB, TagDAO, NewTagDAO, UpdateTagDAO, TagBy, TagsWhere>,
+    {
+        let organization = OrganizationRepository::insert(
+            db,
+            NewOrganizationDAO {
+                name: "shop".to_string(),
+                reporting_currency: Currency::USD,
+            },
+        )
+        .await
+        .expect("Could not create organization");
+        let admin = AdminRepository::insert(
+            db,
+            NewAdminDAO {
+                organization_id: organization.id,
+                email: "owner@shop.com".to_string(),
+                password: "secret".to_string(),
+                is_default: true,
+            },
+        )
+        .await
+        .expect("Could not create admin");
+        let seller = SellerRepository::insert(
+            db,
+            NewSellerDAO {
+                organization_id: organization.id,
+                email: "seller@shop.com".to_string(),
+                password: "secret".to_string(),
+            },
+        )
+        .await
+        .expect("Could not create seller");
+        let drinks = CategoryRepository::insert(
+            db,
+            NewCategoryDAO {
+                organization_id: organization.id,
+                parent_id: None,
+                name: "Drinks".to_string(),
+            },
+        )
+        .await
+        .expect("Could not create category");
+        let hot_drinks = CategoryRepository::insert(
+            db,
+            NewCategoryDAO {
+                organization_id: organization.id,
+                parent_id: Some(drinks.id),
+                name: "Hot drinks".to_string(),
+            },
+        )
+        .await
+        .expect("Could not create subcategory");
+        let tag = TagRepository::insert(
+            db,
+            NewTagDAO {
+                organization_id: organization.id,
+                name: "organic".to_string(),
+            },
+        )
+        .await
+        .expect("Could not create tag");
+        let product = ProductRepository::insert(
+            db,
+            NewProductDAO {
+                organization_id: organization.id,
+                category_id: Some(hot_drinks.id),
+                sku: None,
+                barcode: None,
+                name: "Coffee".to_string(),
+                description: "Beans".to_string(),
+                amount: 10,
+                price: Money::new(5, Currency::USD),
+            },
+        )
+        .await
+        .expect("Could not create product");
+        ProductRepository::add_tag(db, product.id, tag.id)
+            .await
+            .expect("Could not tag product");
+        let sale = SalesRepository::insert(
+            db,
+            NewSalesDAO {
+                organization_id: organization.id,
+                product_id: product.id,
+                seller_id: seller.id,
+                amount: 1,
+                total_price: Money::new(5, Currency::USD),
+            },
+        )
+        .await
+        .expect("Could not create sale");
+
+        let refused = OrganizationRepository::purge(db, OrganizationBy::Name("shop".to_string()))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            refused,
+            DatabaseError::OrganizationInUse {
+                admins: 1,
+                sellers: 1,
+                products: 1,
+                sales: 1,
+                categories: 2,
+                tags: 1,
+            }
+        );
+        let refused = OrganizationRepository::delete_with(
+            db,
+            OrganizationBy::Id(organization.id),
+            DeletePolicy::Refuse,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(refused, DatabaseError::OrganizationInUse { .. }));
+        assert!(SalesRepository::try_get(db, SalesBy::Id(sale.id))
+            .await
+            .expect("Could not look up sale")
+            .is_some());
+
+        let deleted = OrganizationRepository::delete_with(
+            db,
+            OrganizationBy::Name("shop".to_string()),
+            DeletePolicy::Cascade,
+        )
+        .await
+        .expect("Could not delete organization with its rows");
+        assert_eq!(deleted, organization);
+
+        assert!(SalesRepository::try_get(db, SalesBy::Id(sale.id))
+            .await
+            .expect("Could not look up sale")
+            .is_none());
+        assert!(ProductRepository::try_get(db, ProductBy::Id(product.id))
+            .await
+            .expect("Could not look up product")
+            .is_none());
+        assert!(CategoryRepository::try_get(db, CategoryBy::Id(drinks.id))
+            .await
+            .expect("Could not look up category")
+            .is_none());
+        assert!(TagRepository::try_get(db, TagBy::Id(tag.id))
+            .await
+            .expect("Could not look up tag")
+            .is_none());
+        assert!(SellerRepository::try_get(db, SellerBy::Id(seller.id))
+            .await
+            .expect("Could not look up seller")
+            .is_none());
+        assert!(AdminRepository::try_get(db, AdminBy::Id(admin.id))
+            .await
+            .expect("Could not look up admin")
+            .is_none());
+    }
+
+    async fn listing<DB: Database>(db: &Pool<DB>)
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+    {
+        for name in ["charlie", "alpha", "bravo"] {
+            OrganizationRepository::insert(
+                db,
+                NewOrganizationDAO {
+                    name: name.to_string(),
+                    reporting_currency: Currency::USD,
+                },
+            )
+            .await
+            .expect("Could not create organization");
+        }
+
+        let mut after = None;
+        let mut pages = vec![];
+        loop {
+            let page = OrganizationRepository::get_all(
+                db,
+                OrganizationsWhere::Active {
+                    active: true,
+                    limit: 2,
+                    after,
+                },
+            )
+            .await
+            .expect("Could not list organizations");
+            after = page.next_cursor.clone();
+            pages.push(page.items.into_iter().map(|o| o.name).collect::<Vec<_>>());
+            if after.is_none() {
+                break;
+            }
+        }
+        assert_eq!(pages, vec![vec!["alpha", "bravo"], vec!["charlie"]]);
+
+        let invalid = OrganizationRepository::get_all(
+            db,
+            OrganizationsWhere::Active {
+                active: true,
+                limit: 2,
+                after: Some("bm90IGEgY3Vyc29y".parse().unwrap()),
+            },
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(invalid, DatabaseError::InvalidCursor);
+    }
+
+    #[tokio::test]
+    async fn sqlite_queries() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        queries(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_queries() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            queries(&db.connection).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn sqlite_listing() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        listing(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_listing() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            listing(&db.connection).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn sqlite_delete_policy() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        delete_policy(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_delete_policy() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            delete_policy(&db.connection).await;
+        }
     }
 }
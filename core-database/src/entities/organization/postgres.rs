@@ -0,0 +1,330 @@
+use sqlx::{Acquire, PgConnection, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use super::{
+    organization_cursor, DeletePolicy, NewOrganizationDAO, OrganizationBy, OrganizationDAO,
+    OrganizationRemover, OrganizationRepository, OrganizationsWhere, UpdateOrganizationDAO,
+};
+use crate::{
+    entities::{version_rejection, SortOrder},
+    pagination::{push_after_postgres, Page},
+    traits::{DatabaseError, EntityRepository, SoftDeleteRepository},
+};
+
+#[async_trait::async_trait]
+impl
+    EntityRepository<
+        Postgres,
+        OrganizationDAO,
+        NewOrganizationDAO,
+        UpdateOrganizationDAO,
+        OrganizationBy,
+        OrganizationsWhere,
+    > for OrganizationRepository
+{
+    #[tracing::instrument(
+        name = "repository",
+        level = "debug",
+        skip_all,
+        fields(entity = "organization", operation = "insert"),
+        err(Debug, level = "warn")
+    )]
+    async fn insert<'c, A>(
+        db: A,
+        input: NewOrganizationDAO,
+    ) -> Result<OrganizationDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let uuid = Uuid::new_v4();
+        sqlx::query_as::<_, OrganizationDAO>(
+            "INSERT INTO organizations (id, name, reporting_currency) VALUES ($1, $2, $3) RETURNING id, name, active, reporting_currency, created_at, updated_at, version",
+        )
+        .bind(uuid)
+        .bind(input.name)
+        .bind(input.reporting_currency)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "organization", operation = "get", key = ?key), err(Debug, level = "warn"))]
+    async fn get<'c, A>(db: A, key: OrganizationBy) -> Result<OrganizationDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        match key {
+            OrganizationBy::Id(uuid) => sqlx::query_as::<_, OrganizationDAO>(
+                "SELECT id, name, active, reporting_currency, created_at, updated_at, version FROM organizations WHERE id = $1 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(uuid)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from),
+            OrganizationBy::Name(name) => sqlx::query_as::<_, OrganizationDAO>(
+                "SELECT id, name, active, reporting_currency, created_at, updated_at, version FROM organizations WHERE name = $1 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(name)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from),
+        }
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "organization", operation = "try_get", key = ?key), err(Debug, level = "warn"))]
+    async fn try_get<'c, A>(
+        db: A,
+        key: OrganizationBy,
+    ) -> Result<Option<OrganizationDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        match key {
+            OrganizationBy::Id(uuid) => sqlx::query_as::<_, OrganizationDAO>(
+                "SELECT id, name, active, reporting_currency, created_at, updated_at, version FROM organizations WHERE id = $1 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(uuid)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(DatabaseError::from),
+            OrganizationBy::Name(name) => sqlx::query_as::<_, OrganizationDAO>(
+                "SELECT id, name, active, reporting_currency, created_at, updated_at, version FROM organizations WHERE name = $1 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(name)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(DatabaseError::from),
+        }
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "organization", operation = "get_all", key = ?key), err(Debug, level = "warn"))]
+    async fn get_all<'c, A>(
+        db: A,
+        key: OrganizationsWhere,
+    ) -> Result<Page<OrganizationDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        match key {
+            OrganizationsWhere::Active {
+                active,
+                limit,
+                after,
+            } => {
+                let mut query = QueryBuilder::<Postgres>::new(
+                    "SELECT id, name, active, reporting_currency, created_at, updated_at, version FROM organizations WHERE deleted_at IS NULL AND active = ",
+                );
+                query.push_bind(active);
+                if let Some(after) = &after {
+                    push_after_postgres(&mut query, "name", SortOrder::Ascending, after)?;
+                }
+                query
+                    .push(" ORDER BY name, id LIMIT ")
+                    .push_bind(i64::from(limit) + 1);
+
+                let rows = query
+                    .build_query_as::<OrganizationDAO>()
+                    .fetch_all(&mut *conn)
+                    .await
+                    .map_err(DatabaseError::from)?;
+                Ok(Page::from_rows(rows, limit, |organization| {
+                    organization_cursor(organization)
+                }))
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "organization", operation = "update", key = ?key), err(Debug, level = "warn"))]
+    async fn update<'c, A>(
+        db: A,
+        key: OrganizationBy,
+        input: UpdateOrganizationDAO,
+    ) -> Result<OrganizationDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match &key {
+            OrganizationBy::Id(uuid) => sqlx::query_as::<_, OrganizationDAO>(
+                "UPDATE organizations SET name = $2, active = $3, reporting_currency = $4, version = version + 1 WHERE id = $1 AND version = $5 AND deleted_at IS NULL RETURNING id, name, active, reporting_currency, created_at, updated_at, version",
+            )
+            .bind(*uuid),
+            OrganizationBy::Name(name) => sqlx::query_as::<_, OrganizationDAO>(
+                "UPDATE organizations SET name = $2, active = $3, reporting_currency = $4, version = version + 1 WHERE name = $1 AND version = $5 AND deleted_at IS NULL RETURNING id, name, active, reporting_currency, created_at, updated_at, version",
+            )
+            .bind(name.clone()),
+        };
+        let updated = query
+            .bind(input.name)
+            .bind(input.active)
+            .bind(input.reporting_currency)
+            .bind(input.version)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?;
+        match updated {
+            Some(organization) => Ok(organization),
+            None => {
+                let missing = format!("organization {key:?}");
+                let current = Self::try_get(&mut *conn, key).await;
+                Err(version_rejection(
+                    current.map(|organization| organization.map(|o| o.version)),
+                    missing,
+                ))
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "organization", operation = "delete", key = ?key), err(Debug, level = "warn"))]
+    async fn delete<'c, A>(db: A, key: OrganizationBy) -> Result<OrganizationDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            OrganizationBy::Id(uuid) => sqlx::query_as::<_, OrganizationDAO>(
+                "UPDATE organizations SET deleted_at = now(), version = version + 1 WHERE id = $1 AND deleted_at IS NULL RETURNING id, name, active, reporting_currency, created_at, updated_at, version",
+            )
+            .bind(uuid),
+            OrganizationBy::Name(name) => sqlx::query_as::<_, OrganizationDAO>(
+                "UPDATE organizations SET deleted_at = now(), version = version + 1 WHERE name = $1 AND deleted_at IS NULL RETURNING id, name, active, reporting_currency, created_at, updated_at, version",
+            )
+            .bind(name),
+        };
+        query
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+    }
+}
+
+#[async_trait::async_trait]
+impl SoftDeleteRepository<Postgres, OrganizationDAO, OrganizationBy> for OrganizationRepository {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "organization", operation = "restore", key = ?key), err(Debug, level = "warn"))]
+    async fn restore<'c, A>(db: A, key: OrganizationBy) -> Result<OrganizationDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            OrganizationBy::Id(uuid) => sqlx::query_as::<_, OrganizationDAO>(
+                "UPDATE organizations SET deleted_at = NULL, version = version + 1 WHERE id = $1 AND deleted_at IS NOT NULL RETURNING id, name, active, reporting_currency, created_at, updated_at, version",
+            )
+            .bind(uuid),
+            OrganizationBy::Name(name) => sqlx::query_as::<_, OrganizationDAO>(
+                "UPDATE organizations SET deleted_at = NULL, version = version + 1 WHERE name = $1 AND deleted_at IS NOT NULL RETURNING id, name, active, reporting_currency, created_at, updated_at, version",
+            )
+            .bind(name),
+        };
+        query
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "organization", operation = "purge", key = ?key), err(Debug, level = "warn"))]
+    async fn purge<'c, A>(db: A, key: OrganizationBy) -> Result<OrganizationDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        OrganizationRepository::delete_with(db, key, DeletePolicy::Refuse).await
+    }
+}
+
+/// Fails with [`DatabaseError::OrganizationInUse`] when any row still references the
+/// organization.
+async fn refuse_if_referenced(conn: &mut PgConnection, id: Uuid) -> Result<(), DatabaseError> {
+    let (admins, sellers, products, sales, categories, tags) =
+        sqlx::query_as::<_, (i64, i64, i64, i64, i64, i64)>(
+            "SELECT (SELECT COUNT(*) FROM admins WHERE organization_id = $1), (SELECT COUNT(*) FROM sellers WHERE organization_id = $1), (SELECT COUNT(*) FROM products WHERE organization_id = $1), (SELECT COUNT(*) FROM sales WHERE organization_id = $1), (SELECT COUNT(*) FROM categories WHERE organization_id = $1), (SELECT COUNT(*) FROM tags WHERE organization_id = $1)",
+        )
+    .bind(id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(DatabaseError::from)?;
+    if admins + sellers + products + sales + categories + tags == 0 {
+        return Ok(());
+    }
+    Err(DatabaseError::OrganizationInUse {
+        admins: admins.unsigned_abs(),
+        sellers: sellers.unsigned_abs(),
+        products: products.unsigned_abs(),
+        sales: sales.unsigned_abs(),
+        categories: categories.unsigned_abs(),
+        tags: tags.unsigned_abs(),
+    })
+}
+
+#[async_trait::async_trait]
+impl OrganizationRemover<Postgres> for OrganizationRepository {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "organization", operation = "get_removable", key = ?key), err(Debug, level = "warn"))]
+    async fn get_removable<'c, A>(
+        db: A,
+        key: OrganizationBy,
+    ) -> Result<OrganizationDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        // Soft-deleted organizations can be purged too, so look them up regardless of `deleted_at`.
+        let query = match key {
+            OrganizationBy::Id(uuid) => sqlx::query_as::<_, OrganizationDAO>(
+                "SELECT id, name, active, reporting_currency, created_at, updated_at, version FROM organizations WHERE id = $1 LIMIT 1",
+            )
+            .bind(uuid),
+            OrganizationBy::Name(name) => sqlx::query_as::<_, OrganizationDAO>(
+                "SELECT id, name, active, reporting_currency, created_at, updated_at, version FROM organizations WHERE name = $1 LIMIT 1",
+            )
+            .bind(name),
+        };
+        query
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "organization", operation = "delete_with", key = ?key, policy = ?policy), err(Debug, level = "warn"))]
+    async fn delete_with<'c, A>(
+        db: A,
+        key: OrganizationBy,
+        policy: DeletePolicy,
+    ) -> Result<OrganizationDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut tx = db.begin().await.map_err(DatabaseError::from)?;
+        let organization = OrganizationRepository::get_removable(&mut tx, key).await?;
+        match policy {
+            DeletePolicy::Refuse => refuse_if_referenced(&mut tx, organization.id).await?,
+            DeletePolicy::Cascade => {
+                for statement in [
+                    "DELETE FROM sales WHERE organization_id = $1",
+                    "DELETE FROM products WHERE organization_id = $1",
+                    "DELETE FROM categories WHERE organization_id = $1",
+                    "DELETE FROM tags WHERE organization_id = $1",
+                    "DELETE FROM sellers WHERE organization_id = $1",
+                    "DELETE FROM admins WHERE organization_id = $1",
+                ] {
+                    sqlx::query(statement)
+                        .bind(organization.id)
+                        .execute(&mut *tx)
+                        .await
+                        .map_err(DatabaseError::from)?;
+                }
+            }
+        }
+        let organization = sqlx::query_as::<_, OrganizationDAO>(
+            "DELETE FROM organizations WHERE id = $1 RETURNING id, name, active, reporting_currency, created_at, updated_at, version",
+        )
+        .bind(organization.id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(DatabaseError::from)?;
+        tx.commit().await.map_err(DatabaseError::from)?;
+        Ok(organization)
+    }
+}
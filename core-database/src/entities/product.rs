@@ -1,248 +1,780 @@
 use chrono::{DateTime, Utc};
-use num_bigint::BigUint;
-use sqlx::{Pool, Sqlite};
+use sqlx::{Acquire, Database, QueryBuilder, Sqlite};
 use uuid::Uuid;
 
-use crate::traits::{DatabaseError, EntityRepository};
+use crate::{
+    entities::{
+        like_prefix, quantity_from_column, quantity_to_column, version_rejection, SortOrder,
+    },
+    money::{Currency, Money},
+    pagination::{push_after_sqlite, Cursor, CursorKey, Page},
+    sqlite,
+    traits::{DatabaseError, EntityRepository, SoftDeleteRepository},
+};
 
+#[cfg(feature = "postgres")]
+pub mod postgres;
+
+#[derive(Debug, Clone)]
 pub enum ProductBy {
     Id(Uuid),
+    /// SKUs are unique within an organization.
+    Sku {
+        organization_id: Uuid,
+        sku: String,
+    },
+    /// Barcodes are unique within an organization.
+    Barcode {
+        organization_id: Uuid,
+        barcode: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProductsOrderBy {
+    #[default]
+    CreatedAt,
+    Name,
+    Amount,
+    Price,
 }
 
+impl ProductsOrderBy {
+    pub(crate) fn column(&self) -> &'static str {
+        match self {
+            ProductsOrderBy::CreatedAt => "created_at",
+            ProductsOrderBy::Name => "name",
+            ProductsOrderBy::Amount => "amount",
+            ProductsOrderBy::Price => "price",
+        }
+    }
+
+    pub(crate) fn cursor(&self, order: SortOrder, product: &ProductDAO) -> Cursor {
+        let key = match self {
+            ProductsOrderBy::CreatedAt => CursorKey::Timestamp(product.created_at),
+            ProductsOrderBy::Name => CursorKey::Text(product.name.clone()),
+            ProductsOrderBy::Amount => CursorKey::Integer(product.amount.into()),
+            ProductsOrderBy::Price => CursorKey::Integer(product.price.minor_units),
+        };
+        Cursor::new(self.column(), order, key, product.id)
+    }
+}
+
+/// Filters used to list products. Filters left as `None` are not applied.
 #[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ProductsWhere {
+    pub organization_id: Option<Uuid>,
+    /// Only products of this category or of any of its subcategories.
+    pub category_id: Option<Uuid>,
+    /// Only products tagged with this tag.
+    pub tag_id: Option<Uuid>,
+    /// Case-insensitive prefix of the product name.
+    pub name_prefix: Option<String>,
+    /// Only products priced in this currency.
+    pub currency: Option<Currency>,
+    /// Inclusive lower bound of the price; only products in its currency match.
+    pub min_price: Option<Money>,
+    /// Inclusive upper bound of the price; only products in its currency match.
+    pub max_price: Option<Money>,
+    /// Only products created at or after this instant.
+    pub created_after: Option<DateTime<Utc>>,
+    /// Only products created before this instant.
+    pub created_before: Option<DateTime<Utc>>,
+    pub order_by: ProductsOrderBy,
+    pub order: SortOrder,
+    pub limit: i32,
+    /// Resume after the last row of a previous page.
+    pub after: Option<Cursor>,
+    /// List soft-deleted rows instead of live ones.
+    pub deleted: bool,
+}
+
+impl Default for ProductsWhere {
+    fn default() -> Self {
+        Self {
+            organization_id: None,
+            category_id: None,
+            tag_id: None,
+            name_prefix: None,
+            currency: None,
+            min_price: None,
+            max_price: None,
+            created_after: None,
+            created_before: None,
+            order_by: ProductsOrderBy::default(),
+            order: SortOrder::default(),
+            limit: 100,
+            after: None,
+            deleted: false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, serde::Serialize)]
 pub struct ProductDAO {
     pub id: Uuid,
     pub organization_id: Uuid,
+    pub category_id: Option<Uuid>,
+    pub sku: Option<String>,
+    pub barcode: Option<String>,
     pub name: String,
     pub description: String,
     pub amount: u32,
-    pub price: BigUint,
+    pub price: Money,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub version: i64,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct NewProductDAO {
     pub organization_id: Uuid,
+    /// A category of the same organization.
+    pub category_id: Option<Uuid>,
+    /// Stock keeping unit; unique within the organization.
+    pub sku: Option<String>,
+    /// Unique within the organization.
+    pub barcode: Option<String>,
     pub name: String,
     pub description: String,
     pub amount: u32,
-    pub price: BigUint,
+    pub price: Money,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct UpdateProductDAO {
+    pub category_id: Option<Uuid>,
+    pub sku: Option<String>,
+    pub barcode: Option<String>,
     pub name: String,
     pub description: String,
     pub amount: u32,
-    pub price: BigUint,
+    pub price: Money,
+    /// Version of the row the update is based on; the update fails with
+    /// [`DatabaseError::Conflict`] when the row changed since.
+    pub version: i64,
+}
+
+/// A product found by [`ProductSearch::search`]. Matched words are wrapped in `[` and `]` in
+/// `name` and `snippet`.
+#[derive(Debug, PartialEq, Clone, serde::Serialize)]
+pub struct ProductSearchResultDAO {
+    pub product: ProductDAO,
+    /// The product name with the matched words highlighted.
+    pub name: String,
+    /// The part of the description around the matched words, highlighted.
+    pub snippet: String,
+    /// Relevance of the match; higher ranks first. Only comparable within one search.
+    pub rank: f64,
 }
 
 #[derive(sqlx::FromRow, Debug, PartialEq, Eq, Clone)]
 pub struct SqliteProductDAO {
     pub id: Uuid,
     pub organization_id: Uuid,
+    pub category_id: Option<Uuid>,
+    pub sku: Option<String>,
+    pub barcode: Option<String>,
     pub name: String,
     pub description: String,
     pub amount: i32,
-    pub price: Vec<u8>,
+    pub price: i64,
+    pub currency: Currency,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub version: i64,
 }
 
-impl From<ProductDAO> for SqliteProductDAO {
-    fn from(value: ProductDAO) -> Self {
-        Self {
+impl TryFrom<ProductDAO> for SqliteProductDAO {
+    type Error = DatabaseError;
+
+    fn try_from(value: ProductDAO) -> Result<Self, Self::Error> {
+        Ok(Self {
             id: value.id,
             organization_id: value.organization_id,
+            category_id: value.category_id,
+            sku: value.sku,
+            barcode: value.barcode,
             name: value.name,
-            amount: i32::try_from(value.amount).unwrap_or_default(),
-            price: value.price.to_bytes_le(),
+            amount: quantity_to_column(value.amount)?,
+            price: value.price.minor_units,
+            currency: value.price.currency,
             description: value.description,
             created_at: value.created_at,
             updated_at: value.updated_at,
-        }
+            version: value.version,
+        })
     }
 }
 
-impl From<NewProductDAO> for SqliteProductDAO {
-    fn from(value: NewProductDAO) -> Self {
-        Self {
+impl TryFrom<NewProductDAO> for SqliteProductDAO {
+    type Error = DatabaseError;
+
+    fn try_from(value: NewProductDAO) -> Result<Self, Self::Error> {
+        Ok(Self {
             id: Uuid::new_v4(),
             organization_id: value.organization_id,
+            category_id: value.category_id,
+            sku: value.sku,
+            barcode: value.barcode,
             name: value.name,
-            amount: i32::try_from(value.amount).unwrap_or_default(),
-            price: value.price.to_bytes_le(),
+            amount: quantity_to_column(value.amount)?,
+            price: value.price.minor_units,
+            currency: value.price.currency,
             description: value.description,
             created_at: Utc::now(),
             updated_at: Utc::now(),
-        }
+            version: 1,
+        })
     }
 }
 
-impl From<UpdateProductDAO> for SqliteProductDAO {
-    fn from(value: UpdateProductDAO) -> Self {
-        Self {
+impl TryFrom<UpdateProductDAO> for SqliteProductDAO {
+    type Error = DatabaseError;
+
+    fn try_from(value: UpdateProductDAO) -> Result<Self, Self::Error> {
+        Ok(Self {
             id: Uuid::default(),
             organization_id: Uuid::default(),
+            category_id: value.category_id,
+            sku: value.sku,
+            barcode: value.barcode,
             name: value.name,
-            amount: i32::try_from(value.amount).unwrap_or_default(),
-            price: value.price.to_bytes_le(),
+            amount: quantity_to_column(value.amount)?,
+            price: value.price.minor_units,
+            currency: value.price.currency,
             description: value.description,
             created_at: DateTime::default(),
             updated_at: DateTime::default(),
-        }
+            version: value.version,
+        })
     }
 }
 
-impl From<SqliteProductDAO> for ProductDAO {
-    fn from(value: SqliteProductDAO) -> Self {
-        Self {
+impl TryFrom<SqliteProductDAO> for ProductDAO {
+    type Error = DatabaseError;
+
+    fn try_from(value: SqliteProductDAO) -> Result<Self, Self::Error> {
+        Ok(Self {
             id: value.id,
             organization_id: value.organization_id,
+            category_id: value.category_id,
+            sku: value.sku,
+            barcode: value.barcode,
             description: value.description,
             name: value.name,
-            amount: value.amount.unsigned_abs(),
-            price: BigUint::from_bytes_le(&value.price),
+            amount: quantity_from_column(value.amount)?,
+            price: Money::new(value.price, value.currency),
             created_at: value.created_at,
             updated_at: value.updated_at,
-        }
+            version: value.version,
+        })
     }
 }
 
 #[derive(Debug)]
 pub struct ProductRepository;
 
+#[derive(sqlx::FromRow)]
+struct SqliteProductSearchRow {
+    #[sqlx(flatten)]
+    product: SqliteProductDAO,
+    name_highlight: String,
+    snippet: String,
+    rank: f64,
+}
+
+impl TryFrom<SqliteProductSearchRow> for ProductSearchResultDAO {
+    type Error = DatabaseError;
+
+    fn try_from(value: SqliteProductSearchRow) -> Result<Self, Self::Error> {
+        Ok(Self {
+            product: ProductDAO::try_from(value.product)?,
+            name: value.name_highlight,
+            snippet: value.snippet,
+            rank: value.rank,
+        })
+    }
+}
+
+/// Builds the query of `get_all`; kept apart so the query plan tests can check it.
+pub(crate) fn list_query(
+    key: &ProductsWhere,
+) -> Result<QueryBuilder<'static, Sqlite>, DatabaseError> {
+    let mut query = QueryBuilder::<Sqlite>::new(
+        "SELECT id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version FROM products WHERE 1 = 1",
+    );
+    query.push(if key.deleted {
+        " AND deleted_at IS NOT NULL"
+    } else {
+        " AND deleted_at IS NULL"
+    });
+    if let Some(organization_id) = key.organization_id {
+        query
+            .push(" AND organization_id = ")
+            .push_bind(organization_id);
+    }
+    if let Some(category_id) = key.category_id {
+        query
+            .push(" AND category_id IN (WITH RECURSIVE subtree(id) AS (SELECT id FROM categories WHERE id = ")
+            .push_bind(category_id)
+            .push(" UNION ALL SELECT categories.id FROM categories JOIN subtree ON categories.parent_id = subtree.id) SELECT id FROM subtree)");
+    }
+    if let Some(tag_id) = key.tag_id {
+        query
+            .push(" AND id IN (SELECT product_id FROM product_tags WHERE tag_id = ")
+            .push_bind(tag_id)
+            .push(")");
+    }
+    if let Some(prefix) = &key.name_prefix {
+        query
+            .push(" AND lower(name) LIKE ")
+            .push_bind(like_prefix(prefix))
+            .push(" ESCAPE '\\'");
+    }
+    if let Some(currency) = key.currency {
+        query.push(" AND currency = ").push_bind(currency);
+    }
+    if let Some(min_price) = key.min_price {
+        query
+            .push(" AND currency = ")
+            .push_bind(min_price.currency)
+            .push(" AND price >= ")
+            .push_bind(min_price.minor_units);
+    }
+    if let Some(max_price) = key.max_price {
+        query
+            .push(" AND currency = ")
+            .push_bind(max_price.currency)
+            .push(" AND price <= ")
+            .push_bind(max_price.minor_units);
+    }
+    if let Some(created_after) = key.created_after {
+        query
+            .push(" AND created_at >= ")
+            .push_bind(sqlite::timestamp(created_after));
+    }
+    if let Some(created_before) = key.created_before {
+        query
+            .push(" AND created_at < ")
+            .push_bind(sqlite::timestamp(created_before));
+    }
+    if let Some(after) = &key.after {
+        push_after_sqlite(&mut query, key.order_by.column(), key.order, after)?;
+    }
+    query
+        .push(" ORDER BY ")
+        .push(key.order_by.column())
+        .push(" ")
+        .push(key.order.keyword())
+        .push(", id ")
+        .push(key.order.keyword())
+        .push(" LIMIT ")
+        .push_bind(i64::from(key.limit) + 1);
+    Ok(query)
+}
+
 #[async_trait::async_trait]
-impl EntityRepository<Sqlite, ProductDAO, NewProductDAO, UpdateProductDAO, ProductBy, ProductBy>
+impl EntityRepository<Sqlite, ProductDAO, NewProductDAO, UpdateProductDAO, ProductBy, ProductsWhere>
     for ProductRepository
 {
-    async fn insert(db: &Pool<Sqlite>, input: NewProductDAO) -> Result<ProductDAO, DatabaseError> {
+    #[tracing::instrument(
+        name = "repository",
+        level = "debug",
+        skip_all,
+        fields(entity = "product", operation = "insert"),
+        err(Debug, level = "warn")
+    )]
+    async fn insert<'c, A>(db: A, input: NewProductDAO) -> Result<ProductDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        // A statement failing after the search triggers wrote to the FTS5 table leaves that
+        // table locked for the other connections of the shared in-memory cache until this
+        // connection touches it again; rolling back a transaction releases it right away.
+        let mut tx = db.begin().await.map_err(DatabaseError::from)?;
         let uuid = Uuid::new_v4();
-        let input = SqliteProductDAO::from(input);
-        sqlx::query_as::<_, SqliteProductDAO>(
-            "INSERT INTO products (id, organization_id, name, description, amount, price) VALUES ($1, $2, $3, $4, $5, $6) RETURNING id, organization_id, name, description, amount, price, created_at, updated_at",
+        let input = SqliteProductDAO::try_from(input)?;
+        let product = sqlx::query_as::<_, SqliteProductDAO>(
+            "INSERT INTO products (id, organization_id, category_id, sku, barcode, name, description, amount, price, currency) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version",
         )
         .bind(uuid)
         .bind(input.organization_id)
+        .bind(input.category_id)
+        .bind(input.sku)
+        .bind(input.barcode)
         .bind(input.name)
         .bind(input.description)
         .bind(input.amount)
         .bind(input.price)
-        .fetch_one(db)
+        .bind(input.currency)
+        .fetch_one(&mut *tx)
         .await
-        .map(ProductDAO::from)
         .map_err(DatabaseError::from)
+        .and_then(ProductDAO::try_from)?;
+        tx.commit().await.map_err(DatabaseError::from)?;
+        Ok(product)
     }
 
-    async fn get(db: &Pool<Sqlite>, key: ProductBy) -> Result<ProductDAO, DatabaseError> {
-        match key {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "product", operation = "get", key = ?key), err(Debug, level = "warn"))]
+    async fn get<'c, A>(db: A, key: ProductBy) -> Result<ProductDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
             ProductBy::Id(uuid) => sqlx::query_as::<_, SqliteProductDAO>(
-                "SELECT id, organization_id, name, description, amount, price, created_at, updated_at FROM products WHERE id = $1 LIMIT 1",
+                "SELECT id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version FROM products WHERE id = $1 AND deleted_at IS NULL LIMIT 1",
             )
-            .bind(uuid)
-            .fetch_one(db)
+            .bind(uuid),
+            ProductBy::Sku { organization_id, sku } => sqlx::query_as::<_, SqliteProductDAO>(
+                "SELECT id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version FROM products WHERE organization_id = $1 AND sku = $2 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(organization_id)
+            .bind(sku),
+            ProductBy::Barcode { organization_id, barcode } => sqlx::query_as::<_, SqliteProductDAO>(
+                "SELECT id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version FROM products WHERE organization_id = $1 AND barcode = $2 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(organization_id)
+            .bind(barcode),
+        };
+        query
+            .fetch_one(&mut *conn)
             .await
-            .map(ProductDAO::from)
-            .map_err(DatabaseError::from),
-        }
+            .map_err(DatabaseError::from)
+            .and_then(ProductDAO::try_from)
     }
 
-    async fn try_get(
-        db: &Pool<Sqlite>,
-        key: ProductBy,
-    ) -> Result<Option<ProductDAO>, DatabaseError> {
-        match key {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "product", operation = "try_get", key = ?key), err(Debug, level = "warn"))]
+    async fn try_get<'c, A>(db: A, key: ProductBy) -> Result<Option<ProductDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
             ProductBy::Id(uuid) => sqlx::query_as::<_, SqliteProductDAO>(
-                "SELECT id, organization_id, name, description, amount, price, created_at, updated_at FROM products WHERE id = $1 LIMIT 1",
+                "SELECT id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version FROM products WHERE id = $1 AND deleted_at IS NULL LIMIT 1",
             )
-            .bind(uuid)
-            .fetch_optional(db)
+            .bind(uuid),
+            ProductBy::Sku { organization_id, sku } => sqlx::query_as::<_, SqliteProductDAO>(
+                "SELECT id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version FROM products WHERE organization_id = $1 AND sku = $2 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(organization_id)
+            .bind(sku),
+            ProductBy::Barcode { organization_id, barcode } => sqlx::query_as::<_, SqliteProductDAO>(
+                "SELECT id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version FROM products WHERE organization_id = $1 AND barcode = $2 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(organization_id)
+            .bind(barcode),
+        };
+        query
+            .fetch_optional(&mut *conn)
             .await
-            .map(|v| v.map(ProductDAO::from))
-            .map_err(DatabaseError::from),
-        }
+            .map_err(DatabaseError::from)
+            .and_then(|v| v.map(ProductDAO::try_from).transpose())
     }
 
-    async fn get_all(
-        _db: &Pool<Sqlite>,
-        _key: ProductBy,
-    ) -> Result<Vec<ProductDAO>, DatabaseError> {
-        Err(DatabaseError::NotImplemented)
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "product", operation = "get_all", key = ?key), err(Debug, level = "warn"))]
+    async fn get_all<'c, A>(db: A, key: ProductsWhere) -> Result<Page<ProductDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let mut query = list_query(&key)?;
+        let rows: Vec<ProductDAO> = query
+            .build_query_as::<SqliteProductDAO>()
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?
+            .into_iter()
+            .map(ProductDAO::try_from)
+            .collect::<Result<_, _>>()?;
+        Ok(Page::from_rows(rows, key.limit, |product| {
+            key.order_by.cursor(key.order, product)
+        }))
     }
 
-    async fn update(
-        db: &Pool<Sqlite>,
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "product", operation = "update", key = ?key), err(Debug, level = "warn"))]
+    async fn update<'c, A>(
+        db: A,
         key: ProductBy,
         input: UpdateProductDAO,
-    ) -> Result<ProductDAO, DatabaseError> {
-        let input = SqliteProductDAO::from(input);
-        match key {
-            ProductBy::Id(uuid) => {
-                sqlx::query_as::<_, SqliteProductDAO>("UPDATE products SET name = $2, description = $3, amount = $4, price = $5, updated_at = unixepoch('now') WHERE id = $1 RETURNING id, organization_id, name, description, amount, price, created_at, updated_at")
-                    .bind(uuid)
-                    .bind(input.name)
-                    .bind(input.description)
-                    .bind(input.amount)
-                    .bind(input.price)
-                    .fetch_one(db)
-                    .await
-                    .map(ProductDAO::from)
-                    .map_err(DatabaseError::from)
+    ) -> Result<ProductDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        // See `insert`: a failed update of the name or description must be rolled back.
+        let mut tx = db.begin().await.map_err(DatabaseError::from)?;
+        let input = SqliteProductDAO::try_from(input)?;
+        let query = match &key {
+            ProductBy::Id(uuid) => sqlx::query_as::<_, SqliteProductDAO>("UPDATE products SET category_id = $2, sku = $3, barcode = $4, name = $5, description = $6, amount = $7, price = $8, currency = $9, updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE id = $1 AND version = $10 AND deleted_at IS NULL RETURNING id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version")
+                .bind(*uuid),
+            ProductBy::Sku { organization_id, sku } => sqlx::query_as::<_, SqliteProductDAO>("UPDATE products SET category_id = $3, sku = $4, barcode = $5, name = $6, description = $7, amount = $8, price = $9, currency = $10, updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE organization_id = $1 AND sku = $2 AND version = $11 AND deleted_at IS NULL RETURNING id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version")
+                .bind(*organization_id)
+                .bind(sku.clone()),
+            ProductBy::Barcode { organization_id, barcode } => sqlx::query_as::<_, SqliteProductDAO>("UPDATE products SET category_id = $3, sku = $4, barcode = $5, name = $6, description = $7, amount = $8, price = $9, currency = $10, updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE organization_id = $1 AND barcode = $2 AND version = $11 AND deleted_at IS NULL RETURNING id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version")
+                .bind(*organization_id)
+                .bind(barcode.clone()),
+        };
+        let updated = query
+            .bind(input.category_id)
+            .bind(input.sku)
+            .bind(input.barcode)
+            .bind(input.name)
+            .bind(input.description)
+            .bind(input.amount)
+            .bind(input.price)
+            .bind(input.currency)
+            .bind(input.version)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(DatabaseError::from)?;
+        match updated {
+            Some(product) => {
+                let product = ProductDAO::try_from(product)?;
+                tx.commit().await.map_err(DatabaseError::from)?;
+                Ok(product)
+            }
+            None => {
+                let missing = format!("product {key:?}");
+                let current = Self::try_get(&mut *tx, key).await;
+                Err(version_rejection(
+                    current.map(|product| product.map(|product| product.version)),
+                    missing,
+                ))
             }
         }
     }
 
-    async fn delete(db: &Pool<Sqlite>, key: ProductBy) -> Result<ProductDAO, DatabaseError> {
-        match key {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "product", operation = "delete", key = ?key), err(Debug, level = "warn"))]
+    async fn delete<'c, A>(db: A, key: ProductBy) -> Result<ProductDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
             ProductBy::Id(uuid) => sqlx::query_as::<_, SqliteProductDAO>(
-                "DELETE FROM products WHERE id = $1 RETURNING id, organization_id, name, description, amount, price, created_at, updated_at",
+                "UPDATE products SET deleted_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE id = $1 AND deleted_at IS NULL RETURNING id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version",
             )
-            .bind(uuid)
-            .fetch_one(db)
+            .bind(uuid),
+            ProductBy::Sku { organization_id, sku } => sqlx::query_as::<_, SqliteProductDAO>(
+                "UPDATE products SET deleted_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE organization_id = $1 AND sku = $2 AND deleted_at IS NULL RETURNING id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version",
+            )
+            .bind(organization_id)
+            .bind(sku),
+            ProductBy::Barcode { organization_id, barcode } => sqlx::query_as::<_, SqliteProductDAO>(
+                "UPDATE products SET deleted_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE organization_id = $1 AND barcode = $2 AND deleted_at IS NULL RETURNING id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version",
+            )
+            .bind(organization_id)
+            .bind(barcode),
+        };
+        query
+            .fetch_one(&mut *conn)
             .await
-            .map(ProductDAO::from)
-            .map_err(DatabaseError::from),
-        }
+            .map_err(DatabaseError::from)
+            .and_then(ProductDAO::try_from)
+    }
+}
+
+#[async_trait::async_trait]
+impl SoftDeleteRepository<Sqlite, ProductDAO, ProductBy> for ProductRepository {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "product", operation = "restore", key = ?key), err(Debug, level = "warn"))]
+    async fn restore<'c, A>(db: A, key: ProductBy) -> Result<ProductDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            ProductBy::Id(uuid) => sqlx::query_as::<_, SqliteProductDAO>(
+                "UPDATE products SET deleted_at = NULL, updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE id = $1 AND deleted_at IS NOT NULL RETURNING id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version",
+            )
+            .bind(uuid),
+            ProductBy::Sku { organization_id, sku } => sqlx::query_as::<_, SqliteProductDAO>(
+                "UPDATE products SET deleted_at = NULL, updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE organization_id = $1 AND sku = $2 AND deleted_at IS NOT NULL RETURNING id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version",
+            )
+            .bind(organization_id)
+            .bind(sku),
+            ProductBy::Barcode { organization_id, barcode } => sqlx::query_as::<_, SqliteProductDAO>(
+                "UPDATE products SET deleted_at = NULL, updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE organization_id = $1 AND barcode = $2 AND deleted_at IS NOT NULL RETURNING id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version",
+            )
+            .bind(organization_id)
+            .bind(barcode),
+        };
+        query
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+            .and_then(ProductDAO::try_from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "product", operation = "purge", key = ?key), err(Debug, level = "warn"))]
+    async fn purge<'c, A>(db: A, key: ProductBy) -> Result<ProductDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        // sqlx steps a statement again after SQLite reports an error, which re-runs the DELETE;
+        // rolling back keeps a product still referenced by sales from being removed later on.
+        let mut tx = db.begin().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            ProductBy::Id(uuid) => sqlx::query_as::<_, SqliteProductDAO>(
+                "DELETE FROM products WHERE id = $1 RETURNING id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version",
+            )
+            .bind(uuid),
+            ProductBy::Sku { organization_id, sku } => sqlx::query_as::<_, SqliteProductDAO>(
+                "DELETE FROM products WHERE organization_id = $1 AND sku = $2 RETURNING id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version",
+            )
+            .bind(organization_id)
+            .bind(sku),
+            ProductBy::Barcode { organization_id, barcode } => sqlx::query_as::<_, SqliteProductDAO>(
+                "DELETE FROM products WHERE organization_id = $1 AND barcode = $2 RETURNING id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version",
+            )
+            .bind(organization_id)
+            .bind(barcode),
+        };
+        let product = query
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(DatabaseError::from)
+            .and_then(ProductDAO::try_from)?;
+        tx.commit().await.map_err(DatabaseError::from)?;
+        Ok(product)
+    }
+}
+
+#[async_trait::async_trait]
+pub trait ProductSearch<DB: Database> {
+    /// Finds the live products of the organization whose name or description contain every
+    /// word of `query`, each word matching as a prefix, best matches first. Operators and
+    /// quotes in `query` are taken literally; a query without words finds nothing.
+    async fn search<'c, A>(
+        db: A,
+        organization_id: Uuid,
+        query: &str,
+        limit: i32,
+    ) -> Result<Vec<ProductSearchResultDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = DB> + Send;
+}
+
+/// Turns the words of a search into an FTS5 query matching rows that contain all of them as
+/// prefixes, e.g. `"iph"* "pro"*`. Each word is quoted, so FTS5 syntax in it is not
+/// interpreted.
+fn fts5_query(query: &str) -> Option<String> {
+    let terms = query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+#[async_trait::async_trait]
+impl ProductSearch<Sqlite> for ProductRepository {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "product", operation = "search", key = ?organization_id), err(Debug, level = "warn"))]
+    async fn search<'c, A>(
+        db: A,
+        organization_id: Uuid,
+        query: &str,
+        limit: i32,
+    ) -> Result<Vec<ProductSearchResultDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let Some(query) = fts5_query(query) else {
+            return Ok(vec![]);
+        };
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        // bm25 scores better matches lower; names weigh ten times as much as descriptions
+        sqlx::query_as::<_, SqliteProductSearchRow>(
+            "SELECT products.id, products.organization_id, products.category_id, products.sku, products.barcode, products.name, products.description, products.amount, products.price, products.currency, products.created_at, products.updated_at, products.version, highlight(products_search, 1, '[', ']') AS name_highlight, snippet(products_search, 2, '[', ']', '...', 16) AS snippet, -bm25(products_search, 0.0, 10.0, 1.0) AS rank FROM products_search JOIN products ON products.id = products_search.product_id WHERE products_search MATCH $2 AND products.organization_id = $1 AND products.deleted_at IS NULL ORDER BY rank DESC, products.id LIMIT $3",
+        )
+        .bind(organization_id)
+        .bind(query)
+        .bind(i64::from(limit))
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)?
+        .into_iter()
+        .map(ProductSearchResultDAO::try_from)
+        .collect()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        entities::organization::{NewOrganizationDAO, OrganizationDAO, OrganizationRepository},
+        entities::{
+            category::{
+                CategoriesWhere, CategoryBy, CategoryDAO, CategoryRepository, NewCategoryDAO,
+                UpdateCategoryDAO,
+            },
+            organization::{
+                NewOrganizationDAO, OrganizationBy, OrganizationDAO, OrganizationRepository,
+                OrganizationsWhere, UpdateOrganizationDAO,
+            },
+            tag::{NewTagDAO, ProductTags, TagBy, TagDAO, TagRepository, TagsWhere, UpdateTagDAO},
+        },
         sqlite::DatabaseRepository,
     };
+    use proptest::prelude::*;
+    use sqlx::Database;
 
     use super::*;
+    use sqlx::Pool;
 
-    async fn create_organization(pool: &Pool<Sqlite>, name: &str) -> OrganizationDAO {
+    async fn create_organization<DB: Database>(pool: &Pool<DB>, name: &str) -> OrganizationDAO
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+    {
         OrganizationRepository::insert(
             pool,
             NewOrganizationDAO {
                 name: name.to_string(),
+                reporting_currency: Currency::USD,
             },
         )
         .await
         .expect("Could not create organization")
     }
 
-    #[tokio::test]
-    async fn queries() {
-        let db = DatabaseRepository::new()
-            .await
-            .expect("Could not initialize db");
-
-        let organization = create_organization(&db.connection, "test").await;
+    async fn queries<DB: Database>(db: &Pool<DB>)
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+        ProductRepository: EntityRepository<
+            DB,
+            ProductDAO,
+            NewProductDAO,
+            UpdateProductDAO,
+            ProductBy,
+            ProductsWhere,
+        >,
+    {
+        let organization = create_organization(db, "test").await;
 
         let product = ProductRepository::insert(
-            &db.connection,
+            db,
             NewProductDAO {
                 organization_id: organization.id,
+                category_id: None,
+                sku: None,
+                barcode: None,
                 name: "Iphone".to_string(),
                 description: "smartphone".to_string(),
                 amount: 10,
-                price: BigUint::from(5000u32),
+                price: Money::new(5000, Currency::USD),
             },
         )
         .await
@@ -251,38 +783,41 @@ mod tests {
         assert_eq!(product.name, "Iphone");
         assert_eq!(product.description, "smartphone");
         assert_eq!(product.amount, 10);
-        assert_eq!(product.price, BigUint::from(5000u32));
+        assert_eq!(product.price, Money::new(5000, Currency::USD));
 
-        let product = ProductRepository::get(&db.connection, ProductBy::Id(product.id))
+        let product = ProductRepository::get(db, ProductBy::Id(product.id))
             .await
             .expect("Could not find a new product");
 
         assert_eq!(product.name, "Iphone");
         assert_eq!(product.description, "smartphone");
         assert_eq!(product.amount, 10);
-        assert_eq!(product.price, BigUint::from(5000u32));
+        assert_eq!(product.price, Money::new(5000, Currency::USD));
 
-        let maybe_product =
-            ProductRepository::try_get(&db.connection, ProductBy::Id(Uuid::default()))
-                .await
-                .expect("Could not find a new product");
+        let maybe_product = ProductRepository::try_get(db, ProductBy::Id(Uuid::default()))
+            .await
+            .expect("Could not find a new product");
 
         assert!(maybe_product.is_none());
 
-        let maybe_product = ProductRepository::try_get(&db.connection, ProductBy::Id(product.id))
+        let maybe_product = ProductRepository::try_get(db, ProductBy::Id(product.id))
             .await
             .expect("Could not find a new product");
 
         assert!(maybe_product.is_some());
 
         let updated_product = ProductRepository::update(
-            &db.connection,
+            db,
             ProductBy::Id(product.id),
             UpdateProductDAO {
+                category_id: None,
+                sku: None,
+                barcode: None,
                 name: "Iphone XR".to_string(),
                 description: "smartphone premium".to_string(),
                 amount: 11,
-                price: BigUint::from(4000u32),
+                price: Money::new(4000, Currency::USD),
+                version: product.version,
             },
         )
         .await
@@ -293,14 +828,1062 @@ mod tests {
         assert!(product.amount != updated_product.amount);
         assert!(product.price != updated_product.price);
 
-        let deleted = ProductRepository::delete(&db.connection, ProductBy::Id(product.id))
+        let deleted = ProductRepository::delete(db, ProductBy::Id(product.id))
             .await
             .expect("Could not delete a product");
 
-        let maybe_product = ProductRepository::try_get(&db.connection, ProductBy::Id(deleted.id))
+        let maybe_product = ProductRepository::try_get(db, ProductBy::Id(deleted.id))
             .await
             .expect("Could not find a product");
 
         assert!(maybe_product.is_none());
     }
+
+    async fn listing<DB: Database>(db: &Pool<DB>)
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+        ProductRepository: EntityRepository<
+            DB,
+            ProductDAO,
+            NewProductDAO,
+            UpdateProductDAO,
+            ProductBy,
+            ProductsWhere,
+        >,
+    {
+        let organization = create_organization(db, "listing").await;
+        let other = create_organization(db, "other").await;
+
+        for (organization_id, name, amount, price, currency) in [
+            (organization.id, "Iphone", 3, 5000, Currency::USD),
+            (organization.id, "Ipad", 1, 7000, Currency::USD),
+            (organization.id, "Macbook", 2, 9000, Currency::USD),
+            (organization.id, "100%_cotton", 5, 100, Currency::USD),
+            (other.id, "Iphone", 4, 5000, Currency::USD),
+            (other.id, "Airpods", 6, 6000, Currency::EUR),
+        ] {
+            ProductRepository::insert(
+                db,
+                NewProductDAO {
+                    organization_id,
+                    category_id: None,
+                    sku: None,
+                    barcode: None,
+                    name: name.to_string(),
+                    description: String::new(),
+                    amount,
+                    price: Money::new(price, currency),
+                },
+            )
+            .await
+            .expect("Could not create a new product");
+        }
+
+        let names = |page: Page<ProductDAO>| {
+            page.items
+                .into_iter()
+                .map(|p| p.name)
+                .collect::<Vec<String>>()
+        };
+
+        let products = ProductRepository::get_all(
+            db,
+            ProductsWhere {
+                organization_id: Some(organization.id),
+                name_prefix: Some("ip".to_string()),
+                order_by: ProductsOrderBy::Name,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list products");
+        assert_eq!(names(products), vec!["Ipad", "Iphone"]);
+
+        let products = ProductRepository::get_all(
+            db,
+            ProductsWhere {
+                name_prefix: Some("100%_".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list products");
+        assert_eq!(names(products), vec!["100%_cotton"]);
+
+        let products = ProductRepository::get_all(
+            db,
+            ProductsWhere {
+                organization_id: Some(organization.id),
+                min_price: Some(Money::new(5000, Currency::USD)),
+                max_price: Some(Money::new(7000, Currency::USD)),
+                order_by: ProductsOrderBy::Amount,
+                order: SortOrder::Descending,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list products");
+        assert_eq!(names(products), vec!["Iphone", "Ipad"]);
+
+        let products = ProductRepository::get_all(
+            db,
+            ProductsWhere {
+                organization_id: Some(organization.id),
+                order_by: ProductsOrderBy::Price,
+                order: SortOrder::Descending,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list products");
+        assert_eq!(
+            names(products),
+            vec!["Macbook", "Ipad", "Iphone", "100%_cotton"]
+        );
+
+        // Prices in another currency never fall into a range.
+        let products = ProductRepository::get_all(
+            db,
+            ProductsWhere {
+                min_price: Some(Money::new(5500, Currency::USD)),
+                max_price: Some(Money::new(6500, Currency::USD)),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list products");
+        assert!(products.items.is_empty());
+
+        let products = ProductRepository::get_all(
+            db,
+            ProductsWhere {
+                currency: Some(Currency::EUR),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list products");
+        assert_eq!(names(products), vec!["Airpods"]);
+
+        let mut after = None;
+        let mut pages = vec![];
+        loop {
+            let page = ProductRepository::get_all(
+                db,
+                ProductsWhere {
+                    organization_id: Some(organization.id),
+                    min_price: Some(Money::new(1000, Currency::USD)),
+                    order_by: ProductsOrderBy::Amount,
+                    limit: 1,
+                    after,
+                    ..Default::default()
+                },
+            )
+            .await
+            .expect("Could not list products");
+            after = page.next_cursor.clone();
+            pages.push(names(page));
+            if after.is_none() {
+                break;
+            }
+        }
+        assert_eq!(pages, vec![vec!["Ipad"], vec!["Macbook"], vec!["Iphone"]]);
+
+        let first = ProductRepository::get_all(
+            db,
+            ProductsWhere {
+                organization_id: Some(organization.id),
+                order_by: ProductsOrderBy::Amount,
+                limit: 2,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list products");
+        let after = first.next_cursor.clone();
+        assert!(after.is_some());
+        assert_eq!(names(first), vec!["Ipad", "Macbook"]);
+
+        // Rows inserted before the cursor do not shift the next page.
+        ProductRepository::insert(
+            db,
+            NewProductDAO {
+                organization_id: organization.id,
+                category_id: None,
+                sku: None,
+                barcode: None,
+                name: "Cable".to_string(),
+                description: String::new(),
+                amount: 0,
+                price: Money::new(10, Currency::USD),
+            },
+        )
+        .await
+        .expect("Could not create a new product");
+
+        let second = ProductRepository::get_all(
+            db,
+            ProductsWhere {
+                organization_id: Some(organization.id),
+                order_by: ProductsOrderBy::Amount,
+                limit: 2,
+                after: after.clone(),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list products");
+        assert_eq!(second.next_cursor, None);
+        assert_eq!(names(second), vec!["Iphone", "100%_cotton"]);
+
+        let mismatch = ProductRepository::get_all(
+            db,
+            ProductsWhere {
+                order_by: ProductsOrderBy::Name,
+                after,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(mismatch, DatabaseError::InvalidCursor);
+
+        let products = ProductRepository::get_all(
+            db,
+            ProductsWhere {
+                created_before: Some(Utc::now() - chrono::Duration::days(1)),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list products");
+        assert!(products.items.is_empty());
+    }
+
+    async fn concurrent_updates<DB: Database>(db: &Pool<DB>)
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+        ProductRepository: EntityRepository<
+                DB,
+                ProductDAO,
+                NewProductDAO,
+                UpdateProductDAO,
+                ProductBy,
+                ProductsWhere,
+            > + SoftDeleteRepository<DB, ProductDAO, ProductBy>,
+    {
+        let organization = create_organization(db, "test").await;
+        let product = ProductRepository::insert(
+            db,
+            NewProductDAO {
+                organization_id: organization.id,
+                category_id: None,
+                sku: None,
+                barcode: None,
+                name: "Iphone".to_string(),
+                description: "smartphone".to_string(),
+                amount: 10,
+                price: Money::new(5000, Currency::USD),
+            },
+        )
+        .await
+        .expect("Could not create a new product");
+        assert_eq!(product.version, 1);
+
+        let restock = |amount: u32, version: i64| UpdateProductDAO {
+            category_id: None,
+            sku: None,
+            barcode: None,
+            name: "Iphone".to_string(),
+            description: "smartphone".to_string(),
+            amount,
+            price: Money::new(5000, Currency::USD),
+            version,
+        };
+
+        // Two admins read version 1; the first write wins and the second one is refused
+        let first = ProductRepository::update(db, ProductBy::Id(product.id), restock(20, 1))
+            .await
+            .expect("Could not update product");
+        assert_eq!(first.version, 2);
+        let second = ProductRepository::update(db, ProductBy::Id(product.id), restock(15, 1)).await;
+        assert_eq!(second, Err(DatabaseError::Conflict { current_version: 2 }));
+        let stored = ProductRepository::get(db, ProductBy::Id(product.id))
+            .await
+            .expect("Could not find product");
+        assert_eq!(stored.amount, 20);
+
+        // Retrying on top of the current version goes through
+        let retried =
+            ProductRepository::update(db, ProductBy::Id(product.id), restock(25, stored.version))
+                .await
+                .expect("Could not update product");
+        assert_eq!(retried.amount, 25);
+        assert_eq!(retried.version, 3);
+
+        let missing =
+            ProductRepository::update(db, ProductBy::Id(Uuid::new_v4()), restock(1, 1)).await;
+        assert!(matches!(missing, Err(DatabaseError::NotFound(_))));
+
+        let deleted = ProductRepository::delete(db, ProductBy::Id(product.id))
+            .await
+            .expect("Could not delete product");
+        assert_eq!(deleted.version, 4);
+        let hidden = ProductRepository::update(db, ProductBy::Id(product.id), restock(1, 4)).await;
+        assert!(matches!(hidden, Err(DatabaseError::NotFound(_))));
+        let restored = ProductRepository::restore(db, ProductBy::Id(product.id))
+            .await
+            .expect("Could not restore product");
+        assert_eq!(restored.version, 5);
+    }
+
+    #[tokio::test]
+    async fn sqlite_queries() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        queries(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_queries() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            queries(&db.connection).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn sqlite_listing() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        listing(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_listing() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            listing(&db.connection).await;
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn new_quantities_round_trip_or_are_rejected(amount in any::<u32>()) {
+            let input = NewProductDAO {
+                organization_id: Uuid::new_v4(),
+                category_id: None,
+                sku: None,
+                barcode: None,
+                name: "Iphone".to_string(),
+                description: "smartphone".to_string(),
+                amount,
+                price: Money::new(5000, Currency::USD),
+            };
+            match SqliteProductDAO::try_from(input) {
+                Ok(row) => {
+                    prop_assert!(i32::try_from(amount).is_ok());
+                    prop_assert_eq!(ProductDAO::try_from(row).map(|p| p.amount), Ok(amount));
+                }
+                Err(e) => {
+                    prop_assert!(i32::try_from(amount).is_err());
+                    prop_assert_eq!(e, DatabaseError::InvalidQuantity(amount.into()));
+                }
+            }
+        }
+
+        #[test]
+        fn stored_quantities_round_trip_or_are_rejected(amount in any::<i32>()) {
+            let row = SqliteProductDAO {
+                category_id: None,
+                sku: None,
+                barcode: None,
+                id: Uuid::new_v4(),
+                organization_id: Uuid::new_v4(),
+                name: "Iphone".to_string(),
+                description: "smartphone".to_string(),
+                amount,
+                price: 5000,
+                currency: Currency::USD,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                version: 1,
+            };
+            match ProductDAO::try_from(row.clone()) {
+                Ok(product) => {
+                    prop_assert!(amount >= 0);
+                    prop_assert_eq!(SqliteProductDAO::try_from(product), Ok(row));
+                }
+                Err(e) => {
+                    prop_assert!(amount < 0);
+                    prop_assert_eq!(e, DatabaseError::InvalidQuantity(amount.into()));
+                }
+            }
+        }
+    }
+
+    async fn quantity_limits<DB: Database>(db: &Pool<DB>) -> ProductDAO
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+        ProductRepository: EntityRepository<
+            DB,
+            ProductDAO,
+            NewProductDAO,
+            UpdateProductDAO,
+            ProductBy,
+            ProductsWhere,
+        >,
+    {
+        let organization = create_organization(db, "test").await;
+        let new_product = |amount| NewProductDAO {
+            organization_id: organization.id,
+            category_id: None,
+            sku: None,
+            barcode: None,
+            name: "Iphone".to_string(),
+            description: "smartphone".to_string(),
+            amount,
+            price: Money::new(5000, Currency::USD),
+        };
+        let largest = i32::MAX.unsigned_abs();
+        let too_large = ProductRepository::insert(db, new_product(largest + 1)).await;
+        assert_eq!(
+            too_large,
+            Err(DatabaseError::InvalidQuantity(i64::from(largest) + 1))
+        );
+
+        let product = ProductRepository::insert(db, new_product(largest))
+            .await
+            .expect("Could not create a new product");
+        assert_eq!(product.amount, largest);
+        let too_large = ProductRepository::update(
+            db,
+            ProductBy::Id(product.id),
+            UpdateProductDAO {
+                category_id: None,
+                sku: None,
+                barcode: None,
+                name: product.name.clone(),
+                description: product.description.clone(),
+                amount: u32::MAX,
+                price: product.price,
+                version: product.version,
+            },
+        )
+        .await;
+        assert_eq!(
+            too_large,
+            Err(DatabaseError::InvalidQuantity(u32::MAX.into()))
+        );
+        product
+    }
+
+    async fn timestamps<DB: Database>(db: &Pool<DB>) -> ProductDAO
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+        ProductRepository: EntityRepository<
+            DB,
+            ProductDAO,
+            NewProductDAO,
+            UpdateProductDAO,
+            ProductBy,
+            ProductsWhere,
+        >,
+    {
+        let organization = create_organization(db, "test").await;
+        assert_eq!(organization.created_at, organization.updated_at);
+        let product = ProductRepository::insert(
+            db,
+            NewProductDAO {
+                organization_id: organization.id,
+                category_id: None,
+                sku: None,
+                barcode: None,
+                name: "Iphone".to_string(),
+                description: "smartphone".to_string(),
+                amount: 10,
+                price: Money::new(5000, Currency::USD),
+            },
+        )
+        .await
+        .expect("Could not create a new product");
+        let fetched = ProductRepository::get(db, ProductBy::Id(product.id))
+            .await
+            .expect("Could not get product");
+        assert_eq!(fetched, product);
+
+        // Bounds one microsecond apart tell the stored instant apart, so it was read back with
+        // its sub-second part intact.
+        let microsecond = chrono::Duration::microseconds(1);
+        let count = |created_after, created_before| async move {
+            ProductRepository::get_all(
+                db,
+                ProductsWhere {
+                    organization_id: Some(organization.id),
+                    created_after,
+                    created_before,
+                    ..Default::default()
+                },
+            )
+            .await
+            .expect("Could not list products")
+            .items
+            .len()
+        };
+        assert_eq!(count(Some(product.created_at), None).await, 1);
+        assert_eq!(count(Some(product.created_at + microsecond), None).await, 0);
+        assert_eq!(count(None, Some(product.created_at)).await, 0);
+        assert_eq!(count(None, Some(product.created_at + microsecond)).await, 1);
+
+        tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+        let updated = ProductRepository::update(
+            db,
+            ProductBy::Id(product.id),
+            UpdateProductDAO {
+                category_id: None,
+                sku: None,
+                barcode: None,
+                name: "Iphone".to_string(),
+                description: "refurbished".to_string(),
+                amount: 10,
+                price: Money::new(4000, Currency::USD),
+                version: product.version,
+            },
+        )
+        .await
+        .expect("Could not update product");
+        assert_eq!(updated.created_at, product.created_at);
+        assert!(updated.updated_at > product.updated_at);
+        let fetched = ProductRepository::get(db, ProductBy::Id(product.id))
+            .await
+            .expect("Could not get product");
+        assert_eq!(fetched, updated);
+        updated
+    }
+
+    #[tokio::test]
+    async fn sqlite_concurrent_updates() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        concurrent_updates(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_concurrent_updates() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            concurrent_updates(&db.connection).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn sqlite_timestamps() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        let product = timestamps(&db.connection).await;
+
+        // Updates made outside of the repository are stamped by a trigger.
+        tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+        sqlx::query("UPDATE products SET amount = 0 WHERE id = $1")
+            .bind(product.id)
+            .execute(&db.connection)
+            .await
+            .expect("Could not update product");
+        let touched = ProductRepository::get(&db.connection, ProductBy::Id(product.id))
+            .await
+            .expect("Could not get product");
+        assert!(touched.updated_at > product.updated_at);
+
+        // The clock of SQLite has millisecond resolution, below that its timestamps are padding
+        for stamp in [product.created_at, product.updated_at, touched.updated_at] {
+            assert_eq!(stamp.timestamp_subsec_micros() % 1000, 0, "{stamp}");
+        }
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_timestamps() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            let product = timestamps(&db.connection).await;
+
+            sqlx::query("UPDATE products SET amount = 0 WHERE id = $1")
+                .bind(product.id)
+                .execute(&db.connection)
+                .await
+                .expect("Could not update product");
+            let touched = ProductRepository::get(&db.connection, ProductBy::Id(product.id))
+                .await
+                .expect("Could not get product");
+            assert!(touched.updated_at > product.updated_at);
+        }
+    }
+
+    #[tokio::test]
+    async fn sqlite_quantity_limits() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        let product = quantity_limits(&db.connection).await;
+
+        let negative = sqlx::query("UPDATE products SET amount = -1 WHERE id = $1")
+            .bind(product.id)
+            .execute(&db.connection)
+            .await
+            .map_err(DatabaseError::from);
+        assert_eq!(
+            negative.map(|_| ()),
+            Err(DatabaseError::CheckViolation {
+                constraint: Some("products_amount_non_negative".to_string())
+            })
+        );
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_quantity_limits() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            let product = quantity_limits(&db.connection).await;
+
+            let negative = sqlx::query("UPDATE products SET amount = -1 WHERE id = $1")
+                .bind(product.id)
+                .execute(&db.connection)
+                .await
+                .map_err(DatabaseError::from);
+            assert_eq!(
+                negative.map(|_| ()),
+                Err(DatabaseError::CheckViolation {
+                    constraint: Some("products_amount_non_negative".to_string())
+                })
+            );
+        }
+    }
+
+    async fn search<DB: Database>(db: &Pool<DB>)
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+        ProductRepository: EntityRepository<
+                DB,
+                ProductDAO,
+                NewProductDAO,
+                UpdateProductDAO,
+                ProductBy,
+                ProductsWhere,
+            > + SoftDeleteRepository<DB, ProductDAO, ProductBy>
+            + ProductSearch<DB>,
+    {
+        let organization = create_organization(db, "test").await;
+        let other = create_organization(db, "other").await;
+        let mut products = vec![];
+        for (organization_id, name, description) in [
+            (
+                organization.id,
+                "Iphone 14",
+                "A smartphone with a great camera",
+            ),
+            (
+                organization.id,
+                "Phone case",
+                "Protects your iphone from drops",
+            ),
+            (
+                organization.id,
+                "Charger",
+                "Fast charging for any smartphone",
+            ),
+            (other.id, "Iphone 13", "Last year's smartphone"),
+        ] {
+            let product = ProductRepository::insert(
+                db,
+                NewProductDAO {
+                    organization_id,
+                    category_id: None,
+                    sku: None,
+                    barcode: None,
+                    name: name.to_string(),
+                    description: description.to_string(),
+                    amount: 10,
+                    price: Money::new(5000, Currency::USD),
+                },
+            )
+            .await
+            .expect("Could not create a new product");
+            products.push(product);
+        }
+        let names = |results: &[ProductSearchResultDAO]| {
+            results
+                .iter()
+                .map(|result| result.product.name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        // Matches in the name rank above matches in the description
+        let results = ProductRepository::search(db, organization.id, "IPH", 10)
+            .await
+            .expect("Could not search products");
+        assert_eq!(names(&results), vec!["Iphone 14", "Phone case"]);
+        assert_eq!(results[0].product, products[0]);
+        assert_eq!(results[0].name, "[Iphone] 14");
+        assert!(results[1].snippet.contains("[iphone]"));
+        assert!(results[0].rank > results[1].rank);
+
+        let results = ProductRepository::search(db, organization.id, "smartphone camera", 10)
+            .await
+            .expect("Could not search products");
+        assert_eq!(names(&results), vec!["Iphone 14"]);
+        let results = ProductRepository::search(db, organization.id, "smartphone", 1)
+            .await
+            .expect("Could not search products");
+        assert_eq!(results.len(), 1);
+
+        // Search syntax is taken literally
+        for query in ["\"iphone", "iphone OR", "NOT iphone*", "(iphone", "", "  "] {
+            ProductRepository::search(db, organization.id, query, 10)
+                .await
+                .expect("Could not search products");
+        }
+
+        // The index follows updates and deletions
+        ProductRepository::update(
+            db,
+            ProductBy::Id(products[2].id),
+            UpdateProductDAO {
+                category_id: None,
+                sku: None,
+                barcode: None,
+                name: "Wireless charger".to_string(),
+                description: "Charges any iphone".to_string(),
+                amount: 10,
+                price: Money::new(5000, Currency::USD),
+                version: products[2].version,
+            },
+        )
+        .await
+        .expect("Could not update product");
+        let results = ProductRepository::search(db, organization.id, "wireless", 10)
+            .await
+            .expect("Could not search products");
+        assert_eq!(names(&results), vec!["Wireless charger"]);
+        let results = ProductRepository::search(db, organization.id, "fast", 10)
+            .await
+            .expect("Could not search products");
+        assert!(results.is_empty());
+
+        ProductRepository::delete(db, ProductBy::Id(products[0].id))
+            .await
+            .expect("Could not delete product");
+        ProductRepository::purge(db, ProductBy::Id(products[1].id))
+            .await
+            .expect("Could not purge product");
+        let results = ProductRepository::search(db, organization.id, "iphone", 10)
+            .await
+            .expect("Could not search products");
+        assert_eq!(names(&results), vec!["Wireless charger"]);
+    }
+
+    #[tokio::test]
+    async fn sqlite_search() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        search(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_search() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            search(&db.connection).await;
+        }
+    }
+
+    async fn catalog<DB: Database>(db: &Pool<DB>)
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+        ProductRepository: EntityRepository<
+                DB,
+                ProductDAO,
+                NewProductDAO,
+                UpdateProductDAO,
+                ProductBy,
+                ProductsWhere,
+            > + ProductTags<DB>,
+        CategoryRepository: EntityRepository<
+            DB,
+            CategoryDAO,
+            NewCategoryDAO,
+            UpdateCategoryDAO,
+            CategoryBy,
+            CategoriesWhere,
+        >,
+        TagRepository: EntityRepository<DB, TagDAO, NewTagDAO, UpdateTagDAO, TagBy, TagsWhere>,
+    {
+        let organization = create_organization(db, "test").await;
+        let other = create_organization(db, "other").await;
+        let category = |parent_id, name: &str| NewCategoryDAO {
+            organization_id: organization.id,
+            parent_id,
+            name: name.to_string(),
+        };
+        let electronics = CategoryRepository::insert(db, category(None, "Electronics"))
+            .await
+            .expect("Could not create category");
+        let phones = CategoryRepository::insert(db, category(Some(electronics.id), "Phones"))
+            .await
+            .expect("Could not create subcategory");
+        let groceries = CategoryRepository::insert(db, category(None, "Groceries"))
+            .await
+            .expect("Could not create category");
+        let product = |organization_id, category_id, sku: &str, name: &str| NewProductDAO {
+            organization_id,
+            category_id,
+            sku: Some(sku.to_string()),
+            barcode: Some(format!("0{sku}")),
+            name: name.to_string(),
+            description: "".to_string(),
+            amount: 10,
+            price: Money::new(5000, Currency::USD),
+        };
+        let iphone = ProductRepository::insert(
+            db,
+            product(organization.id, Some(phones.id), "IP-14", "Iphone 14"),
+        )
+        .await
+        .expect("Could not create product");
+        let charger = ProductRepository::insert(
+            db,
+            product(organization.id, Some(electronics.id), "CH-1", "Charger"),
+        )
+        .await
+        .expect("Could not create product");
+        let coffee = ProductRepository::insert(
+            db,
+            product(organization.id, Some(groceries.id), "CO-1", "Coffee"),
+        )
+        .await
+        .expect("Could not create product");
+
+        // SKUs and barcodes are unique within an organization only
+        let duplicate = ProductRepository::insert(
+            db,
+            NewProductDAO {
+                barcode: None,
+                ..product(organization.id, None, "IP-14", "Iphone 14 Pro")
+            },
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            matches!(&duplicate, DatabaseError::UniqueViolation { constraint } if constraint.contains("sku")),
+            "{duplicate:?}"
+        );
+        let duplicate = ProductRepository::insert(
+            db,
+            NewProductDAO {
+                sku: None,
+                ..product(organization.id, None, "IP-14", "Iphone 14 Pro")
+            },
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            matches!(&duplicate, DatabaseError::UniqueViolation { constraint } if constraint.contains("barcode")),
+            "{duplicate:?}"
+        );
+        let elsewhere = ProductRepository::insert(db, product(other.id, None, "IP-14", "Iphone"))
+            .await
+            .expect("Could not reuse a SKU in another organization");
+
+        // Products without a SKU or barcode do not collide
+        for name in ["Loose beans", "Loose tea"] {
+            ProductRepository::insert(
+                db,
+                NewProductDAO {
+                    sku: None,
+                    barcode: None,
+                    ..product(organization.id, None, "", name)
+                },
+            )
+            .await
+            .expect("Could not create product without SKU");
+        }
+
+        // A product cannot be filed under another organization's category
+        let foreign =
+            ProductRepository::insert(db, product(other.id, Some(phones.id), "IP-15", "Iphone 15"))
+                .await
+                .unwrap_err();
+        assert!(matches!(foreign, DatabaseError::ForeignKeyViolation { .. }));
+
+        let found = ProductRepository::get(
+            db,
+            ProductBy::Sku {
+                organization_id: organization.id,
+                sku: "IP-14".to_string(),
+            },
+        )
+        .await
+        .expect("Could not get product by SKU");
+        assert_eq!(found, iphone);
+        let found = ProductRepository::get(
+            db,
+            ProductBy::Barcode {
+                organization_id: other.id,
+                barcode: "0IP-14".to_string(),
+            },
+        )
+        .await
+        .expect("Could not get product by barcode");
+        assert_eq!(found, elsewhere);
+        assert!(ProductRepository::try_get(
+            db,
+            ProductBy::Sku {
+                organization_id: other.id,
+                sku: "CO-1".to_string(),
+            },
+        )
+        .await
+        .expect("Could not look up product by SKU")
+        .is_none());
+
+        let updated = ProductRepository::update(
+            db,
+            ProductBy::Sku {
+                organization_id: organization.id,
+                sku: "CH-1".to_string(),
+            },
+            UpdateProductDAO {
+                category_id: Some(phones.id),
+                sku: Some("CH-2".to_string()),
+                barcode: None,
+                name: charger.name.clone(),
+                description: charger.description.clone(),
+                amount: charger.amount,
+                price: charger.price,
+                version: charger.version,
+            },
+        )
+        .await
+        .expect("Could not update product by SKU");
+        assert_eq!(updated.category_id, Some(phones.id));
+        assert_eq!(updated.sku.as_deref(), Some("CH-2"));
+        assert_eq!(updated.barcode, None);
+
+        // A category includes the products of its subcategories
+        let names = |page: Page<ProductDAO>| {
+            page.items
+                .into_iter()
+                .map(|product| product.name)
+                .collect::<Vec<_>>()
+        };
+        let in_category = |category_id| ProductsWhere {
+            organization_id: Some(organization.id),
+            category_id: Some(category_id),
+            order_by: ProductsOrderBy::Name,
+            ..Default::default()
+        };
+        let listed = ProductRepository::get_all(db, in_category(electronics.id))
+            .await
+            .expect("Could not list products of a category");
+        assert_eq!(names(listed), vec!["Charger", "Iphone 14"]);
+        let listed = ProductRepository::get_all(db, in_category(groceries.id))
+            .await
+            .expect("Could not list products of a category");
+        assert_eq!(names(listed), vec!["Coffee"]);
+
+        let sale = TagRepository::insert(
+            db,
+            NewTagDAO {
+                organization_id: organization.id,
+                name: "sale".to_string(),
+            },
+        )
+        .await
+        .expect("Could not create tag");
+        for id in [iphone.id, coffee.id] {
+            ProductRepository::add_tag(db, id, sale.id)
+                .await
+                .expect("Could not tag product");
+        }
+        let on_sale = |category_id| ProductsWhere {
+            tag_id: Some(sale.id),
+            ..in_category(category_id)
+        };
+        let listed = ProductRepository::get_all(db, on_sale(electronics.id))
+            .await
+            .expect("Could not list tagged products");
+        assert_eq!(names(listed), vec!["Iphone 14"]);
+        let listed = ProductRepository::get_all(
+            db,
+            ProductsWhere {
+                tag_id: Some(sale.id),
+                order_by: ProductsOrderBy::Name,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list tagged products");
+        assert_eq!(names(listed), vec!["Coffee", "Iphone 14"]);
+    }
+
+    #[tokio::test]
+    async fn sqlite_catalog() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        catalog(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_catalog() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            catalog(&db.connection).await;
+        }
+    }
 }
@@ -0,0 +1,508 @@
+use chrono::{DateTime, Utc};
+use sqlx::{Acquire, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use super::{
+    NewProductDAO, ProductBy, ProductDAO, ProductRepository, ProductSearch, ProductSearchResultDAO,
+    ProductsWhere, UpdateProductDAO,
+};
+use crate::{
+    entities::{like_prefix, quantity_from_column, quantity_to_column, version_rejection},
+    money::{Currency, Money},
+    pagination::{push_after_postgres, Page},
+    traits::{DatabaseError, EntityRepository, SoftDeleteRepository},
+};
+
+#[derive(sqlx::FromRow, Debug, PartialEq, Eq, Clone)]
+pub struct PostgresProductDAO {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    pub category_id: Option<Uuid>,
+    pub sku: Option<String>,
+    pub barcode: Option<String>,
+    pub name: String,
+    pub description: String,
+    pub amount: i32,
+    pub price: i64,
+    pub currency: Currency,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub version: i64,
+}
+
+impl TryFrom<ProductDAO> for PostgresProductDAO {
+    type Error = DatabaseError;
+
+    fn try_from(value: ProductDAO) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: value.id,
+            organization_id: value.organization_id,
+            category_id: value.category_id,
+            sku: value.sku,
+            barcode: value.barcode,
+            name: value.name,
+            amount: quantity_to_column(value.amount)?,
+            price: value.price.minor_units,
+            currency: value.price.currency,
+            description: value.description,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            version: value.version,
+        })
+    }
+}
+
+impl TryFrom<NewProductDAO> for PostgresProductDAO {
+    type Error = DatabaseError;
+
+    fn try_from(value: NewProductDAO) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: Uuid::new_v4(),
+            organization_id: value.organization_id,
+            category_id: value.category_id,
+            sku: value.sku,
+            barcode: value.barcode,
+            name: value.name,
+            amount: quantity_to_column(value.amount)?,
+            price: value.price.minor_units,
+            currency: value.price.currency,
+            description: value.description,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            version: 1,
+        })
+    }
+}
+
+impl TryFrom<UpdateProductDAO> for PostgresProductDAO {
+    type Error = DatabaseError;
+
+    fn try_from(value: UpdateProductDAO) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: Uuid::default(),
+            organization_id: Uuid::default(),
+            category_id: value.category_id,
+            sku: value.sku,
+            barcode: value.barcode,
+            name: value.name,
+            amount: quantity_to_column(value.amount)?,
+            price: value.price.minor_units,
+            currency: value.price.currency,
+            description: value.description,
+            created_at: DateTime::default(),
+            updated_at: DateTime::default(),
+            version: value.version,
+        })
+    }
+}
+
+impl TryFrom<PostgresProductDAO> for ProductDAO {
+    type Error = DatabaseError;
+
+    fn try_from(value: PostgresProductDAO) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: value.id,
+            organization_id: value.organization_id,
+            category_id: value.category_id,
+            sku: value.sku,
+            barcode: value.barcode,
+            description: value.description,
+            name: value.name,
+            amount: quantity_from_column(value.amount)?,
+            price: Money::new(value.price, value.currency),
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            version: value.version,
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct PostgresProductSearchRow {
+    #[sqlx(flatten)]
+    product: PostgresProductDAO,
+    name_highlight: String,
+    snippet: String,
+    rank: f64,
+}
+
+impl TryFrom<PostgresProductSearchRow> for ProductSearchResultDAO {
+    type Error = DatabaseError;
+
+    fn try_from(value: PostgresProductSearchRow) -> Result<Self, Self::Error> {
+        Ok(Self {
+            product: ProductDAO::try_from(value.product)?,
+            name: value.name_highlight,
+            snippet: value.snippet,
+            rank: value.rank,
+        })
+    }
+}
+
+/// Turns the words of a search into a `tsquery` matching rows that contain all of them as
+/// prefixes, e.g. `'iph':* & 'pro':*`. Each word is quoted, so `tsquery` syntax in it is not
+/// interpreted.
+fn tsquery(query: &str) -> Option<String> {
+    let terms = query
+        .split_whitespace()
+        .map(|word| format!("'{}':*", word.replace('\\', "\\\\").replace('\'', "''")))
+        .collect::<Vec<_>>();
+    (!terms.is_empty()).then(|| terms.join(" & "))
+}
+
+#[async_trait::async_trait]
+impl
+    EntityRepository<
+        Postgres,
+        ProductDAO,
+        NewProductDAO,
+        UpdateProductDAO,
+        ProductBy,
+        ProductsWhere,
+    > for ProductRepository
+{
+    #[tracing::instrument(
+        name = "repository",
+        level = "debug",
+        skip_all,
+        fields(entity = "product", operation = "insert"),
+        err(Debug, level = "warn")
+    )]
+    async fn insert<'c, A>(db: A, input: NewProductDAO) -> Result<ProductDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let uuid = Uuid::new_v4();
+        let input = PostgresProductDAO::try_from(input)?;
+        sqlx::query_as::<_, PostgresProductDAO>(
+            "INSERT INTO products (id, organization_id, category_id, sku, barcode, name, description, amount, price, currency) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version",
+        )
+        .bind(uuid)
+        .bind(input.organization_id)
+        .bind(input.category_id)
+        .bind(input.sku)
+        .bind(input.barcode)
+        .bind(input.name)
+        .bind(input.description)
+        .bind(input.amount)
+        .bind(input.price)
+        .bind(input.currency)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)
+        .and_then(ProductDAO::try_from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "product", operation = "get", key = ?key), err(Debug, level = "warn"))]
+    async fn get<'c, A>(db: A, key: ProductBy) -> Result<ProductDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            ProductBy::Id(uuid) => sqlx::query_as::<_, PostgresProductDAO>(
+                "SELECT id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version FROM products WHERE id = $1 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(uuid),
+            ProductBy::Sku { organization_id, sku } => sqlx::query_as::<_, PostgresProductDAO>(
+                "SELECT id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version FROM products WHERE organization_id = $1 AND sku = $2 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(organization_id)
+            .bind(sku),
+            ProductBy::Barcode { organization_id, barcode } => sqlx::query_as::<_, PostgresProductDAO>(
+                "SELECT id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version FROM products WHERE organization_id = $1 AND barcode = $2 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(organization_id)
+            .bind(barcode),
+        };
+        query
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+            .and_then(ProductDAO::try_from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "product", operation = "try_get", key = ?key), err(Debug, level = "warn"))]
+    async fn try_get<'c, A>(db: A, key: ProductBy) -> Result<Option<ProductDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            ProductBy::Id(uuid) => sqlx::query_as::<_, PostgresProductDAO>(
+                "SELECT id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version FROM products WHERE id = $1 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(uuid),
+            ProductBy::Sku { organization_id, sku } => sqlx::query_as::<_, PostgresProductDAO>(
+                "SELECT id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version FROM products WHERE organization_id = $1 AND sku = $2 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(organization_id)
+            .bind(sku),
+            ProductBy::Barcode { organization_id, barcode } => sqlx::query_as::<_, PostgresProductDAO>(
+                "SELECT id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version FROM products WHERE organization_id = $1 AND barcode = $2 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(organization_id)
+            .bind(barcode),
+        };
+        query
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+            .and_then(|v| v.map(ProductDAO::try_from).transpose())
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "product", operation = "get_all", key = ?key), err(Debug, level = "warn"))]
+    async fn get_all<'c, A>(db: A, key: ProductsWhere) -> Result<Page<ProductDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let mut query = QueryBuilder::<Postgres>::new(
+            "SELECT id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version FROM products WHERE 1 = 1",
+        );
+        query.push(if key.deleted {
+            " AND deleted_at IS NOT NULL"
+        } else {
+            " AND deleted_at IS NULL"
+        });
+        if let Some(organization_id) = key.organization_id {
+            query
+                .push(" AND organization_id = ")
+                .push_bind(organization_id);
+        }
+        if let Some(category_id) = key.category_id {
+            query
+                .push(" AND category_id IN (WITH RECURSIVE subtree(id) AS (SELECT id FROM categories WHERE id = ")
+                .push_bind(category_id)
+                .push(" UNION ALL SELECT categories.id FROM categories JOIN subtree ON categories.parent_id = subtree.id) SELECT id FROM subtree)");
+        }
+        if let Some(tag_id) = key.tag_id {
+            query
+                .push(" AND id IN (SELECT product_id FROM product_tags WHERE tag_id = ")
+                .push_bind(tag_id)
+                .push(")");
+        }
+        if let Some(prefix) = &key.name_prefix {
+            query
+                .push(" AND lower(name) LIKE ")
+                .push_bind(like_prefix(prefix))
+                .push(" ESCAPE '\\'");
+        }
+        if let Some(currency) = key.currency {
+            query.push(" AND currency = ").push_bind(currency);
+        }
+        if let Some(min_price) = key.min_price {
+            query
+                .push(" AND currency = ")
+                .push_bind(min_price.currency)
+                .push(" AND price >= ")
+                .push_bind(min_price.minor_units);
+        }
+        if let Some(max_price) = key.max_price {
+            query
+                .push(" AND currency = ")
+                .push_bind(max_price.currency)
+                .push(" AND price <= ")
+                .push_bind(max_price.minor_units);
+        }
+        if let Some(created_after) = key.created_after {
+            query.push(" AND created_at >= ").push_bind(created_after);
+        }
+        if let Some(created_before) = key.created_before {
+            query.push(" AND created_at < ").push_bind(created_before);
+        }
+        if let Some(after) = &key.after {
+            push_after_postgres(&mut query, key.order_by.column(), key.order, after)?;
+        }
+        query
+            .push(" ORDER BY ")
+            .push(key.order_by.column())
+            .push(" ")
+            .push(key.order.keyword())
+            .push(", id ")
+            .push(key.order.keyword())
+            .push(" LIMIT ")
+            .push_bind(i64::from(key.limit) + 1);
+
+        let rows: Vec<ProductDAO> = query
+            .build_query_as::<PostgresProductDAO>()
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?
+            .into_iter()
+            .map(ProductDAO::try_from)
+            .collect::<Result<_, _>>()?;
+        Ok(Page::from_rows(rows, key.limit, |product| {
+            key.order_by.cursor(key.order, product)
+        }))
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "product", operation = "update", key = ?key), err(Debug, level = "warn"))]
+    async fn update<'c, A>(
+        db: A,
+        key: ProductBy,
+        input: UpdateProductDAO,
+    ) -> Result<ProductDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let input = PostgresProductDAO::try_from(input)?;
+        let query = match &key {
+            ProductBy::Id(uuid) => sqlx::query_as::<_, PostgresProductDAO>("UPDATE products SET category_id = $2, sku = $3, barcode = $4, name = $5, description = $6, amount = $7, price = $8, currency = $9, version = version + 1 WHERE id = $1 AND version = $10 AND deleted_at IS NULL RETURNING id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version")
+                .bind(*uuid),
+            ProductBy::Sku { organization_id, sku } => sqlx::query_as::<_, PostgresProductDAO>("UPDATE products SET category_id = $3, sku = $4, barcode = $5, name = $6, description = $7, amount = $8, price = $9, currency = $10, version = version + 1 WHERE organization_id = $1 AND sku = $2 AND version = $11 AND deleted_at IS NULL RETURNING id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version")
+                .bind(*organization_id)
+                .bind(sku.clone()),
+            ProductBy::Barcode { organization_id, barcode } => sqlx::query_as::<_, PostgresProductDAO>("UPDATE products SET category_id = $3, sku = $4, barcode = $5, name = $6, description = $7, amount = $8, price = $9, currency = $10, version = version + 1 WHERE organization_id = $1 AND barcode = $2 AND version = $11 AND deleted_at IS NULL RETURNING id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version")
+                .bind(*organization_id)
+                .bind(barcode.clone()),
+        };
+        let updated = query
+            .bind(input.category_id)
+            .bind(input.sku)
+            .bind(input.barcode)
+            .bind(input.name)
+            .bind(input.description)
+            .bind(input.amount)
+            .bind(input.price)
+            .bind(input.currency)
+            .bind(input.version)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?;
+        match updated {
+            Some(product) => ProductDAO::try_from(product),
+            None => {
+                let missing = format!("product {key:?}");
+                let current = Self::try_get(&mut *conn, key).await;
+                Err(version_rejection(
+                    current.map(|product| product.map(|product| product.version)),
+                    missing,
+                ))
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "product", operation = "delete", key = ?key), err(Debug, level = "warn"))]
+    async fn delete<'c, A>(db: A, key: ProductBy) -> Result<ProductDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            ProductBy::Id(uuid) => sqlx::query_as::<_, PostgresProductDAO>(
+                "UPDATE products SET deleted_at = now(), version = version + 1 WHERE id = $1 AND deleted_at IS NULL RETURNING id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version",
+            )
+            .bind(uuid),
+            ProductBy::Sku { organization_id, sku } => sqlx::query_as::<_, PostgresProductDAO>(
+                "UPDATE products SET deleted_at = now(), version = version + 1 WHERE organization_id = $1 AND sku = $2 AND deleted_at IS NULL RETURNING id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version",
+            )
+            .bind(organization_id)
+            .bind(sku),
+            ProductBy::Barcode { organization_id, barcode } => sqlx::query_as::<_, PostgresProductDAO>(
+                "UPDATE products SET deleted_at = now(), version = version + 1 WHERE organization_id = $1 AND barcode = $2 AND deleted_at IS NULL RETURNING id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version",
+            )
+            .bind(organization_id)
+            .bind(barcode),
+        };
+        query
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+            .and_then(ProductDAO::try_from)
+    }
+}
+
+#[async_trait::async_trait]
+impl SoftDeleteRepository<Postgres, ProductDAO, ProductBy> for ProductRepository {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "product", operation = "restore", key = ?key), err(Debug, level = "warn"))]
+    async fn restore<'c, A>(db: A, key: ProductBy) -> Result<ProductDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            ProductBy::Id(uuid) => sqlx::query_as::<_, PostgresProductDAO>(
+                "UPDATE products SET deleted_at = NULL, version = version + 1 WHERE id = $1 AND deleted_at IS NOT NULL RETURNING id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version",
+            )
+            .bind(uuid),
+            ProductBy::Sku { organization_id, sku } => sqlx::query_as::<_, PostgresProductDAO>(
+                "UPDATE products SET deleted_at = NULL, version = version + 1 WHERE organization_id = $1 AND sku = $2 AND deleted_at IS NOT NULL RETURNING id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version",
+            )
+            .bind(organization_id)
+            .bind(sku),
+            ProductBy::Barcode { organization_id, barcode } => sqlx::query_as::<_, PostgresProductDAO>(
+                "UPDATE products SET deleted_at = NULL, version = version + 1 WHERE organization_id = $1 AND barcode = $2 AND deleted_at IS NOT NULL RETURNING id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version",
+            )
+            .bind(organization_id)
+            .bind(barcode),
+        };
+        query
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+            .and_then(ProductDAO::try_from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "product", operation = "purge", key = ?key), err(Debug, level = "warn"))]
+    async fn purge<'c, A>(db: A, key: ProductBy) -> Result<ProductDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            ProductBy::Id(uuid) => sqlx::query_as::<_, PostgresProductDAO>(
+                "DELETE FROM products WHERE id = $1 RETURNING id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version",
+            )
+            .bind(uuid),
+            ProductBy::Sku { organization_id, sku } => sqlx::query_as::<_, PostgresProductDAO>(
+                "DELETE FROM products WHERE organization_id = $1 AND sku = $2 RETURNING id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version",
+            )
+            .bind(organization_id)
+            .bind(sku),
+            ProductBy::Barcode { organization_id, barcode } => sqlx::query_as::<_, PostgresProductDAO>(
+                "DELETE FROM products WHERE organization_id = $1 AND barcode = $2 RETURNING id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version",
+            )
+            .bind(organization_id)
+            .bind(barcode),
+        };
+        query
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+            .and_then(ProductDAO::try_from)
+    }
+}
+
+#[async_trait::async_trait]
+impl ProductSearch<Postgres> for ProductRepository {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "product", operation = "search", key = ?organization_id), err(Debug, level = "warn"))]
+    async fn search<'c, A>(
+        db: A,
+        organization_id: Uuid,
+        query: &str,
+        limit: i32,
+    ) -> Result<Vec<ProductSearchResultDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let Some(query) = tsquery(query) else {
+            return Ok(vec![]);
+        };
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        sqlx::query_as::<_, PostgresProductSearchRow>(
+            "SELECT id, organization_id, category_id, sku, barcode, name, description, amount, price, currency, created_at, updated_at, version, ts_headline('simple', name, query, 'StartSel=\"[\", StopSel=\"]\", HighlightAll=true') AS name_highlight, ts_headline('simple', description, query, 'StartSel=\"[\", StopSel=\"]\", MaxWords=16, MinWords=8') AS snippet, ts_rank(search_vector, query)::float8 AS rank FROM products, to_tsquery('simple', $2) AS query WHERE search_vector @@ query AND organization_id = $1 AND deleted_at IS NULL ORDER BY rank DESC, id LIMIT $3",
+        )
+        .bind(organization_id)
+        .bind(query)
+        .bind(i64::from(limit))
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)?
+        .into_iter()
+        .map(ProductSearchResultDAO::try_from)
+        .collect()
+    }
+}
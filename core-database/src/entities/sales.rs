@@ -1,243 +1,771 @@
 use chrono::{DateTime, Utc};
-use num_bigint::BigUint;
-use sqlx::{Pool, Sqlite};
+use sqlx::{Acquire, Database, QueryBuilder, Sqlite, SqliteConnection};
 use uuid::Uuid;
 
-use crate::traits::{DatabaseError, EntityRepository};
+use crate::{
+    entities::{quantity_from_column, quantity_to_column, version_rejection, SortOrder},
+    money::{Currency, Money, Rate},
+    pagination::{push_after_sqlite, Cursor, CursorKey, Page},
+    sqlite,
+    traits::{DatabaseError, EntityRepository, SoftDeleteRepository},
+};
 
+#[cfg(feature = "postgres")]
+pub mod postgres;
+
+#[derive(Debug, Clone)]
 pub enum SalesBy {
     Id(Uuid),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SalesOrderBy {
+    #[default]
+    CreatedAt,
+    Amount,
+}
+
+impl SalesOrderBy {
+    pub(crate) fn column(&self) -> &'static str {
+        match self {
+            SalesOrderBy::CreatedAt => "created_at",
+            SalesOrderBy::Amount => "amount",
+        }
+    }
+
+    pub(crate) fn cursor(&self, order: SortOrder, sales: &SalesDAO) -> Cursor {
+        let key = match self {
+            SalesOrderBy::CreatedAt => CursorKey::Timestamp(sales.created_at),
+            SalesOrderBy::Amount => CursorKey::Integer(sales.amount.into()),
+        };
+        Cursor::new(self.column(), order, key, sales.id)
+    }
+}
+
+/// Filters used to list sales. Filters left as `None` are not applied.
 #[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SalesWhere {
+    /// Organization the sale was made in.
+    pub organization_id: Option<Uuid>,
+    pub product_id: Option<Uuid>,
+    pub seller_id: Option<Uuid>,
+    /// Only sales made at or after this instant.
+    pub created_after: Option<DateTime<Utc>>,
+    /// Only sales made before this instant.
+    pub created_before: Option<DateTime<Utc>>,
+    pub order_by: SalesOrderBy,
+    pub order: SortOrder,
+    pub limit: i32,
+    /// Resume after the last row of a previous page.
+    pub after: Option<Cursor>,
+    /// List soft-deleted rows instead of live ones.
+    pub deleted: bool,
+}
+
+impl Default for SalesWhere {
+    fn default() -> Self {
+        Self {
+            organization_id: None,
+            product_id: None,
+            seller_id: None,
+            created_after: None,
+            created_before: None,
+            order_by: SalesOrderBy::default(),
+            order: SortOrder::default(),
+            limit: 100,
+            after: None,
+            deleted: false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, serde::Serialize)]
 pub struct SalesDAO {
     pub id: Uuid,
+    pub organization_id: Uuid,
     pub product_id: Uuid,
     pub seller_id: Uuid,
     pub amount: u32,
-    pub total_price: BigUint,
+    pub total_price: Money,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub version: i64,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct NewSalesDAO {
+    /// Must be the organization of both the product and the seller.
+    pub organization_id: Uuid,
     pub product_id: Uuid,
     pub seller_id: Uuid,
     pub amount: u32,
-    pub total_price: BigUint,
+    pub total_price: Money,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct UpdateSalesDAO {
     pub amount: u32,
-    pub total_price: BigUint,
+    pub total_price: Money,
+    /// Version of the row the update is based on; the update fails with
+    /// [`DatabaseError::Conflict`] when the row changed since.
+    pub version: i64,
+}
+
+/// Input of [`SalesRecorder::record_sale`]; the total price comes from the product.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RecordSaleDAO {
+    pub product_id: Uuid,
+    pub seller_id: Uuid,
+    pub amount: u32,
 }
 
 #[derive(sqlx::FromRow, Debug, PartialEq, Eq, Clone)]
 pub struct SqliteSalesDAO {
     pub id: Uuid,
+    pub organization_id: Uuid,
     pub product_id: Uuid,
     pub seller_id: Uuid,
     pub amount: i32,
-    pub total_price: Vec<u8>,
+    pub total_price: i64,
+    pub currency: Currency,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub version: i64,
 }
 
-impl From<SalesDAO> for SqliteSalesDAO {
-    fn from(value: SalesDAO) -> Self {
-        Self {
+impl TryFrom<SalesDAO> for SqliteSalesDAO {
+    type Error = DatabaseError;
+
+    fn try_from(value: SalesDAO) -> Result<Self, Self::Error> {
+        Ok(Self {
             id: value.id,
+            organization_id: value.organization_id,
             product_id: value.product_id,
             seller_id: value.seller_id,
-            amount: i32::try_from(value.amount).unwrap_or_default(),
-            total_price: value.total_price.to_bytes_le(),
+            amount: quantity_to_column(value.amount)?,
+            total_price: value.total_price.minor_units,
+            currency: value.total_price.currency,
             created_at: value.created_at,
             updated_at: value.updated_at,
-        }
+            version: value.version,
+        })
     }
 }
 
-impl From<NewSalesDAO> for SqliteSalesDAO {
-    fn from(value: NewSalesDAO) -> Self {
-        Self {
+impl TryFrom<NewSalesDAO> for SqliteSalesDAO {
+    type Error = DatabaseError;
+
+    fn try_from(value: NewSalesDAO) -> Result<Self, Self::Error> {
+        Ok(Self {
             id: Uuid::default(),
+            organization_id: value.organization_id,
             product_id: value.product_id,
             seller_id: value.seller_id,
-            amount: i32::try_from(value.amount).unwrap_or_default(),
-            total_price: value.total_price.to_bytes_le(),
+            amount: quantity_to_column(value.amount)?,
+            total_price: value.total_price.minor_units,
+            currency: value.total_price.currency,
             created_at: DateTime::default(),
             updated_at: DateTime::default(),
-        }
+            version: 1,
+        })
     }
 }
 
-impl From<UpdateSalesDAO> for SqliteSalesDAO {
-    fn from(value: UpdateSalesDAO) -> Self {
-        Self {
+impl TryFrom<UpdateSalesDAO> for SqliteSalesDAO {
+    type Error = DatabaseError;
+
+    fn try_from(value: UpdateSalesDAO) -> Result<Self, Self::Error> {
+        Ok(Self {
             id: Uuid::default(),
+            organization_id: Uuid::default(),
             product_id: Uuid::default(),
             seller_id: Uuid::default(),
-            amount: i32::try_from(value.amount).unwrap_or_default(),
-            total_price: value.total_price.to_bytes_le(),
+            amount: quantity_to_column(value.amount)?,
+            total_price: value.total_price.minor_units,
+            currency: value.total_price.currency,
             created_at: DateTime::default(),
             updated_at: DateTime::default(),
-        }
+            version: value.version,
+        })
     }
 }
 
-impl From<SqliteSalesDAO> for SalesDAO {
-    fn from(value: SqliteSalesDAO) -> Self {
-        Self {
+impl TryFrom<SqliteSalesDAO> for SalesDAO {
+    type Error = DatabaseError;
+
+    fn try_from(value: SqliteSalesDAO) -> Result<Self, Self::Error> {
+        Ok(Self {
             id: value.id,
+            organization_id: value.organization_id,
             product_id: value.product_id,
             seller_id: value.seller_id,
-            amount: value.amount.unsigned_abs(),
-            total_price: BigUint::from_bytes_le(&value.total_price),
+            amount: quantity_from_column(value.amount)?,
+            total_price: Money::new(value.total_price, value.currency),
             created_at: value.created_at,
             updated_at: value.updated_at,
-        }
+            version: value.version,
+        })
     }
 }
 
 #[derive(Debug)]
 pub struct SalesRepository;
 
+/// Appends the `WHERE` conditions of `key` shared by listings and reports.
+fn push_sales_filters(query: &mut QueryBuilder<'_, Sqlite>, key: &SalesWhere) {
+    query.push(if key.deleted {
+        " AND deleted_at IS NOT NULL"
+    } else {
+        " AND deleted_at IS NULL"
+    });
+    if let Some(organization_id) = key.organization_id {
+        query
+            .push(" AND organization_id = ")
+            .push_bind(organization_id);
+    }
+    if let Some(product_id) = key.product_id {
+        query.push(" AND product_id = ").push_bind(product_id);
+    }
+    if let Some(seller_id) = key.seller_id {
+        query.push(" AND seller_id = ").push_bind(seller_id);
+    }
+    if let Some(created_after) = key.created_after {
+        query
+            .push(" AND created_at >= ")
+            .push_bind(sqlite::timestamp(created_after));
+    }
+    if let Some(created_before) = key.created_before {
+        query
+            .push(" AND created_at < ")
+            .push_bind(sqlite::timestamp(created_before));
+    }
+}
+
+/// Builds the query of `get_all`; kept apart so the query plan tests can check it.
+pub(crate) fn list_query(key: &SalesWhere) -> Result<QueryBuilder<'static, Sqlite>, DatabaseError> {
+    let mut query = QueryBuilder::<Sqlite>::new(
+        "SELECT id, organization_id, product_id, seller_id, amount, total_price, currency, created_at, updated_at, version FROM sales WHERE 1 = 1",
+    );
+    push_sales_filters(&mut query, key);
+    if let Some(after) = &key.after {
+        push_after_sqlite(&mut query, key.order_by.column(), key.order, after)?;
+    }
+    query
+        .push(" ORDER BY ")
+        .push(key.order_by.column())
+        .push(" ")
+        .push(key.order.keyword())
+        .push(", id ")
+        .push(key.order.keyword())
+        .push(" LIMIT ")
+        .push_bind(i64::from(key.limit) + 1);
+    Ok(query)
+}
+
+/// Builds the query of [`SalesReport::revenue`].
+pub(crate) fn revenue_query(key: &SalesWhere) -> QueryBuilder<'static, Sqlite> {
+    let mut query =
+        QueryBuilder::<Sqlite>::new("SELECT currency, SUM(total_price) FROM sales WHERE 1 = 1");
+    push_sales_filters(&mut query, key);
+    query.push(" GROUP BY currency ORDER BY currency");
+    query
+}
+
+/// Builds the query of [`SalesReport::reporting_revenue`]. Sales are grouped by the rate that
+/// applies to them, so each group converts once.
+pub(crate) fn reporting_revenue_query(
+    organization_id: Uuid,
+    key: &SalesWhere,
+    reporting_currency: Currency,
+) -> QueryBuilder<'static, Sqlite> {
+    let mut query = QueryBuilder::<Sqlite>::new(
+        "SELECT currency, rate, SUM(total_price) FROM (SELECT currency, total_price, (SELECT rate FROM exchange_rates WHERE from_currency = sales.currency AND to_currency = ",
+    );
+    query.push_bind(reporting_currency).push(
+        " AND effective_at <= sales.created_at ORDER BY effective_at DESC LIMIT 1) AS rate FROM sales WHERE 1 = 1",
+    );
+    push_sales_filters(
+        &mut query,
+        &SalesWhere {
+            organization_id: Some(organization_id),
+            ..key.clone()
+        },
+    );
+    query.push(") AS converted GROUP BY currency, rate");
+    query
+}
+
 #[async_trait::async_trait]
-impl EntityRepository<Sqlite, SalesDAO, NewSalesDAO, UpdateSalesDAO, SalesBy, SalesBy>
+impl EntityRepository<Sqlite, SalesDAO, NewSalesDAO, UpdateSalesDAO, SalesBy, SalesWhere>
     for SalesRepository
 {
-    async fn insert(db: &Pool<Sqlite>, input: NewSalesDAO) -> Result<SalesDAO, DatabaseError> {
+    #[tracing::instrument(
+        name = "repository",
+        level = "debug",
+        skip_all,
+        fields(entity = "sales", operation = "insert"),
+        err(Debug, level = "warn")
+    )]
+    async fn insert<'c, A>(db: A, input: NewSalesDAO) -> Result<SalesDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
         let uuid = Uuid::new_v4();
-        let input: SqliteSalesDAO = SqliteSalesDAO::from(input);
+        let input = SqliteSalesDAO::try_from(input)?;
         sqlx::query_as::<_, SqliteSalesDAO>(
-            "INSERT INTO sales (id, product_id, seller_id, amount, total_price) VALUES ($1, $2, $3, $4, $5) RETURNING id, product_id, seller_id, amount, total_price, created_at, updated_at",
+            "INSERT INTO sales (id, organization_id, product_id, seller_id, amount, total_price, currency) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id, organization_id, product_id, seller_id, amount, total_price, currency, created_at, updated_at, version",
         )
         .bind(uuid)
+        .bind(input.organization_id)
         .bind(input.product_id)
         .bind(input.seller_id)
         .bind(input.amount)
         .bind(input.total_price)
-        .fetch_one(db)
+        .bind(input.currency)
+        .fetch_one(&mut *conn)
         .await
-        .map(SalesDAO::from)
         .map_err(DatabaseError::from)
+        .and_then(SalesDAO::try_from)
     }
 
-    async fn get(db: &Pool<Sqlite>, key: SalesBy) -> Result<SalesDAO, DatabaseError> {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "sales", operation = "get", key = ?key), err(Debug, level = "warn"))]
+    async fn get<'c, A>(db: A, key: SalesBy) -> Result<SalesDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
         match key {
             SalesBy::Id(uuid) => sqlx::query_as::<_, SqliteSalesDAO>(
-                "SELECT id, product_id, seller_id, amount, total_price, created_at, updated_at FROM sales WHERE id = $1 LIMIT 1",
+                "SELECT id, organization_id, product_id, seller_id, amount, total_price, currency, created_at, updated_at, version FROM sales WHERE id = $1 AND deleted_at IS NULL LIMIT 1",
             )
             .bind(uuid)
-            .fetch_one(db)
+            .fetch_one(&mut *conn)
             .await
-            .map(SalesDAO::from)
-            .map_err(DatabaseError::from),
+            .map_err(DatabaseError::from)
+            .and_then(SalesDAO::try_from),
         }
     }
 
-    async fn try_get(db: &Pool<Sqlite>, key: SalesBy) -> Result<Option<SalesDAO>, DatabaseError> {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "sales", operation = "try_get", key = ?key), err(Debug, level = "warn"))]
+    async fn try_get<'c, A>(db: A, key: SalesBy) -> Result<Option<SalesDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
         match key {
             SalesBy::Id(uuid) => sqlx::query_as::<_, SqliteSalesDAO>(
-                "SELECT id, product_id, seller_id, amount, total_price, created_at, updated_at FROM sales WHERE id = $1 LIMIT 1",
+                "SELECT id, organization_id, product_id, seller_id, amount, total_price, currency, created_at, updated_at, version FROM sales WHERE id = $1 AND deleted_at IS NULL LIMIT 1",
             )
             .bind(uuid)
-            .fetch_optional(db)
+            .fetch_optional(&mut *conn)
             .await
-            .map(|v| v.map(SalesDAO::from))
-            .map_err(DatabaseError::from),
+            .map_err(DatabaseError::from)
+            .and_then(|v| v.map(SalesDAO::try_from).transpose()),
         }
     }
 
-    async fn get_all(_db: &Pool<Sqlite>, _key: SalesBy) -> Result<Vec<SalesDAO>, DatabaseError> {
-        Err(DatabaseError::NotImplemented)
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "sales", operation = "get_all", key = ?key), err(Debug, level = "warn"))]
+    async fn get_all<'c, A>(db: A, key: SalesWhere) -> Result<Page<SalesDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let mut query = list_query(&key)?;
+        let rows: Vec<SalesDAO> = query
+            .build_query_as::<SqliteSalesDAO>()
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?
+            .into_iter()
+            .map(SalesDAO::try_from)
+            .collect::<Result<_, _>>()?;
+        Ok(Page::from_rows(rows, key.limit, |sales| {
+            key.order_by.cursor(key.order, sales)
+        }))
     }
 
-    async fn update(
-        db: &Pool<Sqlite>,
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "sales", operation = "update", key = ?key), err(Debug, level = "warn"))]
+    async fn update<'c, A>(
+        db: A,
         key: SalesBy,
         input: UpdateSalesDAO,
-    ) -> Result<SalesDAO, DatabaseError> {
-        let input = SqliteSalesDAO::from(input);
-        match key {
+    ) -> Result<SalesDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let input = SqliteSalesDAO::try_from(input)?;
+        let updated = match &key {
             SalesBy::Id(uuid) => {
-                sqlx::query_as::<_, SqliteSalesDAO>("UPDATE sales SET amount = $2, total_price = $3, updated_at = unixepoch('now') WHERE id = $1 RETURNING id, product_id, seller_id, amount, total_price, created_at, updated_at")
-                    .bind(uuid)
+                sqlx::query_as::<_, SqliteSalesDAO>("UPDATE sales SET amount = $2, total_price = $3, currency = $4, updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE id = $1 AND version = $5 AND deleted_at IS NULL RETURNING id, organization_id, product_id, seller_id, amount, total_price, currency, created_at, updated_at, version")
+                    .bind(*uuid)
                     .bind(input.amount)
                     .bind(input.total_price)
-                    .fetch_one(db)
+                    .bind(input.currency)
+                    .bind(input.version)
+                    .fetch_optional(&mut *conn)
                     .await
-                    .map(SalesDAO::from)
-                    .map_err(DatabaseError::from)
+                    .map_err(DatabaseError::from)?
+            }
+        };
+        match updated {
+            Some(sales) => SalesDAO::try_from(sales),
+            None => {
+                let missing = format!("sales {key:?}");
+                let current = Self::try_get(&mut *conn, key).await;
+                Err(version_rejection(
+                    current.map(|sales| sales.map(|sales| sales.version)),
+                    missing,
+                ))
             }
         }
     }
 
-    async fn delete(db: &Pool<Sqlite>, key: SalesBy) -> Result<SalesDAO, DatabaseError> {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "sales", operation = "delete", key = ?key), err(Debug, level = "warn"))]
+    async fn delete<'c, A>(db: A, key: SalesBy) -> Result<SalesDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        match key {
+            SalesBy::Id(uuid) => sqlx::query_as::<_, SqliteSalesDAO>(
+                "UPDATE sales SET deleted_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE id = $1 AND deleted_at IS NULL RETURNING id, organization_id, product_id, seller_id, amount, total_price, currency, created_at, updated_at, version",
+            )
+            .bind(uuid)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+            .and_then(SalesDAO::try_from),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SoftDeleteRepository<Sqlite, SalesDAO, SalesBy> for SalesRepository {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "sales", operation = "restore", key = ?key), err(Debug, level = "warn"))]
+    async fn restore<'c, A>(db: A, key: SalesBy) -> Result<SalesDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        match key {
+            SalesBy::Id(uuid) => sqlx::query_as::<_, SqliteSalesDAO>(
+                "UPDATE sales SET deleted_at = NULL, updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE id = $1 AND deleted_at IS NOT NULL RETURNING id, organization_id, product_id, seller_id, amount, total_price, currency, created_at, updated_at, version",
+            )
+            .bind(uuid)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+            .and_then(SalesDAO::try_from),
+        }
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "sales", operation = "purge", key = ?key), err(Debug, level = "warn"))]
+    async fn purge<'c, A>(db: A, key: SalesBy) -> Result<SalesDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
         match key {
             SalesBy::Id(uuid) => sqlx::query_as::<_, SqliteSalesDAO>(
-                "DELETE FROM sales WHERE id = $1 RETURNING id, product_id, seller_id, amount, total_price, created_at, updated_at",
+                "DELETE FROM sales WHERE id = $1 RETURNING id, organization_id, product_id, seller_id, amount, total_price, currency, created_at, updated_at, version",
             )
             .bind(uuid)
-            .fetch_one(db)
+            .fetch_one(&mut *conn)
             .await
-            .map(SalesDAO::from)
-            .map_err(DatabaseError::from),
+            .map_err(DatabaseError::from)
+            .and_then(SalesDAO::try_from),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+pub trait SalesRecorder<DB: Database> {
+    /// Records a sale and takes the sold units out of the product stock atomically.
+    ///
+    /// The product and the seller must belong to the same organization and the stock must
+    /// cover the requested amount, which must be at least one unit. `total_price` is computed
+    /// from the current product price.
+    async fn record_sale<'c, A>(db: A, input: RecordSaleDAO) -> Result<SalesDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = DB> + Send;
+}
+
+#[async_trait::async_trait]
+pub trait SalesReport<DB: Database> {
+    /// Sums the total price of the sales matching `key`, one amount per currency ordered by
+    /// currency code. Ordering and pagination fields of `key` are ignored.
+    async fn revenue<'c, A>(db: A, key: SalesWhere) -> Result<Vec<Money>, DatabaseError>
+    where
+        A: Acquire<'c, Database = DB> + Send;
+
+    /// Sums the total price of the sales of an organization matching `key`, converted into its
+    /// reporting currency with the exchange rate in effect when each sale was made. Fails with
+    /// `MissingExchangeRate` when a sale has no such rate. `key.organization_id` is replaced by
+    /// `organization_id`.
+    async fn reporting_revenue<'c, A>(
+        db: A,
+        organization_id: Uuid,
+        key: SalesWhere,
+    ) -> Result<Money, DatabaseError>
+    where
+        A: Acquire<'c, Database = DB> + Send;
+}
+
+/// Adds up the `(currency, rate, total)` groups of a reporting revenue query. Totals already in
+/// `reporting_currency` need no rate.
+pub(crate) fn sum_converted(
+    totals: Vec<(Currency, Option<Rate>, i64)>,
+    reporting_currency: Currency,
+) -> Result<Money, DatabaseError> {
+    let overflow = || DatabaseError::InvalidMoney("revenue overflows".to_string());
+    let mut sum = 0i64;
+    for (currency, rate, total) in totals {
+        let rate = match rate {
+            _ if currency == reporting_currency => Rate::PAR,
+            Some(rate) => rate,
+            None => {
+                return Err(DatabaseError::MissingExchangeRate {
+                    from: currency,
+                    to: reporting_currency,
+                })
+            }
+        };
+        let converted = Money::new(total, currency)
+            .convert(reporting_currency, rate)
+            .ok_or_else(overflow)?;
+        sum = sum
+            .checked_add(converted.minor_units)
+            .ok_or_else(overflow)?;
+    }
+    Ok(Money::new(sum, reporting_currency))
+}
+
+/// Explains why the stock reservation of `record_sale` matched no product.
+async fn sale_rejection(conn: &mut SqliteConnection, input: &RecordSaleDAO) -> DatabaseError {
+    let product = sqlx::query_as::<_, (Uuid, i32)>(
+        "SELECT organization_id, amount FROM products WHERE id = $1 AND deleted_at IS NULL LIMIT 1",
+    )
+    .bind(input.product_id)
+    .fetch_optional(&mut *conn)
+    .await;
+    let seller = sqlx::query_as::<_, (Uuid,)>(
+        "SELECT organization_id FROM sellers WHERE id = $1 AND deleted_at IS NULL LIMIT 1",
+    )
+    .bind(input.seller_id)
+    .fetch_optional(&mut *conn)
+    .await;
+
+    match (product, seller) {
+        (Err(e), _) | (_, Err(e)) => DatabaseError::from(e),
+        (Ok(None), _) => DatabaseError::NotFound(format!("product {}", input.product_id)),
+        (_, Ok(None)) => DatabaseError::NotFound(format!("seller {}", input.seller_id)),
+        (Ok(Some((product_organization, _))), Ok(Some((seller_organization,))))
+            if product_organization != seller_organization =>
+        {
+            DatabaseError::OrganizationMismatch
+        }
+        (Ok(Some((_, available))), _) => match quantity_from_column(available) {
+            Ok(available) => DatabaseError::InsufficientStock {
+                available,
+                requested: input.amount,
+            },
+            Err(e) => e,
+        },
+    }
+}
+
+#[async_trait::async_trait]
+impl SalesRecorder<Sqlite> for SalesRepository {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "sales", operation = "record_sale", product_id = %input.product_id, seller_id = %input.seller_id), err(Debug, level = "warn"))]
+    async fn record_sale<'c, A>(db: A, input: RecordSaleDAO) -> Result<SalesDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        if input.amount == 0 {
+            return Err(DatabaseError::InvalidQuantity(0));
         }
+        let mut tx = db.begin().await.map_err(DatabaseError::from)?;
+        // Checking stock and organization in the UPDATE itself keeps concurrent sales from
+        // overselling the same product.
+        let reserved = sqlx::query_as::<_, (Uuid, i64, Currency)>(
+            "UPDATE products SET amount = amount - $2, updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE id = $1 AND deleted_at IS NULL AND amount >= $2 AND organization_id = (SELECT organization_id FROM sellers WHERE id = $3 AND deleted_at IS NULL) RETURNING organization_id, price, currency",
+        )
+        .bind(input.product_id)
+        .bind(i64::from(input.amount))
+        .bind(input.seller_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(DatabaseError::from)?;
+
+        let Some((organization_id, price, currency)) = reserved else {
+            return Err(sale_rejection(&mut tx, &input).await);
+        };
+        let total_price = Money::new(price, currency)
+            .checked_mul(input.amount)
+            .ok_or_else(|| DatabaseError::InvalidMoney("total price overflows".to_string()))?;
+
+        let sale = SalesRepository::insert(
+            &mut tx,
+            NewSalesDAO {
+                organization_id,
+                product_id: input.product_id,
+                seller_id: input.seller_id,
+                amount: input.amount,
+                total_price,
+            },
+        )
+        .await?;
+        tx.commit().await.map_err(DatabaseError::from)?;
+        Ok(sale)
+    }
+}
+
+#[async_trait::async_trait]
+impl SalesReport<Sqlite> for SalesRepository {
+    #[tracing::instrument(
+        name = "repository",
+        level = "debug",
+        skip_all,
+        fields(entity = "sales", operation = "revenue"),
+        err(Debug, level = "warn")
+    )]
+    async fn revenue<'c, A>(db: A, key: SalesWhere) -> Result<Vec<Money>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let totals = revenue_query(&key)
+            .build_query_as::<(Currency, i64)>()
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?;
+        Ok(totals
+            .into_iter()
+            .map(|(currency, total)| Money::new(total, currency))
+            .collect())
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "sales", operation = "reporting_revenue", key = ?organization_id), err(Debug, level = "warn"))]
+    async fn reporting_revenue<'c, A>(
+        db: A,
+        organization_id: Uuid,
+        key: SalesWhere,
+    ) -> Result<Money, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let (reporting_currency,) = sqlx::query_as::<_, (Currency,)>(
+            "SELECT reporting_currency FROM organizations WHERE id = $1 AND deleted_at IS NULL LIMIT 1",
+        )
+        .bind(organization_id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)?
+        .ok_or_else(|| DatabaseError::NotFound(format!("organization {organization_id}")))?;
+
+        let totals = reporting_revenue_query(organization_id, &key, reporting_currency)
+            .build_query_as::<(Currency, Option<Rate>, i64)>()
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?;
+        sum_converted(totals, reporting_currency)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::ops::Mul;
 
     use crate::{
         entities::{
-            organization::{NewOrganizationDAO, OrganizationDAO, OrganizationRepository},
-            product::{NewProductDAO, ProductRepository},
-            seller::{NewSellerDAO, SellerRepository},
+            exchange_rate::{ExchangeRateDAO, ExchangeRateRepository, ExchangeRates},
+            organization::{
+                NewOrganizationDAO, OrganizationBy, OrganizationDAO, OrganizationRepository,
+                OrganizationsWhere, UpdateOrganizationDAO,
+            },
+            product::{
+                NewProductDAO, ProductBy, ProductDAO, ProductRepository, ProductsWhere,
+                UpdateProductDAO,
+            },
+            seller::{
+                NewSellerDAO, SellerBy, SellerDAO, SellerRepository, SellersWhere, UpdateSellerDAO,
+            },
         },
         sqlite::DatabaseRepository,
     };
+    use proptest::prelude::*;
+    use sqlx::Database;
 
     use super::*;
+    use sqlx::Pool;
 
-    async fn create_organization(pool: &Pool<Sqlite>, name: &str) -> OrganizationDAO {
+    async fn create_organization<DB: Database>(pool: &Pool<DB>, name: &str) -> OrganizationDAO
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+    {
         OrganizationRepository::insert(
             pool,
             NewOrganizationDAO {
                 name: name.to_string(),
+                reporting_currency: Currency::USD,
             },
         )
         .await
         .expect("Could not create organization")
     }
 
-    #[tokio::test]
-    async fn queries() {
-        let db = DatabaseRepository::new()
-            .await
-            .expect("Could not initialize db");
-
-        let organization = create_organization(&db.connection, "test").await;
+    async fn queries<DB: Database>(db: &Pool<DB>)
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+        ProductRepository: EntityRepository<
+            DB,
+            ProductDAO,
+            NewProductDAO,
+            UpdateProductDAO,
+            ProductBy,
+            ProductsWhere,
+        >,
+        SellerRepository:
+            EntityRepository<DB, SellerDAO, NewSellerDAO, UpdateSellerDAO, SellerBy, SellersWhere>,
+        SalesRepository:
+            EntityRepository<DB, SalesDAO, NewSalesDAO, UpdateSalesDAO, SalesBy, SalesWhere>,
+    {
+        let organization = create_organization(db, "test").await;
 
         let product = ProductRepository::insert(
-            &db.connection,
+            db,
             NewProductDAO {
                 organization_id: organization.id,
+                category_id: None,
+                sku: None,
+                barcode: None,
                 name: "Iphone".to_string(),
                 description: "smartphone".to_string(),
                 amount: 10,
-                price: BigUint::from(5000u32),
+                price: Money::new(5000, Currency::USD),
             },
         )
         .await
         .expect("Could not create a new product");
 
         let seller = SellerRepository::insert(
-            &db.connection,
+            db,
             NewSellerDAO {
                 organization_id: organization.id,
                 email: "test@gmail.com".to_string(),
@@ -248,25 +776,26 @@ mod tests {
         .expect("Could not create a seller");
 
         let sales = SalesRepository::insert(
-            &db.connection,
+            db,
             NewSalesDAO {
+                organization_id: organization.id,
                 product_id: product.id,
                 seller_id: seller.id,
                 amount: 2,
-                total_price: BigUint::from(product.price.mul(2u32)),
+                total_price: product.price.checked_mul(2).unwrap(),
             },
         )
         .await
         .expect("Could not create a new sale");
 
-        let sales = SalesRepository::get(&db.connection, SalesBy::Id(sales.id))
+        let sales = SalesRepository::get(db, SalesBy::Id(sales.id))
             .await
             .expect("Could not get sales");
         assert_eq!(sales.amount, 2);
         assert_eq!(sales.product_id, product.id);
         assert_eq!(sales.seller_id, seller.id);
 
-        let sales = SalesRepository::try_get(&db.connection, SalesBy::Id(sales.id))
+        let sales = SalesRepository::try_get(db, SalesBy::Id(sales.id))
             .await
             .expect("Could not get sales")
             .unwrap();
@@ -274,33 +803,890 @@ mod tests {
         assert_eq!(sales.product_id, product.id);
         assert_eq!(sales.seller_id, seller.id);
 
-        let maybe_sales = SalesRepository::try_get(&db.connection, SalesBy::Id(Uuid::default()))
+        let maybe_sales = SalesRepository::try_get(db, SalesBy::Id(Uuid::default()))
             .await
             .expect("Could not get sales");
         assert!(maybe_sales.is_none());
 
         let updated = SalesRepository::update(
-            &db.connection,
+            db,
             SalesBy::Id(sales.id),
             UpdateSalesDAO {
                 amount: 4,
-                total_price: BigUint::from(34u32),
+                total_price: Money::new(34, Currency::USD),
+                version: sales.version,
             },
         )
         .await
         .expect("Could not get sales");
         assert_eq!(updated.amount, 4);
-        assert_eq!(updated.total_price, BigUint::from(34u32));
+        assert_eq!(updated.total_price, Money::new(34, Currency::USD));
         assert_eq!(updated.product_id, product.id);
         assert_eq!(updated.seller_id, seller.id);
 
-        let deleted = SalesRepository::delete(&db.connection, SalesBy::Id(sales.id))
+        let deleted = SalesRepository::delete(db, SalesBy::Id(sales.id))
             .await
             .expect("Could not get sales");
 
-        let maybe_sales = SalesRepository::try_get(&db.connection, SalesBy::Id(deleted.id))
+        let maybe_sales = SalesRepository::try_get(db, SalesBy::Id(deleted.id))
             .await
             .expect("Could not get sales");
         assert!(maybe_sales.is_none());
     }
+
+    async fn recording_sales<DB: Database>(db: &Pool<DB>)
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+        ProductRepository: EntityRepository<
+            DB,
+            ProductDAO,
+            NewProductDAO,
+            UpdateProductDAO,
+            ProductBy,
+            ProductsWhere,
+        >,
+        SellerRepository:
+            EntityRepository<DB, SellerDAO, NewSellerDAO, UpdateSellerDAO, SellerBy, SellersWhere>,
+        SalesRepository: SalesRecorder<DB>,
+    {
+        let organization = create_organization(db, "test").await;
+        let other_organization = create_organization(db, "other").await;
+
+        let product = ProductRepository::insert(
+            db,
+            NewProductDAO {
+                organization_id: organization.id,
+                category_id: None,
+                sku: None,
+                barcode: None,
+                name: "Iphone".to_string(),
+                description: "smartphone".to_string(),
+                amount: 10,
+                price: Money::new(5000, Currency::USD),
+            },
+        )
+        .await
+        .expect("Could not create a new product");
+
+        let seller = SellerRepository::insert(
+            db,
+            NewSellerDAO {
+                organization_id: organization.id,
+                email: "test@gmail.com".to_string(),
+                password: "test123".to_string(),
+            },
+        )
+        .await
+        .expect("Could not create a seller");
+
+        let outsider = SellerRepository::insert(
+            db,
+            NewSellerDAO {
+                organization_id: other_organization.id,
+                email: "outsider@gmail.com".to_string(),
+                password: "test123".to_string(),
+            },
+        )
+        .await
+        .expect("Could not create a seller");
+
+        let sale = SalesRepository::record_sale(
+            db,
+            RecordSaleDAO {
+                product_id: product.id,
+                seller_id: seller.id,
+                amount: 3,
+            },
+        )
+        .await
+        .expect("Could not record sale");
+        assert_eq!(sale.amount, 3);
+        assert_eq!(sale.total_price, Money::new(15000, Currency::USD));
+
+        let stock = ProductRepository::get(db, ProductBy::Id(product.id))
+            .await
+            .expect("Could not find product");
+        assert_eq!(stock.amount, 7);
+        // Selling counts as a change for admins editing the product meanwhile
+        assert_eq!(stock.version, product.version + 1);
+
+        let oversold = SalesRepository::record_sale(
+            db,
+            RecordSaleDAO {
+                product_id: product.id,
+                seller_id: seller.id,
+                amount: 8,
+            },
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            oversold,
+            DatabaseError::InsufficientStock {
+                available: 7,
+                requested: 8
+            }
+        );
+
+        let mismatch = SalesRepository::record_sale(
+            db,
+            RecordSaleDAO {
+                product_id: product.id,
+                seller_id: outsider.id,
+                amount: 1,
+            },
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(mismatch, DatabaseError::OrganizationMismatch);
+
+        let missing = SalesRepository::record_sale(
+            db,
+            RecordSaleDAO {
+                product_id: Uuid::default(),
+                seller_id: seller.id,
+                amount: 1,
+            },
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(missing, DatabaseError::NotFound(_)));
+
+        let empty = SalesRepository::record_sale(
+            db,
+            RecordSaleDAO {
+                product_id: product.id,
+                seller_id: seller.id,
+                amount: 0,
+            },
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(empty, DatabaseError::InvalidQuantity(0));
+
+        let stock = ProductRepository::get(db, ProductBy::Id(product.id))
+            .await
+            .expect("Could not find product");
+        assert_eq!(stock.amount, 7);
+        assert_eq!(stock.version, product.version + 1);
+    }
+
+    async fn soft_delete<DB: Database>(db: &Pool<DB>)
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+        ProductRepository: EntityRepository<
+                DB,
+                ProductDAO,
+                NewProductDAO,
+                UpdateProductDAO,
+                ProductBy,
+                ProductsWhere,
+            > + SoftDeleteRepository<DB, ProductDAO, ProductBy>,
+        SellerRepository:
+            EntityRepository<DB, SellerDAO, NewSellerDAO, UpdateSellerDAO, SellerBy, SellersWhere>,
+        SalesRepository: EntityRepository<DB, SalesDAO, NewSalesDAO, UpdateSalesDAO, SalesBy, SalesWhere>
+            + SalesRecorder<DB>
+            + SoftDeleteRepository<DB, SalesDAO, SalesBy>,
+    {
+        let organization = create_organization(db, "test").await;
+        let product = ProductRepository::insert(
+            db,
+            NewProductDAO {
+                organization_id: organization.id,
+                category_id: None,
+                sku: None,
+                barcode: None,
+                name: "Iphone".to_string(),
+                description: "smartphone".to_string(),
+                amount: 10,
+                price: Money::new(5000, Currency::USD),
+            },
+        )
+        .await
+        .expect("Could not create a new product");
+        let seller = SellerRepository::insert(
+            db,
+            NewSellerDAO {
+                organization_id: organization.id,
+                email: "test@gmail.com".to_string(),
+                password: "test123".to_string(),
+            },
+        )
+        .await
+        .expect("Could not create a seller");
+        let sale = SalesRepository::record_sale(
+            db,
+            RecordSaleDAO {
+                product_id: product.id,
+                seller_id: seller.id,
+                amount: 1,
+            },
+        )
+        .await
+        .expect("Could not record sale");
+
+        let deleted = ProductRepository::delete(db, ProductBy::Id(product.id))
+            .await
+            .expect("Could not delete product");
+        assert_eq!(deleted.id, product.id);
+
+        // The sale history survives, while the product is hidden from every default query
+        let history = SalesRepository::get(db, SalesBy::Id(sale.id))
+            .await
+            .expect("Could not find sale");
+        assert_eq!(history.product_id, product.id);
+        let hidden = ProductRepository::try_get(db, ProductBy::Id(product.id))
+            .await
+            .expect("Could not look up product");
+        assert!(hidden.is_none());
+        let live = ProductRepository::get_all(
+            db,
+            ProductsWhere {
+                organization_id: Some(organization.id),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list products");
+        assert!(live.items.is_empty());
+        let trash = ProductRepository::get_all(
+            db,
+            ProductsWhere {
+                organization_id: Some(organization.id),
+                deleted: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list deleted products");
+        assert_eq!(trash.items.len(), 1);
+        assert_eq!(trash.items[0].id, product.id);
+
+        let again = ProductRepository::delete(db, ProductBy::Id(product.id)).await;
+        assert!(matches!(again, Err(DatabaseError::NotFound(_))));
+        let sold = SalesRepository::record_sale(
+            db,
+            RecordSaleDAO {
+                product_id: product.id,
+                seller_id: seller.id,
+                amount: 1,
+            },
+        )
+        .await;
+        assert!(matches!(sold, Err(DatabaseError::NotFound(_))));
+
+        let restored = ProductRepository::restore(db, ProductBy::Id(product.id))
+            .await
+            .expect("Could not restore product");
+        assert_eq!(restored.amount, 9);
+        let again = ProductRepository::restore(db, ProductBy::Id(product.id)).await;
+        assert!(matches!(again, Err(DatabaseError::NotFound(_))));
+
+        let referenced = ProductRepository::purge(db, ProductBy::Id(product.id)).await;
+        assert!(matches!(
+            referenced,
+            Err(DatabaseError::ForeignKeyViolation { .. })
+        ));
+
+        SalesRepository::delete(db, SalesBy::Id(sale.id))
+            .await
+            .expect("Could not delete sale");
+        let purged = SalesRepository::purge(db, SalesBy::Id(sale.id))
+            .await
+            .expect("Could not purge deleted sale");
+        assert_eq!(purged.id, sale.id);
+        ProductRepository::purge(db, ProductBy::Id(product.id))
+            .await
+            .expect("Could not purge product");
+        let gone = ProductRepository::restore(db, ProductBy::Id(product.id)).await;
+        assert!(matches!(gone, Err(DatabaseError::NotFound(_))));
+    }
+
+    async fn listing<DB: Database>(db: &Pool<DB>)
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+        ProductRepository: EntityRepository<
+            DB,
+            ProductDAO,
+            NewProductDAO,
+            UpdateProductDAO,
+            ProductBy,
+            ProductsWhere,
+        >,
+        SellerRepository:
+            EntityRepository<DB, SellerDAO, NewSellerDAO, UpdateSellerDAO, SellerBy, SellersWhere>,
+        SalesRepository:
+            EntityRepository<DB, SalesDAO, NewSalesDAO, UpdateSalesDAO, SalesBy, SalesWhere>,
+        SalesRepository: SalesReport<DB>,
+    {
+        let mut products = vec![];
+        let mut sellers = vec![];
+        for (name, currency) in [("listing", Currency::USD), ("other", Currency::EUR)] {
+            let organization = create_organization(db, name).await;
+            let product = ProductRepository::insert(
+                db,
+                NewProductDAO {
+                    organization_id: organization.id,
+                    category_id: None,
+                    sku: None,
+                    barcode: None,
+                    name: "Iphone".to_string(),
+                    description: "smartphone".to_string(),
+                    amount: 10,
+                    price: Money::new(5000, currency),
+                },
+            )
+            .await
+            .expect("Could not create a new product");
+            let seller = SellerRepository::insert(
+                db,
+                NewSellerDAO {
+                    organization_id: organization.id,
+                    email: format!("seller@{name}.com"),
+                    password: "test123".to_string(),
+                },
+            )
+            .await
+            .expect("Could not create a seller");
+            products.push((organization, product));
+            sellers.push(seller);
+        }
+
+        for (index, amount) in [(0, 2u32), (0, 5), (0, 1), (1, 4)] {
+            let (organization, product) = &products[index];
+            SalesRepository::insert(
+                db,
+                NewSalesDAO {
+                    organization_id: organization.id,
+                    product_id: product.id,
+                    seller_id: sellers[index].id,
+                    amount,
+                    total_price: product.price.checked_mul(amount).unwrap(),
+                },
+            )
+            .await
+            .expect("Could not create sales");
+        }
+
+        let amounts = |page: Page<SalesDAO>| {
+            page.items
+                .into_iter()
+                .map(|s| s.amount)
+                .collect::<Vec<u32>>()
+        };
+
+        let sales = SalesRepository::get_all(
+            db,
+            SalesWhere {
+                organization_id: Some(products[0].0.id),
+                order_by: SalesOrderBy::Amount,
+                order: SortOrder::Descending,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list sales");
+        assert_eq!(amounts(sales), vec![5, 2, 1]);
+
+        let sales = SalesRepository::get_all(
+            db,
+            SalesWhere {
+                seller_id: Some(sellers[1].id),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list sales");
+        assert_eq!(amounts(sales), vec![4]);
+
+        // Sales recorded within the same instant are told apart by their id, so streaming
+        // page by page neither repeats nor skips rows.
+        let mut after = None;
+        let mut streamed = vec![];
+        loop {
+            let page = SalesRepository::get_all(
+                db,
+                SalesWhere {
+                    product_id: Some(products[0].1.id),
+                    limit: 1,
+                    after,
+                    ..Default::default()
+                },
+            )
+            .await
+            .expect("Could not list sales");
+            after = page.next_cursor.clone();
+            streamed.extend(page.items.into_iter().map(|s| s.id));
+            if after.is_none() {
+                break;
+            }
+        }
+        streamed.sort();
+        streamed.dedup();
+        assert_eq!(streamed.len(), 3);
+
+        let sales = SalesRepository::get_all(
+            db,
+            SalesWhere {
+                created_before: Some(Utc::now() - chrono::Duration::days(1)),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list sales");
+        assert!(sales.items.is_empty());
+
+        let revenue = SalesRepository::revenue(db, SalesWhere::default())
+            .await
+            .expect("Could not sum revenue");
+        assert_eq!(
+            revenue,
+            vec![
+                Money::new(20000, Currency::EUR),
+                Money::new(40000, Currency::USD)
+            ]
+        );
+        let revenue = SalesRepository::revenue(
+            db,
+            SalesWhere {
+                organization_id: Some(products[0].0.id),
+                limit: 1,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not sum revenue");
+        assert_eq!(revenue, vec![Money::new(40000, Currency::USD)]);
+        let revenue = SalesRepository::revenue(
+            db,
+            SalesWhere {
+                created_before: Some(Utc::now() - chrono::Duration::days(1)),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not sum revenue");
+        assert!(revenue.is_empty());
+    }
+
+    async fn orphans<DB: Database>(db: &Pool<DB>)
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+        ProductRepository: EntityRepository<
+            DB,
+            ProductDAO,
+            NewProductDAO,
+            UpdateProductDAO,
+            ProductBy,
+            ProductsWhere,
+        >,
+        SellerRepository:
+            EntityRepository<DB, SellerDAO, NewSellerDAO, UpdateSellerDAO, SellerBy, SellersWhere>,
+        SalesRepository:
+            EntityRepository<DB, SalesDAO, NewSalesDAO, UpdateSalesDAO, SalesBy, SalesWhere>,
+    {
+        let mut organizations = vec![];
+        let mut products = vec![];
+        let mut sellers = vec![];
+        for name in ["first", "second"] {
+            let organization = create_organization(db, name).await;
+            let product = ProductRepository::insert(
+                db,
+                NewProductDAO {
+                    organization_id: organization.id,
+                    category_id: None,
+                    sku: None,
+                    barcode: None,
+                    name: "Iphone".to_string(),
+                    description: "smartphone".to_string(),
+                    amount: 10,
+                    price: Money::new(5000, Currency::USD),
+                },
+            )
+            .await
+            .expect("Could not create a new product");
+            let seller = SellerRepository::insert(
+                db,
+                NewSellerDAO {
+                    organization_id: organization.id,
+                    email: format!("seller@{name}.com"),
+                    password: "test123".to_string(),
+                },
+            )
+            .await
+            .expect("Could not create a seller");
+            organizations.push(organization.id);
+            products.push(product.id);
+            sellers.push(seller.id);
+        }
+
+        let unknown = Uuid::new_v4();
+        let product = ProductRepository::insert(
+            db,
+            NewProductDAO {
+                organization_id: unknown,
+                category_id: None,
+                sku: None,
+                barcode: None,
+                name: "Iphone".to_string(),
+                description: "smartphone".to_string(),
+                amount: 10,
+                price: Money::new(5000, Currency::USD),
+            },
+        )
+        .await;
+        assert!(matches!(
+            product,
+            Err(DatabaseError::ForeignKeyViolation { .. })
+        ));
+        let seller = SellerRepository::insert(
+            db,
+            NewSellerDAO {
+                organization_id: unknown,
+                email: "seller@unknown.com".to_string(),
+                password: "test123".to_string(),
+            },
+        )
+        .await;
+        assert!(matches!(
+            seller,
+            Err(DatabaseError::ForeignKeyViolation { .. })
+        ));
+
+        // Unknown rows, and products and sellers of another organization than the sale's.
+        for (organization_id, product_id, seller_id) in [
+            (unknown, products[0], sellers[0]),
+            (organizations[0], unknown, sellers[0]),
+            (organizations[0], products[0], unknown),
+            (organizations[0], products[0], sellers[1]),
+            (organizations[0], products[1], sellers[0]),
+            (organizations[1], products[0], sellers[0]),
+        ] {
+            let sale = SalesRepository::insert(
+                db,
+                NewSalesDAO {
+                    organization_id,
+                    product_id,
+                    seller_id,
+                    amount: 1,
+                    total_price: Money::new(5000, Currency::USD),
+                },
+            )
+            .await;
+            assert!(
+                matches!(sale, Err(DatabaseError::ForeignKeyViolation { .. })),
+                "{sale:?}"
+            );
+        }
+        let sales = SalesRepository::get_all(db, SalesWhere::default())
+            .await
+            .expect("Could not list sales");
+        assert!(sales.items.is_empty());
+    }
+
+    async fn reporting_revenue<DB: Database>(db: &Pool<DB>)
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+        ProductRepository: EntityRepository<
+            DB,
+            ProductDAO,
+            NewProductDAO,
+            UpdateProductDAO,
+            ProductBy,
+            ProductsWhere,
+        >,
+        SellerRepository:
+            EntityRepository<DB, SellerDAO, NewSellerDAO, UpdateSellerDAO, SellerBy, SellersWhere>,
+        SalesRepository: EntityRepository<DB, SalesDAO, NewSalesDAO, UpdateSalesDAO, SalesBy, SalesWhere>
+            + SalesReport<DB>,
+        ExchangeRateRepository: ExchangeRates<DB>,
+    {
+        let organization = OrganizationRepository::insert(
+            db,
+            NewOrganizationDAO {
+                name: "europe".to_string(),
+                reporting_currency: Currency::EUR,
+            },
+        )
+        .await
+        .expect("Could not create organization");
+        let seller = SellerRepository::insert(
+            db,
+            NewSellerDAO {
+                organization_id: organization.id,
+                email: "seller@europe.com".to_string(),
+                password: "test123".to_string(),
+            },
+        )
+        .await
+        .expect("Could not create a seller");
+        let gbp = "GBP".parse::<Currency>().unwrap();
+        let sell = |price: Money, amount: u32| async move {
+            let product = ProductRepository::insert(
+                db,
+                NewProductDAO {
+                    organization_id: organization.id,
+                    category_id: None,
+                    sku: None,
+                    barcode: None,
+                    name: format!("Item in {}", price.currency),
+                    description: String::new(),
+                    amount: 10,
+                    price,
+                },
+            )
+            .await
+            .expect("Could not create a new product");
+            SalesRepository::insert(
+                db,
+                NewSalesDAO {
+                    organization_id: organization.id,
+                    product_id: product.id,
+                    seller_id: seller.id,
+                    amount,
+                    total_price: price.checked_mul(amount).unwrap(),
+                },
+            )
+            .await
+            .expect("Could not create sales")
+        };
+        sell(Money::new(5000, Currency::USD), 2).await;
+        let euro_sale = sell(Money::new(1000, Currency::EUR), 1).await;
+
+        let revenue =
+            || SalesRepository::reporting_revenue(db, organization.id, SalesWhere::default());
+        assert_eq!(
+            revenue().await,
+            Err(DatabaseError::MissingExchangeRate {
+                from: Currency::USD,
+                to: Currency::EUR
+            })
+        );
+
+        let add_rate = |days: i64, rate: &str| {
+            let rate = rate.parse().unwrap();
+            async move {
+                ExchangeRateRepository::insert(
+                    db,
+                    ExchangeRateDAO {
+                        from_currency: Currency::USD,
+                        to_currency: Currency::EUR,
+                        effective_at: Utc::now() + chrono::Duration::days(days),
+                        rate,
+                    },
+                )
+                .await
+                .expect("Could not insert rate");
+            }
+        };
+        add_rate(-30, "0.9").await;
+        assert_eq!(revenue().await, Ok(Money::new(10000, Currency::EUR)));
+
+        // A newer rate applies to the sales made since it took effect, later ones do not yet.
+        add_rate(-1, "0.92").await;
+        add_rate(1, "2").await;
+        assert_eq!(revenue().await, Ok(Money::new(10200, Currency::EUR)));
+
+        sell(Money::new(100, gbp), 1).await;
+        assert_eq!(
+            revenue().await,
+            Err(DatabaseError::MissingExchangeRate {
+                from: gbp,
+                to: Currency::EUR
+            })
+        );
+        let revenue = SalesRepository::reporting_revenue(
+            db,
+            organization.id,
+            SalesWhere {
+                product_id: Some(euro_sale.product_id),
+                ..Default::default()
+            },
+        )
+        .await;
+        assert_eq!(revenue, Ok(Money::new(1000, Currency::EUR)));
+        let revenue =
+            SalesRepository::reporting_revenue(db, Uuid::new_v4(), SalesWhere::default()).await;
+        assert!(matches!(revenue, Err(DatabaseError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn sqlite_queries() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        queries(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_queries() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            queries(&db.connection).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn sqlite_recording_sales() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        recording_sales(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_recording_sales() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            recording_sales(&db.connection).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn sqlite_listing() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        listing(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_listing() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            listing(&db.connection).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn sqlite_soft_delete() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        soft_delete(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_soft_delete() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            soft_delete(&db.connection).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn sqlite_reporting_revenue() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        reporting_revenue(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_reporting_revenue() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            reporting_revenue(&db.connection).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn sqlite_orphans() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        orphans(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_orphans() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            orphans(&db.connection).await;
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn new_quantities_round_trip_or_are_rejected(amount in any::<u32>()) {
+            let input = NewSalesDAO {
+                organization_id: Uuid::new_v4(),
+                product_id: Uuid::new_v4(),
+                seller_id: Uuid::new_v4(),
+                amount,
+                total_price: Money::new(5000, Currency::USD),
+            };
+            match SqliteSalesDAO::try_from(input) {
+                Ok(row) => {
+                    prop_assert!(i32::try_from(amount).is_ok());
+                    prop_assert_eq!(SalesDAO::try_from(row).map(|s| s.amount), Ok(amount));
+                }
+                Err(e) => {
+                    prop_assert!(i32::try_from(amount).is_err());
+                    prop_assert_eq!(e, DatabaseError::InvalidQuantity(amount.into()));
+                }
+            }
+        }
+
+        #[test]
+        fn stored_quantities_round_trip_or_are_rejected(amount in any::<i32>()) {
+            let row = SqliteSalesDAO {
+                id: Uuid::new_v4(),
+                organization_id: Uuid::new_v4(),
+                product_id: Uuid::new_v4(),
+                seller_id: Uuid::new_v4(),
+                amount,
+                total_price: 5000,
+                currency: Currency::USD,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                version: 1,
+            };
+            match SalesDAO::try_from(row.clone()) {
+                Ok(sale) => {
+                    prop_assert!(amount >= 0);
+                    prop_assert_eq!(SqliteSalesDAO::try_from(sale), Ok(row));
+                }
+                Err(e) => {
+                    prop_assert!(amount < 0);
+                    prop_assert_eq!(e, DatabaseError::InvalidQuantity(amount.into()));
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,485 @@
+use chrono::{DateTime, Utc};
+use sqlx::{Acquire, PgConnection, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use super::{
+    sum_converted, NewSalesDAO, RecordSaleDAO, SalesBy, SalesDAO, SalesRecorder, SalesReport,
+    SalesRepository, SalesWhere, UpdateSalesDAO,
+};
+use crate::{
+    entities::{quantity_from_column, quantity_to_column, version_rejection},
+    money::{Currency, Money, Rate},
+    pagination::{push_after_postgres, Page},
+    traits::{DatabaseError, EntityRepository, SoftDeleteRepository},
+};
+
+#[derive(sqlx::FromRow, Debug, PartialEq, Eq, Clone)]
+pub struct PostgresSalesDAO {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    pub product_id: Uuid,
+    pub seller_id: Uuid,
+    pub amount: i32,
+    pub total_price: i64,
+    pub currency: Currency,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub version: i64,
+}
+
+impl TryFrom<SalesDAO> for PostgresSalesDAO {
+    type Error = DatabaseError;
+
+    fn try_from(value: SalesDAO) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: value.id,
+            organization_id: value.organization_id,
+            product_id: value.product_id,
+            seller_id: value.seller_id,
+            amount: quantity_to_column(value.amount)?,
+            total_price: value.total_price.minor_units,
+            currency: value.total_price.currency,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            version: value.version,
+        })
+    }
+}
+
+impl TryFrom<NewSalesDAO> for PostgresSalesDAO {
+    type Error = DatabaseError;
+
+    fn try_from(value: NewSalesDAO) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: Uuid::default(),
+            organization_id: value.organization_id,
+            product_id: value.product_id,
+            seller_id: value.seller_id,
+            amount: quantity_to_column(value.amount)?,
+            total_price: value.total_price.minor_units,
+            currency: value.total_price.currency,
+            created_at: DateTime::default(),
+            updated_at: DateTime::default(),
+            version: 1,
+        })
+    }
+}
+
+impl TryFrom<UpdateSalesDAO> for PostgresSalesDAO {
+    type Error = DatabaseError;
+
+    fn try_from(value: UpdateSalesDAO) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: Uuid::default(),
+            organization_id: Uuid::default(),
+            product_id: Uuid::default(),
+            seller_id: Uuid::default(),
+            amount: quantity_to_column(value.amount)?,
+            total_price: value.total_price.minor_units,
+            currency: value.total_price.currency,
+            created_at: DateTime::default(),
+            updated_at: DateTime::default(),
+            version: value.version,
+        })
+    }
+}
+
+impl TryFrom<PostgresSalesDAO> for SalesDAO {
+    type Error = DatabaseError;
+
+    fn try_from(value: PostgresSalesDAO) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: value.id,
+            organization_id: value.organization_id,
+            product_id: value.product_id,
+            seller_id: value.seller_id,
+            amount: quantity_from_column(value.amount)?,
+            total_price: Money::new(value.total_price, value.currency),
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            version: value.version,
+        })
+    }
+}
+
+/// Appends the `WHERE` conditions of `key` shared by listings and reports.
+fn push_sales_filters(query: &mut QueryBuilder<'_, Postgres>, key: &SalesWhere) {
+    query.push(if key.deleted {
+        " AND deleted_at IS NOT NULL"
+    } else {
+        " AND deleted_at IS NULL"
+    });
+    if let Some(organization_id) = key.organization_id {
+        query
+            .push(" AND organization_id = ")
+            .push_bind(organization_id);
+    }
+    if let Some(product_id) = key.product_id {
+        query.push(" AND product_id = ").push_bind(product_id);
+    }
+    if let Some(seller_id) = key.seller_id {
+        query.push(" AND seller_id = ").push_bind(seller_id);
+    }
+    if let Some(created_after) = key.created_after {
+        query.push(" AND created_at >= ").push_bind(created_after);
+    }
+    if let Some(created_before) = key.created_before {
+        query.push(" AND created_at < ").push_bind(created_before);
+    }
+}
+
+#[async_trait::async_trait]
+impl EntityRepository<Postgres, SalesDAO, NewSalesDAO, UpdateSalesDAO, SalesBy, SalesWhere>
+    for SalesRepository
+{
+    #[tracing::instrument(
+        name = "repository",
+        level = "debug",
+        skip_all,
+        fields(entity = "sales", operation = "insert"),
+        err(Debug, level = "warn")
+    )]
+    async fn insert<'c, A>(db: A, input: NewSalesDAO) -> Result<SalesDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let uuid = Uuid::new_v4();
+        let input = PostgresSalesDAO::try_from(input)?;
+        sqlx::query_as::<_, PostgresSalesDAO>(
+            "INSERT INTO sales (id, organization_id, product_id, seller_id, amount, total_price, currency) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id, organization_id, product_id, seller_id, amount, total_price, currency, created_at, updated_at, version",
+        )
+        .bind(uuid)
+        .bind(input.organization_id)
+        .bind(input.product_id)
+        .bind(input.seller_id)
+        .bind(input.amount)
+        .bind(input.total_price)
+        .bind(input.currency)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)
+        .and_then(SalesDAO::try_from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "sales", operation = "get", key = ?key), err(Debug, level = "warn"))]
+    async fn get<'c, A>(db: A, key: SalesBy) -> Result<SalesDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        match key {
+            SalesBy::Id(uuid) => sqlx::query_as::<_, PostgresSalesDAO>(
+                "SELECT id, organization_id, product_id, seller_id, amount, total_price, currency, created_at, updated_at, version FROM sales WHERE id = $1 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(uuid)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+            .and_then(SalesDAO::try_from),
+        }
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "sales", operation = "try_get", key = ?key), err(Debug, level = "warn"))]
+    async fn try_get<'c, A>(db: A, key: SalesBy) -> Result<Option<SalesDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        match key {
+            SalesBy::Id(uuid) => sqlx::query_as::<_, PostgresSalesDAO>(
+                "SELECT id, organization_id, product_id, seller_id, amount, total_price, currency, created_at, updated_at, version FROM sales WHERE id = $1 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(uuid)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+            .and_then(|v| v.map(SalesDAO::try_from).transpose()),
+        }
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "sales", operation = "get_all", key = ?key), err(Debug, level = "warn"))]
+    async fn get_all<'c, A>(db: A, key: SalesWhere) -> Result<Page<SalesDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let mut query = QueryBuilder::<Postgres>::new(
+            "SELECT id, organization_id, product_id, seller_id, amount, total_price, currency, created_at, updated_at, version FROM sales WHERE 1 = 1",
+        );
+        push_sales_filters(&mut query, &key);
+        if let Some(after) = &key.after {
+            push_after_postgres(&mut query, key.order_by.column(), key.order, after)?;
+        }
+        query
+            .push(" ORDER BY ")
+            .push(key.order_by.column())
+            .push(" ")
+            .push(key.order.keyword())
+            .push(", id ")
+            .push(key.order.keyword())
+            .push(" LIMIT ")
+            .push_bind(i64::from(key.limit) + 1);
+
+        let rows: Vec<SalesDAO> = query
+            .build_query_as::<PostgresSalesDAO>()
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?
+            .into_iter()
+            .map(SalesDAO::try_from)
+            .collect::<Result<_, _>>()?;
+        Ok(Page::from_rows(rows, key.limit, |sales| {
+            key.order_by.cursor(key.order, sales)
+        }))
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "sales", operation = "update", key = ?key), err(Debug, level = "warn"))]
+    async fn update<'c, A>(
+        db: A,
+        key: SalesBy,
+        input: UpdateSalesDAO,
+    ) -> Result<SalesDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let input = PostgresSalesDAO::try_from(input)?;
+        let updated = match &key {
+            SalesBy::Id(uuid) => {
+                sqlx::query_as::<_, PostgresSalesDAO>("UPDATE sales SET amount = $2, total_price = $3, currency = $4, version = version + 1 WHERE id = $1 AND version = $5 AND deleted_at IS NULL RETURNING id, organization_id, product_id, seller_id, amount, total_price, currency, created_at, updated_at, version")
+                    .bind(*uuid)
+                    .bind(input.amount)
+                    .bind(input.total_price)
+                    .bind(input.currency)
+                    .bind(input.version)
+                    .fetch_optional(&mut *conn)
+                    .await
+                    .map_err(DatabaseError::from)?
+            }
+        };
+        match updated {
+            Some(sales) => SalesDAO::try_from(sales),
+            None => {
+                let missing = format!("sales {key:?}");
+                let current = Self::try_get(&mut *conn, key).await;
+                Err(version_rejection(
+                    current.map(|sales| sales.map(|sales| sales.version)),
+                    missing,
+                ))
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "sales", operation = "delete", key = ?key), err(Debug, level = "warn"))]
+    async fn delete<'c, A>(db: A, key: SalesBy) -> Result<SalesDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        match key {
+            SalesBy::Id(uuid) => sqlx::query_as::<_, PostgresSalesDAO>(
+                "UPDATE sales SET deleted_at = now(), version = version + 1 WHERE id = $1 AND deleted_at IS NULL RETURNING id, organization_id, product_id, seller_id, amount, total_price, currency, created_at, updated_at, version",
+            )
+            .bind(uuid)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+            .and_then(SalesDAO::try_from),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SoftDeleteRepository<Postgres, SalesDAO, SalesBy> for SalesRepository {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "sales", operation = "restore", key = ?key), err(Debug, level = "warn"))]
+    async fn restore<'c, A>(db: A, key: SalesBy) -> Result<SalesDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        match key {
+            SalesBy::Id(uuid) => sqlx::query_as::<_, PostgresSalesDAO>(
+                "UPDATE sales SET deleted_at = NULL, version = version + 1 WHERE id = $1 AND deleted_at IS NOT NULL RETURNING id, organization_id, product_id, seller_id, amount, total_price, currency, created_at, updated_at, version",
+            )
+            .bind(uuid)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+            .and_then(SalesDAO::try_from),
+        }
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "sales", operation = "purge", key = ?key), err(Debug, level = "warn"))]
+    async fn purge<'c, A>(db: A, key: SalesBy) -> Result<SalesDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        match key {
+            SalesBy::Id(uuid) => sqlx::query_as::<_, PostgresSalesDAO>(
+                "DELETE FROM sales WHERE id = $1 RETURNING id, organization_id, product_id, seller_id, amount, total_price, currency, created_at, updated_at, version",
+            )
+            .bind(uuid)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+            .and_then(SalesDAO::try_from),
+        }
+    }
+}
+
+/// Explains why the stock reservation of `record_sale` matched no product.
+async fn sale_rejection(conn: &mut PgConnection, input: &RecordSaleDAO) -> DatabaseError {
+    let product = sqlx::query_as::<_, (Uuid, i32)>(
+        "SELECT organization_id, amount FROM products WHERE id = $1 AND deleted_at IS NULL LIMIT 1",
+    )
+    .bind(input.product_id)
+    .fetch_optional(&mut *conn)
+    .await;
+    let seller = sqlx::query_as::<_, (Uuid,)>(
+        "SELECT organization_id FROM sellers WHERE id = $1 AND deleted_at IS NULL LIMIT 1",
+    )
+    .bind(input.seller_id)
+    .fetch_optional(&mut *conn)
+    .await;
+
+    match (product, seller) {
+        (Err(e), _) | (_, Err(e)) => DatabaseError::from(e),
+        (Ok(None), _) => DatabaseError::NotFound(format!("product {}", input.product_id)),
+        (_, Ok(None)) => DatabaseError::NotFound(format!("seller {}", input.seller_id)),
+        (Ok(Some((product_organization, _))), Ok(Some((seller_organization,))))
+            if product_organization != seller_organization =>
+        {
+            DatabaseError::OrganizationMismatch
+        }
+        (Ok(Some((_, available))), _) => match quantity_from_column(available) {
+            Ok(available) => DatabaseError::InsufficientStock {
+                available,
+                requested: input.amount,
+            },
+            Err(e) => e,
+        },
+    }
+}
+
+#[async_trait::async_trait]
+impl SalesRecorder<Postgres> for SalesRepository {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "sales", operation = "record_sale", product_id = %input.product_id, seller_id = %input.seller_id), err(Debug, level = "warn"))]
+    async fn record_sale<'c, A>(db: A, input: RecordSaleDAO) -> Result<SalesDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        if input.amount == 0 {
+            return Err(DatabaseError::InvalidQuantity(0));
+        }
+        let mut tx = db.begin().await.map_err(DatabaseError::from)?;
+        // Checking stock and organization in the UPDATE itself keeps concurrent sales from
+        // overselling the same product.
+        let reserved = sqlx::query_as::<_, (Uuid, i64, Currency)>(
+            "UPDATE products SET amount = amount - $2, version = version + 1 WHERE id = $1 AND deleted_at IS NULL AND amount >= $2 AND organization_id = (SELECT organization_id FROM sellers WHERE id = $3 AND deleted_at IS NULL) RETURNING organization_id, price, currency",
+        )
+        .bind(input.product_id)
+        .bind(i64::from(input.amount))
+        .bind(input.seller_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(DatabaseError::from)?;
+
+        let Some((organization_id, price, currency)) = reserved else {
+            return Err(sale_rejection(&mut tx, &input).await);
+        };
+        let total_price = Money::new(price, currency)
+            .checked_mul(input.amount)
+            .ok_or_else(|| DatabaseError::InvalidMoney("total price overflows".to_string()))?;
+
+        let sale = SalesRepository::insert(
+            &mut tx,
+            NewSalesDAO {
+                organization_id,
+                product_id: input.product_id,
+                seller_id: input.seller_id,
+                amount: input.amount,
+                total_price,
+            },
+        )
+        .await?;
+        tx.commit().await.map_err(DatabaseError::from)?;
+        Ok(sale)
+    }
+}
+
+#[async_trait::async_trait]
+impl SalesReport<Postgres> for SalesRepository {
+    #[tracing::instrument(
+        name = "repository",
+        level = "debug",
+        skip_all,
+        fields(entity = "sales", operation = "revenue"),
+        err(Debug, level = "warn")
+    )]
+    async fn revenue<'c, A>(db: A, key: SalesWhere) -> Result<Vec<Money>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let mut query = QueryBuilder::<Postgres>::new(
+            "SELECT currency, SUM(total_price)::BIGINT FROM sales WHERE 1 = 1",
+        );
+        push_sales_filters(&mut query, &key);
+        query.push(" GROUP BY currency ORDER BY currency");
+
+        let totals = query
+            .build_query_as::<(Currency, i64)>()
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?;
+        Ok(totals
+            .into_iter()
+            .map(|(currency, total)| Money::new(total, currency))
+            .collect())
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "sales", operation = "reporting_revenue", key = ?organization_id), err(Debug, level = "warn"))]
+    async fn reporting_revenue<'c, A>(
+        db: A,
+        organization_id: Uuid,
+        key: SalesWhere,
+    ) -> Result<Money, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let (reporting_currency,) = sqlx::query_as::<_, (Currency,)>(
+            "SELECT reporting_currency FROM organizations WHERE id = $1 AND deleted_at IS NULL LIMIT 1",
+        )
+        .bind(organization_id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)?
+        .ok_or_else(|| DatabaseError::NotFound(format!("organization {organization_id}")))?;
+
+        // Sales are grouped by the rate that applies to them, so each group converts once.
+        let mut query = QueryBuilder::<Postgres>::new(
+            "SELECT currency, rate, SUM(total_price)::BIGINT FROM (SELECT currency, total_price, (SELECT rate FROM exchange_rates WHERE from_currency = sales.currency AND to_currency = ",
+        );
+        query.push_bind(reporting_currency).push(
+            " AND effective_at <= sales.created_at ORDER BY effective_at DESC LIMIT 1) AS rate FROM sales WHERE 1 = 1",
+        );
+        push_sales_filters(
+            &mut query,
+            &SalesWhere {
+                organization_id: Some(organization_id),
+                ..key
+            },
+        );
+        query.push(") AS converted GROUP BY currency, rate");
+
+        let totals = query
+            .build_query_as::<(Currency, Option<Rate>, i64)>()
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?;
+        sum_converted(totals, reporting_currency)
+    }
+}
@@ -1,146 +1,531 @@
+use std::fmt;
+
 use chrono::{DateTime, Utc};
-use sqlx::{Pool, Sqlite};
+use sqlx::{Acquire, QueryBuilder, Sqlite};
 use uuid::Uuid;
 
-use crate::traits::{DatabaseError, EntityRepository};
+use crate::{
+    entities::{like_prefix, normalize_email, version_rejection, SortOrder, REDACTED},
+    pagination::{push_after_sqlite, Cursor, CursorKey, Page},
+    password::{self, Verification},
+    sqlite,
+    traits::{CredentialsRepository, DatabaseError, EntityRepository, SoftDeleteRepository},
+};
+
+#[cfg(feature = "postgres")]
+pub mod postgres;
 
+#[derive(Clone)]
 pub enum SellerBy {
     Id(Uuid),
+    /// Emails are unique within an organization; matched case-insensitively.
+    Email {
+        organization_id: Uuid,
+        email: String,
+    },
 }
 
-#[derive(sqlx::FromRow, Debug, PartialEq, Eq, Clone)]
+/// Leaves the email out: keys are recorded in tracing spans and error messages.
+impl fmt::Debug for SellerBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SellerBy::Id(id) => f.debug_tuple("Id").field(id).finish(),
+            SellerBy::Email {
+                organization_id, ..
+            } => f
+                .debug_struct("Email")
+                .field("organization_id", organization_id)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SellersOrderBy {
+    #[default]
+    CreatedAt,
+    Email,
+}
+
+impl SellersOrderBy {
+    pub(crate) fn column(&self) -> &'static str {
+        match self {
+            SellersOrderBy::CreatedAt => "created_at",
+            SellersOrderBy::Email => "email",
+        }
+    }
+
+    pub(crate) fn cursor(&self, order: SortOrder, seller: &SellerDAO) -> Cursor {
+        let key = match self {
+            SellersOrderBy::CreatedAt => CursorKey::Timestamp(seller.created_at),
+            SellersOrderBy::Email => CursorKey::Text(seller.email.clone()),
+        };
+        Cursor::new(self.column(), order, key, seller.id)
+    }
+}
+
+/// Filters used to list sellers. Filters left as `None` are not applied.
+#[derive(PartialEq, Eq, Clone)]
+pub struct SellersWhere {
+    pub organization_id: Option<Uuid>,
+    pub active: Option<bool>,
+    /// Case-insensitive prefix of the seller email.
+    pub email_prefix: Option<String>,
+    /// Only sellers created at or after this instant.
+    pub created_after: Option<DateTime<Utc>>,
+    /// Only sellers created before this instant.
+    pub created_before: Option<DateTime<Utc>>,
+    pub order_by: SellersOrderBy,
+    pub order: SortOrder,
+    pub limit: i32,
+    /// Resume after the last row of a previous page.
+    pub after: Option<Cursor>,
+    /// List soft-deleted rows instead of live ones.
+    pub deleted: bool,
+}
+
+/// Leaves the email prefix out: filters are recorded in tracing spans.
+impl fmt::Debug for SellersWhere {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SellersWhere")
+            .field("organization_id", &self.organization_id)
+            .field("active", &self.active)
+            .field("created_after", &self.created_after)
+            .field("created_before", &self.created_before)
+            .field(
+                "email_prefix",
+                &self.email_prefix.as_ref().map(|_| REDACTED),
+            )
+            .field("order_by", &self.order_by)
+            .field("order", &self.order)
+            .field("limit", &self.limit)
+            .field("after", &self.after)
+            .field("deleted", &self.deleted)
+            .finish()
+    }
+}
+
+impl Default for SellersWhere {
+    fn default() -> Self {
+        Self {
+            organization_id: None,
+            active: None,
+            email_prefix: None,
+            created_after: None,
+            created_before: None,
+            order_by: SellersOrderBy::default(),
+            order: SortOrder::default(),
+            limit: 100,
+            after: None,
+            deleted: false,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow, Debug, PartialEq, Eq, Clone, serde::Serialize)]
 pub struct SellerDAO {
     pub id: Uuid,
     pub organization_id: Uuid,
     pub email: String,
-    pub password: String,
     pub active: bool,
     pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub version: i64,
 }
 
 #[derive(sqlx::FromRow, Debug, PartialEq, Eq, Clone)]
 pub struct NewSellerDAO {
     pub organization_id: Uuid,
     pub email: String,
+    /// Plaintext password; only its Argon2id hash is stored.
     pub password: String,
 }
 
 #[derive(sqlx::FromRow, Debug, PartialEq, Eq, Clone)]
 pub struct UpdateSellerDAO {
-    pub password: String,
+    /// New plaintext password, or `None` to keep the current one.
+    pub password: Option<String>,
     pub active: bool,
+    /// Version of the row the update is based on; the update fails with
+    /// [`DatabaseError::Conflict`] when the row changed since.
+    pub version: i64,
 }
 
 #[derive(Debug)]
 pub struct SellerRepository;
 
+/// Builds the query of `get_all`; kept apart so the query plan tests can check it.
+pub(crate) fn list_query(
+    key: &SellersWhere,
+) -> Result<QueryBuilder<'static, Sqlite>, DatabaseError> {
+    let mut query = QueryBuilder::<Sqlite>::new(
+        "SELECT id, organization_id, email, active, created_at, updated_at, version FROM sellers WHERE 1 = 1",
+    );
+    query.push(if key.deleted {
+        " AND deleted_at IS NOT NULL"
+    } else {
+        " AND deleted_at IS NULL"
+    });
+    if let Some(organization_id) = key.organization_id {
+        query
+            .push(" AND organization_id = ")
+            .push_bind(organization_id);
+    }
+    if let Some(active) = key.active {
+        query.push(" AND active = ").push_bind(active);
+    }
+    if let Some(prefix) = &key.email_prefix {
+        query
+            .push(" AND lower(email) LIKE ")
+            .push_bind(like_prefix(prefix))
+            .push(" ESCAPE '\\'");
+    }
+    if let Some(created_after) = key.created_after {
+        query
+            .push(" AND created_at >= ")
+            .push_bind(sqlite::timestamp(created_after));
+    }
+    if let Some(created_before) = key.created_before {
+        query
+            .push(" AND created_at < ")
+            .push_bind(sqlite::timestamp(created_before));
+    }
+    if let Some(after) = &key.after {
+        push_after_sqlite(&mut query, key.order_by.column(), key.order, after)?;
+    }
+    query
+        .push(" ORDER BY ")
+        .push(key.order_by.column())
+        .push(" ")
+        .push(key.order.keyword())
+        .push(", id ")
+        .push(key.order.keyword())
+        .push(" LIMIT ")
+        .push_bind(i64::from(key.limit) + 1);
+    Ok(query)
+}
+
 #[async_trait::async_trait]
-impl EntityRepository<Sqlite, SellerDAO, NewSellerDAO, UpdateSellerDAO, SellerBy, SellerBy>
+impl EntityRepository<Sqlite, SellerDAO, NewSellerDAO, UpdateSellerDAO, SellerBy, SellersWhere>
     for SellerRepository
 {
-    async fn insert(db: &Pool<Sqlite>, input: NewSellerDAO) -> Result<SellerDAO, DatabaseError> {
+    #[tracing::instrument(
+        name = "repository",
+        level = "debug",
+        skip_all,
+        fields(entity = "seller", operation = "insert"),
+        err(Debug, level = "warn")
+    )]
+    async fn insert<'c, A>(db: A, input: NewSellerDAO) -> Result<SellerDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let password_hash = password::hash(input.password).await?;
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
         let uuid = Uuid::new_v4();
         sqlx::query_as::<_, SellerDAO>(
-            "INSERT INTO sellers (id, organization_id, email, password) VALUES ($1, $2, $3, $4) RETURNING id, organization_id, email, password, active, created_at",
+            "INSERT INTO sellers (id, organization_id, email, password_hash) VALUES ($1, $2, $3, $4) RETURNING id, organization_id, email, active, created_at, updated_at, version",
         )
         .bind(uuid)
         .bind(input.organization_id)
-        .bind(input.email)
-        .bind(input.password)
-        .fetch_one(db)
+        .bind(normalize_email(&input.email))
+        .bind(password_hash)
+        .fetch_one(&mut *conn)
         .await
         .map_err(DatabaseError::from)
     }
 
-    async fn get(db: &Pool<Sqlite>, key: SellerBy) -> Result<SellerDAO, DatabaseError> {
-        match key {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "seller", operation = "get", key = ?key), err(Debug, level = "warn"))]
+    async fn get<'c, A>(db: A, key: SellerBy) -> Result<SellerDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
             SellerBy::Id(uuid) => sqlx::query_as::<_, SellerDAO>(
-                "SELECT id, organization_id, email, password, active, created_at FROM sellers WHERE id = $1 LIMIT 1",
+                "SELECT id, organization_id, email, active, created_at, updated_at, version FROM sellers WHERE id = $1 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(uuid),
+            SellerBy::Email { organization_id, email } => sqlx::query_as::<_, SellerDAO>(
+                "SELECT id, organization_id, email, active, created_at, updated_at, version FROM sellers WHERE organization_id = $1 AND email = $2 AND deleted_at IS NULL LIMIT 1",
             )
-            .bind(uuid)
-            .fetch_one(db)
+            .bind(organization_id)
+            .bind(normalize_email(&email)),
+        };
+        query
+            .fetch_one(&mut *conn)
             .await
-            .map_err(DatabaseError::from),
-        }
+            .map_err(DatabaseError::from)
     }
 
-    async fn try_get(db: &Pool<Sqlite>, key: SellerBy) -> Result<Option<SellerDAO>, DatabaseError> {
-        match key {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "seller", operation = "try_get", key = ?key), err(Debug, level = "warn"))]
+    async fn try_get<'c, A>(db: A, key: SellerBy) -> Result<Option<SellerDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
             SellerBy::Id(uuid) => sqlx::query_as::<_, SellerDAO>(
-                "SELECT id, organization_id, email, password, active, created_at FROM sellers WHERE id = $1 LIMIT 1",
+                "SELECT id, organization_id, email, active, created_at, updated_at, version FROM sellers WHERE id = $1 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(uuid),
+            SellerBy::Email { organization_id, email } => sqlx::query_as::<_, SellerDAO>(
+                "SELECT id, organization_id, email, active, created_at, updated_at, version FROM sellers WHERE organization_id = $1 AND email = $2 AND deleted_at IS NULL LIMIT 1",
             )
-            .bind(uuid)
-            .fetch_optional(db)
+            .bind(organization_id)
+            .bind(normalize_email(&email)),
+        };
+        query
+            .fetch_optional(&mut *conn)
             .await
-            .map_err(DatabaseError::from),
-        }
+            .map_err(DatabaseError::from)
     }
 
-    async fn get_all(_db: &Pool<Sqlite>, _key: SellerBy) -> Result<Vec<SellerDAO>, DatabaseError> {
-        Err(DatabaseError::NotImplemented)
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "seller", operation = "get_all", key = ?key), err(Debug, level = "warn"))]
+    async fn get_all<'c, A>(db: A, key: SellersWhere) -> Result<Page<SellerDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let mut query = list_query(&key)?;
+        let rows = query
+            .build_query_as::<SellerDAO>()
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?;
+        Ok(Page::from_rows(rows, key.limit, |seller| {
+            key.order_by.cursor(key.order, seller)
+        }))
     }
 
-    async fn update(
-        db: &Pool<Sqlite>,
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "seller", operation = "update", key = ?key), err(Debug, level = "warn"))]
+    async fn update<'c, A>(
+        db: A,
         key: SellerBy,
         input: UpdateSellerDAO,
-    ) -> Result<SellerDAO, DatabaseError> {
-        match key {
-            SellerBy::Id(uuid) => {
-                sqlx::query_as::<_, SellerDAO>("UPDATE sellers SET password = $2, active = $3 WHERE id = $1 RETURNING id, organization_id, email, password, active, created_at")
-                    .bind(uuid)
-                    .bind(input.password)
-                    .bind(input.active)
-                    .fetch_one(db)
-                    .await
-                    .map_err(DatabaseError::from)
+    ) -> Result<SellerDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let password_hash = match input.password {
+            Some(password) => Some(password::hash(password).await?),
+            None => None,
+        };
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match &key {
+            SellerBy::Id(uuid) => sqlx::query_as::<_, SellerDAO>("UPDATE sellers SET password_hash = COALESCE($2, password_hash), active = $3, updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE id = $1 AND version = $4 AND deleted_at IS NULL RETURNING id, organization_id, email, active, created_at, updated_at, version")
+                .bind(*uuid),
+            SellerBy::Email { organization_id, email } => sqlx::query_as::<_, SellerDAO>("UPDATE sellers SET password_hash = COALESCE($3, password_hash), active = $4, updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE organization_id = $1 AND email = $2 AND version = $5 AND deleted_at IS NULL RETURNING id, organization_id, email, active, created_at, updated_at, version")
+                .bind(*organization_id)
+                .bind(normalize_email(email)),
+        };
+        let updated = query
+            .bind(password_hash)
+            .bind(input.active)
+            .bind(input.version)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?;
+        match updated {
+            Some(seller) => Ok(seller),
+            None => {
+                let missing = format!("seller {key:?}");
+                let current = Self::try_get(&mut *conn, key).await;
+                Err(version_rejection(
+                    current.map(|seller| seller.map(|seller| seller.version)),
+                    missing,
+                ))
             }
         }
     }
 
-    async fn delete(db: &Pool<Sqlite>, key: SellerBy) -> Result<SellerDAO, DatabaseError> {
-        match key {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "seller", operation = "delete", key = ?key), err(Debug, level = "warn"))]
+    async fn delete<'c, A>(db: A, key: SellerBy) -> Result<SellerDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            SellerBy::Id(uuid) => sqlx::query_as::<_, SellerDAO>(
+                "UPDATE sellers SET deleted_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE id = $1 AND deleted_at IS NULL RETURNING id, organization_id, email, active, created_at, updated_at, version",
+            )
+            .bind(uuid),
+            SellerBy::Email { organization_id, email } => sqlx::query_as::<_, SellerDAO>(
+                "UPDATE sellers SET deleted_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE organization_id = $1 AND email = $2 AND deleted_at IS NULL RETURNING id, organization_id, email, active, created_at, updated_at, version",
+            )
+            .bind(organization_id)
+            .bind(normalize_email(&email)),
+        };
+        query
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+    }
+}
+
+#[async_trait::async_trait]
+impl SoftDeleteRepository<Sqlite, SellerDAO, SellerBy> for SellerRepository {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "seller", operation = "restore", key = ?key), err(Debug, level = "warn"))]
+    async fn restore<'c, A>(db: A, key: SellerBy) -> Result<SellerDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            SellerBy::Id(uuid) => sqlx::query_as::<_, SellerDAO>(
+                "UPDATE sellers SET deleted_at = NULL, updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE id = $1 AND deleted_at IS NOT NULL RETURNING id, organization_id, email, active, created_at, updated_at, version",
+            )
+            .bind(uuid),
+            SellerBy::Email { organization_id, email } => sqlx::query_as::<_, SellerDAO>(
+                "UPDATE sellers SET deleted_at = NULL, updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE organization_id = $1 AND email = $2 AND deleted_at IS NOT NULL RETURNING id, organization_id, email, active, created_at, updated_at, version",
+            )
+            .bind(organization_id)
+            .bind(normalize_email(&email)),
+        };
+        query
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "seller", operation = "purge", key = ?key), err(Debug, level = "warn"))]
+    async fn purge<'c, A>(db: A, key: SellerBy) -> Result<SellerDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        // sqlx steps a statement again after SQLite reports an error, which re-runs the DELETE;
+        // rolling back keeps a seller still referenced by sales from being removed later on.
+        let mut tx = db.begin().await.map_err(DatabaseError::from)?;
+        let query = match key {
             SellerBy::Id(uuid) => sqlx::query_as::<_, SellerDAO>(
-                "DELETE FROM sellers WHERE id = $1 RETURNING id, organization_id, email, password, active, created_at",
+                "DELETE FROM sellers WHERE id = $1 RETURNING id, organization_id, email, active, created_at, updated_at, version",
+            )
+            .bind(uuid),
+            SellerBy::Email { organization_id, email } => sqlx::query_as::<_, SellerDAO>(
+                "DELETE FROM sellers WHERE organization_id = $1 AND email = $2 RETURNING id, organization_id, email, active, created_at, updated_at, version",
             )
-            .bind(uuid)
-            .fetch_one(db)
+            .bind(organization_id)
+            .bind(normalize_email(&email)),
+        };
+        let seller = query
+            .fetch_one(&mut *tx)
             .await
-            .map_err(DatabaseError::from),
+            .map_err(DatabaseError::from)?;
+        tx.commit().await.map_err(DatabaseError::from)?;
+        Ok(seller)
+    }
+}
+
+#[async_trait::async_trait]
+impl CredentialsRepository<Sqlite, SellerDAO> for SellerRepository {
+    #[tracing::instrument(
+        name = "repository",
+        level = "debug",
+        skip_all,
+        fields(entity = "seller", operation = "verify_password"),
+        err(Debug, level = "warn")
+    )]
+    async fn verify_password<'c, A>(
+        db: A,
+        organization_id: Uuid,
+        email: &str,
+        candidate: &str,
+    ) -> Result<Option<SellerDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let stored = sqlx::query_as::<_, (Uuid, String)>(
+            "SELECT id, password_hash FROM sellers WHERE organization_id = $1 AND email = $2 AND deleted_at IS NULL LIMIT 1",
+        )
+        .bind(organization_id)
+        .bind(normalize_email(email))
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)?;
+        let Some((id, stored)) = stored else {
+            password::verify_nothing(candidate.to_string()).await?;
+            return Ok(None);
+        };
+
+        match password::verify(stored.clone(), candidate.to_string()).await? {
+            Verification::Invalid => return Ok(None),
+            Verification::Valid => {}
+            Verification::ValidNeedsRehash => {
+                let rehashed = password::hash(candidate.to_string()).await?;
+                // Same password, new hash: not a change callers need to see in `version`
+                sqlx::query(
+                    "UPDATE sellers SET password_hash = $2, updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now') WHERE id = $1 AND password_hash = $3",
+                )
+                .bind(id)
+                .bind(rehashed)
+                .bind(stored)
+                .execute(&mut *conn)
+                .await
+                .map_err(DatabaseError::from)?;
+            }
         }
+
+        SellerRepository::get(&mut *conn, SellerBy::Id(id))
+            .await
+            .map(Some)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        entities::organization::{NewOrganizationDAO, OrganizationDAO, OrganizationRepository},
+        entities::organization::{
+            NewOrganizationDAO, OrganizationBy, OrganizationDAO, OrganizationRepository,
+            OrganizationsWhere, UpdateOrganizationDAO,
+        },
+        money::Currency,
         sqlite::DatabaseRepository,
     };
+    use sqlx::Database;
 
     use super::*;
+    use sqlx::Pool;
 
-    async fn create_organization(pool: &Pool<Sqlite>, name: &str) -> OrganizationDAO {
+    async fn create_organization<DB: Database>(pool: &Pool<DB>, name: &str) -> OrganizationDAO
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+    {
         OrganizationRepository::insert(
             pool,
             NewOrganizationDAO {
                 name: name.to_string(),
+                reporting_currency: Currency::USD,
             },
         )
         .await
         .expect("Could not create organization")
     }
 
-    #[tokio::test]
-    async fn queries() {
-        let db = DatabaseRepository::new()
-            .await
-            .expect("Could not initialize db");
-
-        let organization = create_organization(&db.connection, "test").await;
+    async fn queries<DB: Database>(db: &Pool<DB>)
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+        SellerRepository: EntityRepository<DB, SellerDAO, NewSellerDAO, UpdateSellerDAO, SellerBy, SellersWhere>
+            + CredentialsRepository<DB, SellerDAO>,
+    {
+        let organization = create_organization(db, "test").await;
 
         let seller = SellerRepository::insert(
-            &db.connection,
+            db,
             NewSellerDAO {
                 organization_id: organization.id,
                 email: "test@gmail.com".to_string(),
@@ -150,47 +535,415 @@ mod tests {
         .await
         .expect("Could not create a seller");
 
-        let seller = SellerRepository::get(&db.connection, SellerBy::Id(seller.id))
+        let seller = SellerRepository::get(db, SellerBy::Id(seller.id))
             .await
             .expect("Could not find seller");
 
         assert_eq!(seller.organization_id, organization.id);
         assert_eq!(seller.email, "test@gmail.com");
-        assert_eq!(seller.password, "test123");
         assert!(seller.active);
 
-        let seller = SellerRepository::try_get(&db.connection, SellerBy::Id(seller.id))
+        let seller = SellerRepository::try_get(db, SellerBy::Id(seller.id))
             .await
             .expect("Could not find seller")
             .unwrap();
 
         assert_eq!(seller.organization_id, organization.id);
         assert_eq!(seller.email, "test@gmail.com");
-        assert_eq!(seller.password, "test123");
         assert!(seller.active);
 
         let updated = SellerRepository::update(
-            &db.connection,
+            db,
             SellerBy::Id(seller.id),
             UpdateSellerDAO {
-                password: "newpassword".to_string(),
+                password: Some("newpassword".to_string()),
                 active: false,
+                version: seller.version,
             },
         )
         .await
         .expect("Could not find seller");
 
-        assert_eq!(updated.password, "newpassword");
+        let verified =
+            SellerRepository::verify_password(db, organization.id, "test@gmail.com", "newpassword")
+                .await
+                .expect("Could not verify password");
+        assert_eq!(verified, Some(updated.clone()));
+        let verified =
+            SellerRepository::verify_password(db, organization.id, "test@gmail.com", "test123")
+                .await
+                .expect("Could not verify password");
+        assert_eq!(verified, None);
         assert!(!updated.active);
 
-        let deleted = SellerRepository::delete(&db.connection, SellerBy::Id(seller.id))
+        let deleted = SellerRepository::delete(db, SellerBy::Id(seller.id))
             .await
             .expect("Could not delete seller");
 
-        let maybe_seller = SellerRepository::try_get(&db.connection, SellerBy::Id(deleted.id))
+        let maybe_seller = SellerRepository::try_get(db, SellerBy::Id(deleted.id))
             .await
             .expect("Could not find seller");
 
         assert!(maybe_seller.is_none());
+
+        let orphan = SellerRepository::insert(
+            db,
+            NewSellerDAO {
+                organization_id: Uuid::default(),
+                email: "orphan@gmail.com".to_string(),
+                password: "test123".to_string(),
+            },
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(orphan, DatabaseError::ForeignKeyViolation { .. }));
+    }
+
+    async fn listing<DB: Database>(db: &Pool<DB>)
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+        SellerRepository:
+            EntityRepository<DB, SellerDAO, NewSellerDAO, UpdateSellerDAO, SellerBy, SellersWhere>,
+    {
+        let organization = create_organization(db, "listing").await;
+        let other = create_organization(db, "other").await;
+
+        for (organization_id, email) in [
+            (organization.id, "ana@shop.com"),
+            (organization.id, "bob@shop.com"),
+            (organization.id, "alice@shop.com"),
+            (other.id, "amy@other.com"),
+        ] {
+            SellerRepository::insert(
+                db,
+                NewSellerDAO {
+                    organization_id,
+                    email: email.to_string(),
+                    password: "test123".to_string(),
+                },
+            )
+            .await
+            .expect("Could not create a seller");
+        }
+
+        let bob = SellerRepository::get_all(
+            db,
+            SellersWhere {
+                email_prefix: Some("BOB".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list sellers")
+        .items
+        .remove(0);
+        SellerRepository::update(
+            db,
+            SellerBy::Id(bob.id),
+            UpdateSellerDAO {
+                password: None,
+                active: false,
+                version: bob.version,
+            },
+        )
+        .await
+        .expect("Could not update seller");
+
+        let emails = |page: Page<SellerDAO>| {
+            page.items
+                .into_iter()
+                .map(|s| s.email)
+                .collect::<Vec<String>>()
+        };
+
+        let sellers = SellerRepository::get_all(
+            db,
+            SellersWhere {
+                organization_id: Some(organization.id),
+                active: Some(true),
+                order_by: SellersOrderBy::Email,
+                order: SortOrder::Descending,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list sellers");
+        assert_eq!(emails(sellers), vec!["ana@shop.com", "alice@shop.com"]);
+
+        let first = SellerRepository::get_all(
+            db,
+            SellersWhere {
+                email_prefix: Some("a".to_string()),
+                order_by: SellersOrderBy::Email,
+                order: SortOrder::Descending,
+                limit: 2,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list sellers");
+        let after = first.next_cursor.clone();
+        assert_eq!(emails(first), vec!["ana@shop.com", "amy@other.com"]);
+
+        let second = SellerRepository::get_all(
+            db,
+            SellersWhere {
+                email_prefix: Some("a".to_string()),
+                order_by: SellersOrderBy::Email,
+                order: SortOrder::Descending,
+                limit: 2,
+                after,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list sellers");
+        assert_eq!(second.next_cursor, None);
+        assert_eq!(emails(second), vec!["alice@shop.com"]);
+
+        let sellers = SellerRepository::get_all(
+            db,
+            SellersWhere {
+                created_after: Some(Utc::now() + chrono::Duration::days(1)),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list sellers");
+        assert!(sellers.items.is_empty());
+    }
+
+    async fn lookups<DB: Database>(db: &Pool<DB>)
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+        SellerRepository: EntityRepository<DB, SellerDAO, NewSellerDAO, UpdateSellerDAO, SellerBy, SellersWhere>
+            + CredentialsRepository<DB, SellerDAO>,
+    {
+        let organization = create_organization(db, "dev").await;
+        let empty_organization = create_organization(db, "ops").await;
+        let mut sellers = Vec::new();
+        for email in [" Mixed@Shop.COM ", "ana@shop.com"] {
+            let seller = SellerRepository::insert(
+                db,
+                NewSellerDAO {
+                    organization_id: organization.id,
+                    email: email.to_string(),
+                    password: "secret".to_string(),
+                },
+            )
+            .await
+            .expect("Could not create a seller");
+            sellers.push(seller);
+        }
+        assert_eq!(sellers[0].email, "mixed@shop.com");
+
+        let duplicate = SellerRepository::insert(
+            db,
+            NewSellerDAO {
+                organization_id: organization.id,
+                email: "MIXED@shop.com".to_string(),
+                password: "secret".to_string(),
+            },
+        )
+        .await;
+        assert!(matches!(
+            duplicate,
+            Err(DatabaseError::UniqueViolation { .. })
+        ));
+
+        let seller = SellerRepository::get(
+            db,
+            SellerBy::Email {
+                organization_id: organization.id,
+                email: "MIXED@SHOP.com".to_string(),
+            },
+        )
+        .await
+        .expect("Could not find seller by email");
+        assert_eq!(seller, sellers[0]);
+        let missing = SellerRepository::try_get(
+            db,
+            SellerBy::Email {
+                organization_id: organization.id,
+                email: "bob@shop.com".to_string(),
+            },
+        )
+        .await
+        .expect("Could not look up seller");
+        assert!(missing.is_none());
+
+        // Emails are only unique within an organization
+        let other_organization = create_organization(db, "other").await;
+        let elsewhere = SellerRepository::insert(
+            db,
+            NewSellerDAO {
+                organization_id: other_organization.id,
+                email: "mixed@shop.com".to_string(),
+                password: "other secret".to_string(),
+            },
+        )
+        .await
+        .expect("Could not create a seller in another organization");
+        let found = SellerRepository::get(
+            db,
+            SellerBy::Email {
+                organization_id: other_organization.id,
+                email: "mixed@shop.com".to_string(),
+            },
+        )
+        .await
+        .expect("Could not find seller by email");
+        assert_eq!(found, elsewhere);
+        assert_ne!(found.id, seller.id);
+        let verified =
+            SellerRepository::verify_password(db, organization.id, "Mixed@Shop.com", "secret")
+                .await
+                .expect("Could not verify password");
+        assert_eq!(verified, Some(seller));
+
+        // An organization's sellers are listed rather than looked up
+        let by_email = |organization_id| SellersWhere {
+            organization_id: Some(organization_id),
+            order_by: SellersOrderBy::Email,
+            ..Default::default()
+        };
+        let listed = SellerRepository::get_all(db, by_email(organization.id))
+            .await
+            .expect("Could not list sellers of the organization");
+        assert_eq!(listed.items.first(), Some(&sellers[1]));
+        let none = SellerRepository::get_all(db, by_email(empty_organization.id))
+            .await
+            .expect("Could not list sellers of the organization");
+        assert!(none.items.is_empty());
+
+        let updated = SellerRepository::update(
+            db,
+            SellerBy::Email {
+                organization_id: organization.id,
+                email: "Ana@Shop.com".to_string(),
+            },
+            UpdateSellerDAO {
+                password: None,
+                active: false,
+                version: sellers[1].version,
+            },
+        )
+        .await
+        .expect("Could not update seller by email");
+        assert_eq!(updated.id, sellers[1].id);
+        assert!(!updated.active);
+
+        let deleted = SellerRepository::delete(
+            db,
+            SellerBy::Email {
+                organization_id: organization.id,
+                email: "MIXED@shop.com".to_string(),
+            },
+        )
+        .await
+        .expect("Could not delete seller by email");
+        assert_eq!(deleted.id, sellers[0].id);
+        let deleted = SellerRepository::delete(
+            db,
+            SellerBy::Email {
+                organization_id: organization.id,
+                email: "mixed@shop.com".to_string(),
+            },
+        )
+        .await;
+        assert!(matches!(deleted, Err(DatabaseError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn sqlite_queries() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        queries(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_queries() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            queries(&db.connection).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn sqlite_listing() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        listing(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_listing() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            listing(&db.connection).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn sqlite_lookups() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        lookups(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_lookups() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            lookups(&db.connection).await;
+        }
+    }
+
+    #[test]
+    fn debug_output_leaves_out_emails() {
+        let organization_id = Uuid::new_v4();
+        let key = format!(
+            "{:?}",
+            SellerBy::Email {
+                organization_id,
+                email: "bob@shop.com".to_string(),
+            }
+        );
+        assert!(key.contains(&organization_id.to_string()), "{key}");
+        assert!(!key.contains("bob"), "{key}");
+
+        let cursor = Cursor::new(
+            "email",
+            SortOrder::Ascending,
+            CursorKey::Text("bob@shop.com".to_string()),
+            Uuid::new_v4(),
+        );
+        let filters = format!(
+            "{:?}",
+            SellersWhere {
+                email_prefix: Some("bob".to_string()),
+                order_by: SellersOrderBy::Email,
+                after: Some(cursor),
+                ..Default::default()
+            }
+        );
+        assert!(!filters.contains("bob"), "{filters}");
     }
 }
@@ -0,0 +1,313 @@
+use sqlx::{Acquire, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use super::{NewSellerDAO, SellerBy, SellerDAO, SellerRepository, SellersWhere, UpdateSellerDAO};
+use crate::{
+    entities::{like_prefix, normalize_email, version_rejection},
+    pagination::{push_after_postgres, Page},
+    password::{self, Verification},
+    traits::{CredentialsRepository, DatabaseError, EntityRepository, SoftDeleteRepository},
+};
+
+#[async_trait::async_trait]
+impl EntityRepository<Postgres, SellerDAO, NewSellerDAO, UpdateSellerDAO, SellerBy, SellersWhere>
+    for SellerRepository
+{
+    #[tracing::instrument(
+        name = "repository",
+        level = "debug",
+        skip_all,
+        fields(entity = "seller", operation = "insert"),
+        err(Debug, level = "warn")
+    )]
+    async fn insert<'c, A>(db: A, input: NewSellerDAO) -> Result<SellerDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let password_hash = password::hash(input.password).await?;
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let uuid = Uuid::new_v4();
+        sqlx::query_as::<_, SellerDAO>(
+            "INSERT INTO sellers (id, organization_id, email, password_hash) VALUES ($1, $2, $3, $4) RETURNING id, organization_id, email, active, created_at, updated_at, version",
+        )
+        .bind(uuid)
+        .bind(input.organization_id)
+        .bind(normalize_email(&input.email))
+        .bind(password_hash)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "seller", operation = "get", key = ?key), err(Debug, level = "warn"))]
+    async fn get<'c, A>(db: A, key: SellerBy) -> Result<SellerDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            SellerBy::Id(uuid) => sqlx::query_as::<_, SellerDAO>(
+                "SELECT id, organization_id, email, active, created_at, updated_at, version FROM sellers WHERE id = $1 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(uuid),
+            SellerBy::Email { organization_id, email } => sqlx::query_as::<_, SellerDAO>(
+                "SELECT id, organization_id, email, active, created_at, updated_at, version FROM sellers WHERE organization_id = $1 AND email = $2 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(organization_id)
+            .bind(normalize_email(&email)),
+        };
+        query
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "seller", operation = "try_get", key = ?key), err(Debug, level = "warn"))]
+    async fn try_get<'c, A>(db: A, key: SellerBy) -> Result<Option<SellerDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            SellerBy::Id(uuid) => sqlx::query_as::<_, SellerDAO>(
+                "SELECT id, organization_id, email, active, created_at, updated_at, version FROM sellers WHERE id = $1 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(uuid),
+            SellerBy::Email { organization_id, email } => sqlx::query_as::<_, SellerDAO>(
+                "SELECT id, organization_id, email, active, created_at, updated_at, version FROM sellers WHERE organization_id = $1 AND email = $2 AND deleted_at IS NULL LIMIT 1",
+            )
+            .bind(organization_id)
+            .bind(normalize_email(&email)),
+        };
+        query
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "seller", operation = "get_all", key = ?key), err(Debug, level = "warn"))]
+    async fn get_all<'c, A>(db: A, key: SellersWhere) -> Result<Page<SellerDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let mut query = QueryBuilder::<Postgres>::new(
+            "SELECT id, organization_id, email, active, created_at, updated_at, version FROM sellers WHERE 1 = 1",
+        );
+        query.push(if key.deleted {
+            " AND deleted_at IS NOT NULL"
+        } else {
+            " AND deleted_at IS NULL"
+        });
+        if let Some(organization_id) = key.organization_id {
+            query
+                .push(" AND organization_id = ")
+                .push_bind(organization_id);
+        }
+        if let Some(active) = key.active {
+            query.push(" AND active = ").push_bind(active);
+        }
+        if let Some(prefix) = &key.email_prefix {
+            query
+                .push(" AND lower(email) LIKE ")
+                .push_bind(like_prefix(prefix))
+                .push(" ESCAPE '\\'");
+        }
+        if let Some(created_after) = key.created_after {
+            query.push(" AND created_at >= ").push_bind(created_after);
+        }
+        if let Some(created_before) = key.created_before {
+            query.push(" AND created_at < ").push_bind(created_before);
+        }
+        if let Some(after) = &key.after {
+            push_after_postgres(&mut query, key.order_by.column(), key.order, after)?;
+        }
+        query
+            .push(" ORDER BY ")
+            .push(key.order_by.column())
+            .push(" ")
+            .push(key.order.keyword())
+            .push(", id ")
+            .push(key.order.keyword())
+            .push(" LIMIT ")
+            .push_bind(i64::from(key.limit) + 1);
+
+        let rows = query
+            .build_query_as::<SellerDAO>()
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?;
+        Ok(Page::from_rows(rows, key.limit, |seller| {
+            key.order_by.cursor(key.order, seller)
+        }))
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "seller", operation = "update", key = ?key), err(Debug, level = "warn"))]
+    async fn update<'c, A>(
+        db: A,
+        key: SellerBy,
+        input: UpdateSellerDAO,
+    ) -> Result<SellerDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let password_hash = match input.password {
+            Some(password) => Some(password::hash(password).await?),
+            None => None,
+        };
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match &key {
+            SellerBy::Id(uuid) => sqlx::query_as::<_, SellerDAO>("UPDATE sellers SET password_hash = COALESCE($2, password_hash), active = $3, version = version + 1 WHERE id = $1 AND version = $4 AND deleted_at IS NULL RETURNING id, organization_id, email, active, created_at, updated_at, version")
+                .bind(*uuid),
+            SellerBy::Email { organization_id, email } => sqlx::query_as::<_, SellerDAO>("UPDATE sellers SET password_hash = COALESCE($3, password_hash), active = $4, version = version + 1 WHERE organization_id = $1 AND email = $2 AND version = $5 AND deleted_at IS NULL RETURNING id, organization_id, email, active, created_at, updated_at, version")
+                .bind(*organization_id)
+                .bind(normalize_email(email)),
+        };
+        let updated = query
+            .bind(password_hash)
+            .bind(input.active)
+            .bind(input.version)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?;
+        match updated {
+            Some(seller) => Ok(seller),
+            None => {
+                let missing = format!("seller {key:?}");
+                let current = Self::try_get(&mut *conn, key).await;
+                Err(version_rejection(
+                    current.map(|seller| seller.map(|seller| seller.version)),
+                    missing,
+                ))
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "seller", operation = "delete", key = ?key), err(Debug, level = "warn"))]
+    async fn delete<'c, A>(db: A, key: SellerBy) -> Result<SellerDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            SellerBy::Id(uuid) => sqlx::query_as::<_, SellerDAO>(
+                "UPDATE sellers SET deleted_at = now(), version = version + 1 WHERE id = $1 AND deleted_at IS NULL RETURNING id, organization_id, email, active, created_at, updated_at, version",
+            )
+            .bind(uuid),
+            SellerBy::Email { organization_id, email } => sqlx::query_as::<_, SellerDAO>(
+                "UPDATE sellers SET deleted_at = now(), version = version + 1 WHERE organization_id = $1 AND email = $2 AND deleted_at IS NULL RETURNING id, organization_id, email, active, created_at, updated_at, version",
+            )
+            .bind(organization_id)
+            .bind(normalize_email(&email)),
+        };
+        query
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+    }
+}
+
+#[async_trait::async_trait]
+impl SoftDeleteRepository<Postgres, SellerDAO, SellerBy> for SellerRepository {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "seller", operation = "restore", key = ?key), err(Debug, level = "warn"))]
+    async fn restore<'c, A>(db: A, key: SellerBy) -> Result<SellerDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            SellerBy::Id(uuid) => sqlx::query_as::<_, SellerDAO>(
+                "UPDATE sellers SET deleted_at = NULL, version = version + 1 WHERE id = $1 AND deleted_at IS NOT NULL RETURNING id, organization_id, email, active, created_at, updated_at, version",
+            )
+            .bind(uuid),
+            SellerBy::Email { organization_id, email } => sqlx::query_as::<_, SellerDAO>(
+                "UPDATE sellers SET deleted_at = NULL, version = version + 1 WHERE organization_id = $1 AND email = $2 AND deleted_at IS NOT NULL RETURNING id, organization_id, email, active, created_at, updated_at, version",
+            )
+            .bind(organization_id)
+            .bind(normalize_email(&email)),
+        };
+        query
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "seller", operation = "purge", key = ?key), err(Debug, level = "warn"))]
+    async fn purge<'c, A>(db: A, key: SellerBy) -> Result<SellerDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            SellerBy::Id(uuid) => sqlx::query_as::<_, SellerDAO>(
+                "DELETE FROM sellers WHERE id = $1 RETURNING id, organization_id, email, active, created_at, updated_at, version",
+            )
+            .bind(uuid),
+            SellerBy::Email { organization_id, email } => sqlx::query_as::<_, SellerDAO>(
+                "DELETE FROM sellers WHERE organization_id = $1 AND email = $2 RETURNING id, organization_id, email, active, created_at, updated_at, version",
+            )
+            .bind(organization_id)
+            .bind(normalize_email(&email)),
+        };
+        query
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+    }
+}
+
+#[async_trait::async_trait]
+impl CredentialsRepository<Postgres, SellerDAO> for SellerRepository {
+    #[tracing::instrument(
+        name = "repository",
+        level = "debug",
+        skip_all,
+        fields(entity = "seller", operation = "verify_password"),
+        err(Debug, level = "warn")
+    )]
+    async fn verify_password<'c, A>(
+        db: A,
+        organization_id: Uuid,
+        email: &str,
+        candidate: &str,
+    ) -> Result<Option<SellerDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let stored = sqlx::query_as::<_, (Uuid, String)>(
+            "SELECT id, password_hash FROM sellers WHERE organization_id = $1 AND email = $2 AND deleted_at IS NULL LIMIT 1",
+        )
+        .bind(organization_id)
+        .bind(normalize_email(email))
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)?;
+        let Some((id, stored)) = stored else {
+            password::verify_nothing(candidate.to_string()).await?;
+            return Ok(None);
+        };
+
+        match password::verify(stored.clone(), candidate.to_string()).await? {
+            Verification::Invalid => return Ok(None),
+            Verification::Valid => {}
+            Verification::ValidNeedsRehash => {
+                let rehashed = password::hash(candidate.to_string()).await?;
+                // Same password, new hash: not a change callers need to see in `version`
+                sqlx::query(
+                    "UPDATE sellers SET password_hash = $2 WHERE id = $1 AND password_hash = $3",
+                )
+                .bind(id)
+                .bind(rehashed)
+                .bind(stored)
+                .execute(&mut *conn)
+                .await
+                .map_err(DatabaseError::from)?;
+            }
+        }
+
+        SellerRepository::get(&mut *conn, SellerBy::Id(id))
+            .await
+            .map(Some)
+    }
+}
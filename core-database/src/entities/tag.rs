@@ -0,0 +1,565 @@
+use chrono::{DateTime, Utc};
+use sqlx::{Acquire, Database, QueryBuilder, Sqlite};
+use uuid::Uuid;
+
+use crate::{
+    entities::{
+        like_prefix,
+        product::{ProductBy, ProductRepository},
+        version_rejection, SortOrder,
+    },
+    pagination::{push_after_sqlite, Cursor, CursorKey, Page},
+    traits::{DatabaseError, EntityRepository},
+};
+
+#[cfg(feature = "postgres")]
+pub mod postgres;
+
+#[derive(Debug, Clone)]
+pub enum TagBy {
+    Id(Uuid),
+    /// Tag names are unique within an organization.
+    Name {
+        organization_id: Uuid,
+        name: String,
+    },
+}
+
+/// Filters used to list tags, by name. Filters left as `None` are not applied.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TagsWhere {
+    pub organization_id: Option<Uuid>,
+    /// Case-insensitive prefix of the tag name.
+    pub name_prefix: Option<String>,
+    pub limit: i32,
+    /// Resume after the last row of a previous page.
+    pub after: Option<Cursor>,
+}
+
+impl Default for TagsWhere {
+    fn default() -> Self {
+        Self {
+            organization_id: None,
+            name_prefix: None,
+            limit: 100,
+            after: None,
+        }
+    }
+}
+
+pub(crate) fn tag_cursor(tag: &TagDAO) -> Cursor {
+    Cursor::new(
+        "name",
+        SortOrder::Ascending,
+        CursorKey::Text(tag.name.clone()),
+        tag.id,
+    )
+}
+
+#[derive(sqlx::FromRow, Debug, PartialEq, Eq, Clone, serde::Serialize)]
+pub struct TagDAO {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub version: i64,
+}
+
+#[derive(sqlx::FromRow, Debug, PartialEq, Eq, Clone)]
+pub struct NewTagDAO {
+    pub organization_id: Uuid,
+    pub name: String,
+}
+
+#[derive(sqlx::FromRow, Debug, PartialEq, Eq, Clone)]
+pub struct UpdateTagDAO {
+    pub name: String,
+    /// Version of the row the update is based on; the update fails with
+    /// [`DatabaseError::Conflict`] when the row changed since.
+    pub version: i64,
+}
+
+/// Tags are not soft-deleted: `delete` removes the row and untags the products that had it.
+#[derive(Debug)]
+pub struct TagRepository;
+
+/// Builds the query of `get_all`; kept apart so the query plan tests can check it.
+pub(crate) fn list_query(key: &TagsWhere) -> Result<QueryBuilder<'static, Sqlite>, DatabaseError> {
+    let mut query = QueryBuilder::<Sqlite>::new(
+        "SELECT id, organization_id, name, created_at, updated_at, version FROM tags WHERE 1 = 1",
+    );
+    if let Some(organization_id) = key.organization_id {
+        query
+            .push(" AND organization_id = ")
+            .push_bind(organization_id);
+    }
+    if let Some(prefix) = &key.name_prefix {
+        query
+            .push(" AND lower(name) LIKE ")
+            .push_bind(like_prefix(prefix))
+            .push(" ESCAPE '\\'");
+    }
+    if let Some(after) = &key.after {
+        push_after_sqlite(&mut query, "name", SortOrder::Ascending, after)?;
+    }
+    query
+        .push(" ORDER BY name, id LIMIT ")
+        .push_bind(i64::from(key.limit) + 1);
+    Ok(query)
+}
+
+#[async_trait::async_trait]
+impl EntityRepository<Sqlite, TagDAO, NewTagDAO, UpdateTagDAO, TagBy, TagsWhere> for TagRepository {
+    #[tracing::instrument(
+        name = "repository",
+        level = "debug",
+        skip_all,
+        fields(entity = "tag", operation = "insert"),
+        err(Debug, level = "warn")
+    )]
+    async fn insert<'c, A>(db: A, input: NewTagDAO) -> Result<TagDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let uuid = Uuid::new_v4();
+        sqlx::query_as::<_, TagDAO>(
+            "INSERT INTO tags (id, organization_id, name) VALUES ($1, $2, $3) RETURNING id, organization_id, name, created_at, updated_at, version",
+        )
+        .bind(uuid)
+        .bind(input.organization_id)
+        .bind(input.name)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "tag", operation = "get", key = ?key), err(Debug, level = "warn"))]
+    async fn get<'c, A>(db: A, key: TagBy) -> Result<TagDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            TagBy::Id(uuid) => sqlx::query_as::<_, TagDAO>(
+                "SELECT id, organization_id, name, created_at, updated_at, version FROM tags WHERE id = $1 LIMIT 1",
+            )
+            .bind(uuid),
+            TagBy::Name {
+                organization_id,
+                name,
+            } => sqlx::query_as::<_, TagDAO>(
+                "SELECT id, organization_id, name, created_at, updated_at, version FROM tags WHERE organization_id = $1 AND name = $2 LIMIT 1",
+            )
+            .bind(organization_id)
+            .bind(name),
+        };
+        query
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "tag", operation = "try_get", key = ?key), err(Debug, level = "warn"))]
+    async fn try_get<'c, A>(db: A, key: TagBy) -> Result<Option<TagDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            TagBy::Id(uuid) => sqlx::query_as::<_, TagDAO>(
+                "SELECT id, organization_id, name, created_at, updated_at, version FROM tags WHERE id = $1 LIMIT 1",
+            )
+            .bind(uuid),
+            TagBy::Name {
+                organization_id,
+                name,
+            } => sqlx::query_as::<_, TagDAO>(
+                "SELECT id, organization_id, name, created_at, updated_at, version FROM tags WHERE organization_id = $1 AND name = $2 LIMIT 1",
+            )
+            .bind(organization_id)
+            .bind(name),
+        };
+        query
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "tag", operation = "get_all", key = ?key), err(Debug, level = "warn"))]
+    async fn get_all<'c, A>(db: A, key: TagsWhere) -> Result<Page<TagDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let mut query = list_query(&key)?;
+        let rows = query
+            .build_query_as::<TagDAO>()
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?;
+        Ok(Page::from_rows(rows, key.limit, tag_cursor))
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "tag", operation = "update", key = ?key), err(Debug, level = "warn"))]
+    async fn update<'c, A>(db: A, key: TagBy, input: UpdateTagDAO) -> Result<TagDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match &key {
+            TagBy::Id(uuid) => sqlx::query_as::<_, TagDAO>("UPDATE tags SET name = $2, updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE id = $1 AND version = $3 RETURNING id, organization_id, name, created_at, updated_at, version")
+                .bind(*uuid),
+            TagBy::Name { organization_id, name } => sqlx::query_as::<_, TagDAO>("UPDATE tags SET name = $3, updated_at = strftime('%Y-%m-%dT%H:%M:%f000Z', 'now'), version = version + 1 WHERE organization_id = $1 AND name = $2 AND version = $4 RETURNING id, organization_id, name, created_at, updated_at, version")
+                .bind(*organization_id)
+                .bind(name.clone()),
+        };
+        let updated = query
+            .bind(input.name)
+            .bind(input.version)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?;
+        match updated {
+            Some(tag) => Ok(tag),
+            None => {
+                let missing = format!("tag {key:?}");
+                let current = Self::try_get(&mut *conn, key).await;
+                Err(version_rejection(
+                    current.map(|tag| tag.map(|tag| tag.version)),
+                    missing,
+                ))
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "tag", operation = "delete", key = ?key), err(Debug, level = "warn"))]
+    async fn delete<'c, A>(db: A, key: TagBy) -> Result<TagDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            TagBy::Id(uuid) => sqlx::query_as::<_, TagDAO>(
+                "DELETE FROM tags WHERE id = $1 RETURNING id, organization_id, name, created_at, updated_at, version",
+            )
+            .bind(uuid),
+            TagBy::Name {
+                organization_id,
+                name,
+            } => sqlx::query_as::<_, TagDAO>(
+                "DELETE FROM tags WHERE organization_id = $1 AND name = $2 RETURNING id, organization_id, name, created_at, updated_at, version",
+            )
+            .bind(organization_id)
+            .bind(name),
+        };
+        query
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+    }
+}
+
+/// The many-to-many relation between products and the tags of their organization.
+#[async_trait::async_trait]
+pub trait ProductTags<DB: Database> {
+    /// Tags a live product; adding a tag it already has changes nothing. Fails with `NotFound`
+    /// for an unknown product and with `ForeignKeyViolation` for a tag of another organization.
+    async fn add_tag<'c, A>(db: A, product_id: Uuid, tag_id: Uuid) -> Result<(), DatabaseError>
+    where
+        A: Acquire<'c, Database = DB> + Send;
+
+    /// Untags a product; removing a tag it does not have changes nothing.
+    async fn remove_tag<'c, A>(db: A, product_id: Uuid, tag_id: Uuid) -> Result<(), DatabaseError>
+    where
+        A: Acquire<'c, Database = DB> + Send;
+
+    /// The tags of a product, by name.
+    async fn tags<'c, A>(db: A, product_id: Uuid) -> Result<Vec<TagDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = DB> + Send;
+}
+
+#[async_trait::async_trait]
+impl ProductTags<Sqlite> for ProductRepository {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "product", operation = "add_tag", key = ?product_id, tag = ?tag_id), err(Debug, level = "warn"))]
+    async fn add_tag<'c, A>(db: A, product_id: Uuid, tag_id: Uuid) -> Result<(), DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let inserted = sqlx::query(
+            "INSERT INTO product_tags (product_id, tag_id, organization_id) SELECT id, $2, organization_id FROM products WHERE id = $1 AND deleted_at IS NULL ON CONFLICT DO NOTHING",
+        )
+        .bind(product_id)
+        .bind(tag_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)?;
+        if inserted.rows_affected() == 0 {
+            // Either tagged already or not a live product
+            ProductRepository::get(&mut *conn, ProductBy::Id(product_id)).await?;
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "product", operation = "remove_tag", key = ?product_id, tag = ?tag_id), err(Debug, level = "warn"))]
+    async fn remove_tag<'c, A>(db: A, product_id: Uuid, tag_id: Uuid) -> Result<(), DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        sqlx::query("DELETE FROM product_tags WHERE product_id = $1 AND tag_id = $2")
+            .bind(product_id)
+            .bind(tag_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "product", operation = "tags", key = ?product_id), err(Debug, level = "warn"))]
+    async fn tags<'c, A>(db: A, product_id: Uuid) -> Result<Vec<TagDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        sqlx::query_as::<_, TagDAO>(
+            "SELECT tags.id, tags.organization_id, tags.name, tags.created_at, tags.updated_at, tags.version FROM product_tags JOIN tags ON tags.id = product_tags.tag_id WHERE product_tags.product_id = $1 ORDER BY tags.name, tags.id",
+        )
+        .bind(product_id)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        entities::{
+            organization::{
+                NewOrganizationDAO, OrganizationBy, OrganizationDAO, OrganizationRepository,
+                OrganizationsWhere, UpdateOrganizationDAO,
+            },
+            product::{NewProductDAO, ProductDAO, ProductsWhere, UpdateProductDAO},
+        },
+        money::{Currency, Money},
+        sqlite::DatabaseRepository,
+        traits::SoftDeleteRepository,
+    };
+    use sqlx::Pool;
+
+    async fn tagging<DB: Database>(db: &Pool<DB>)
+    where
+        OrganizationRepository: EntityRepository<
+            DB,
+            OrganizationDAO,
+            NewOrganizationDAO,
+            UpdateOrganizationDAO,
+            OrganizationBy,
+            OrganizationsWhere,
+        >,
+        ProductRepository: EntityRepository<
+                DB,
+                ProductDAO,
+                NewProductDAO,
+                UpdateProductDAO,
+                ProductBy,
+                ProductsWhere,
+            > + SoftDeleteRepository<DB, ProductDAO, ProductBy>
+            + ProductTags<DB>,
+        TagRepository: EntityRepository<DB, TagDAO, NewTagDAO, UpdateTagDAO, TagBy, TagsWhere>,
+    {
+        let mut organizations = vec![];
+        for name in ["shop", "other"] {
+            let organization = OrganizationRepository::insert(
+                db,
+                NewOrganizationDAO {
+                    name: name.to_string(),
+                    reporting_currency: Currency::USD,
+                },
+            )
+            .await
+            .expect("Could not create organization");
+            organizations.push(organization);
+        }
+        let (shop, other) = (&organizations[0], &organizations[1]);
+        let tag = |organization_id, name: &str| NewTagDAO {
+            organization_id,
+            name: name.to_string(),
+        };
+        let sale = TagRepository::insert(db, tag(shop.id, "sale"))
+            .await
+            .expect("Could not create tag");
+        let organic = TagRepository::insert(db, tag(shop.id, "organic"))
+            .await
+            .expect("Could not create tag");
+        let foreign = TagRepository::insert(db, tag(other.id, "sale"))
+            .await
+            .expect("Could not reuse a tag name in another organization");
+        let duplicate = TagRepository::insert(db, tag(shop.id, "sale"))
+            .await
+            .unwrap_err();
+        assert!(matches!(duplicate, DatabaseError::UniqueViolation { .. }));
+
+        let found = TagRepository::get(
+            db,
+            TagBy::Name {
+                organization_id: other.id,
+                name: "sale".to_string(),
+            },
+        )
+        .await
+        .expect("Could not get tag by name");
+        assert_eq!(found, foreign);
+
+        let page = TagRepository::get_all(
+            db,
+            TagsWhere {
+                organization_id: Some(shop.id),
+                limit: 1,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list tags");
+        assert_eq!(page.items, vec![organic.clone()]);
+        let page = TagRepository::get_all(
+            db,
+            TagsWhere {
+                organization_id: Some(shop.id),
+                limit: 1,
+                after: page.next_cursor,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list the next page of tags");
+        assert_eq!(page.items, vec![sale.clone()]);
+        assert_eq!(page.next_cursor, None);
+        let page = TagRepository::get_all(
+            db,
+            TagsWhere {
+                name_prefix: Some("SA".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Could not list tags by prefix");
+        assert_eq!(page.items.len(), 2);
+
+        let product = ProductRepository::insert(
+            db,
+            NewProductDAO {
+                organization_id: shop.id,
+                category_id: None,
+                sku: None,
+                barcode: None,
+                name: "Coffee".to_string(),
+                description: "Beans".to_string(),
+                amount: 10,
+                price: Money::new(5, Currency::USD),
+            },
+        )
+        .await
+        .expect("Could not create product");
+        for tag_id in [sale.id, organic.id, sale.id] {
+            ProductRepository::add_tag(db, product.id, tag_id)
+                .await
+                .expect("Could not tag product");
+        }
+        let tags = ProductRepository::tags(db, product.id)
+            .await
+            .expect("Could not list product tags");
+        assert_eq!(tags, vec![organic.clone(), sale.clone()]);
+
+        let refused = ProductRepository::add_tag(db, product.id, foreign.id)
+            .await
+            .unwrap_err();
+        assert!(matches!(refused, DatabaseError::ForeignKeyViolation { .. }));
+        let missing = ProductRepository::add_tag(db, Uuid::new_v4(), sale.id)
+            .await
+            .unwrap_err();
+        assert!(matches!(missing, DatabaseError::NotFound(_)));
+
+        for _ in 0..2 {
+            ProductRepository::remove_tag(db, product.id, organic.id)
+                .await
+                .expect("Could not untag product");
+        }
+        let renamed = TagRepository::update(
+            db,
+            TagBy::Id(sale.id),
+            UpdateTagDAO {
+                name: "clearance".to_string(),
+                version: sale.version,
+            },
+        )
+        .await
+        .expect("Could not rename tag");
+        assert_eq!(renamed.version, sale.version + 1);
+        let stale = TagRepository::update(
+            db,
+            TagBy::Id(sale.id),
+            UpdateTagDAO {
+                name: "sale".to_string(),
+                version: sale.version,
+            },
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            stale,
+            DatabaseError::Conflict {
+                current_version: renamed.version
+            }
+        );
+        let tags = ProductRepository::tags(db, product.id)
+            .await
+            .expect("Could not list product tags");
+        assert_eq!(tags, vec![renamed.clone()]);
+
+        // Deleting a tag untags its products
+        let deleted = TagRepository::delete(
+            db,
+            TagBy::Name {
+                organization_id: shop.id,
+                name: "clearance".to_string(),
+            },
+        )
+        .await
+        .expect("Could not delete tag");
+        assert_eq!(deleted, renamed);
+        let tags = ProductRepository::tags(db, product.id)
+            .await
+            .expect("Could not list product tags");
+        assert!(tags.is_empty());
+
+        ProductRepository::delete(db, ProductBy::Id(product.id))
+            .await
+            .expect("Could not delete product");
+        let deleted = ProductRepository::add_tag(db, product.id, organic.id)
+            .await
+            .unwrap_err();
+        assert!(matches!(deleted, DatabaseError::NotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn sqlite_tagging() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        tagging(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_tagging() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            tagging(&db.connection).await;
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use sqlx::{Acquire, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use super::{
+    tag_cursor, NewTagDAO, ProductTags, TagBy, TagDAO, TagRepository, TagsWhere, UpdateTagDAO,
+};
+use crate::{
+    entities::{
+        like_prefix,
+        product::{ProductBy, ProductRepository},
+        version_rejection, SortOrder,
+    },
+    pagination::{push_after_postgres, Page},
+    traits::{DatabaseError, EntityRepository},
+};
+
+#[async_trait::async_trait]
+impl EntityRepository<Postgres, TagDAO, NewTagDAO, UpdateTagDAO, TagBy, TagsWhere>
+    for TagRepository
+{
+    #[tracing::instrument(
+        name = "repository",
+        level = "debug",
+        skip_all,
+        fields(entity = "tag", operation = "insert"),
+        err(Debug, level = "warn")
+    )]
+    async fn insert<'c, A>(db: A, input: NewTagDAO) -> Result<TagDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let uuid = Uuid::new_v4();
+        sqlx::query_as::<_, TagDAO>(
+            "INSERT INTO tags (id, organization_id, name) VALUES ($1, $2, $3) RETURNING id, organization_id, name, created_at, updated_at, version",
+        )
+        .bind(uuid)
+        .bind(input.organization_id)
+        .bind(input.name)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "tag", operation = "get", key = ?key), err(Debug, level = "warn"))]
+    async fn get<'c, A>(db: A, key: TagBy) -> Result<TagDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            TagBy::Id(uuid) => sqlx::query_as::<_, TagDAO>(
+                "SELECT id, organization_id, name, created_at, updated_at, version FROM tags WHERE id = $1 LIMIT 1",
+            )
+            .bind(uuid),
+            TagBy::Name {
+                organization_id,
+                name,
+            } => sqlx::query_as::<_, TagDAO>(
+                "SELECT id, organization_id, name, created_at, updated_at, version FROM tags WHERE organization_id = $1 AND name = $2 LIMIT 1",
+            )
+            .bind(organization_id)
+            .bind(name),
+        };
+        query
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "tag", operation = "try_get", key = ?key), err(Debug, level = "warn"))]
+    async fn try_get<'c, A>(db: A, key: TagBy) -> Result<Option<TagDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            TagBy::Id(uuid) => sqlx::query_as::<_, TagDAO>(
+                "SELECT id, organization_id, name, created_at, updated_at, version FROM tags WHERE id = $1 LIMIT 1",
+            )
+            .bind(uuid),
+            TagBy::Name {
+                organization_id,
+                name,
+            } => sqlx::query_as::<_, TagDAO>(
+                "SELECT id, organization_id, name, created_at, updated_at, version FROM tags WHERE organization_id = $1 AND name = $2 LIMIT 1",
+            )
+            .bind(organization_id)
+            .bind(name),
+        };
+        query
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "tag", operation = "get_all", key = ?key), err(Debug, level = "warn"))]
+    async fn get_all<'c, A>(db: A, key: TagsWhere) -> Result<Page<TagDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let mut query = QueryBuilder::<Postgres>::new(
+            "SELECT id, organization_id, name, created_at, updated_at, version FROM tags WHERE 1 = 1",
+        );
+        if let Some(organization_id) = key.organization_id {
+            query
+                .push(" AND organization_id = ")
+                .push_bind(organization_id);
+        }
+        if let Some(prefix) = &key.name_prefix {
+            query
+                .push(" AND lower(name) LIKE ")
+                .push_bind(like_prefix(prefix))
+                .push(" ESCAPE '\\'");
+        }
+        if let Some(after) = &key.after {
+            push_after_postgres(&mut query, "name", SortOrder::Ascending, after)?;
+        }
+        query
+            .push(" ORDER BY name, id LIMIT ")
+            .push_bind(i64::from(key.limit) + 1);
+
+        let rows = query
+            .build_query_as::<TagDAO>()
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?;
+        Ok(Page::from_rows(rows, key.limit, tag_cursor))
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "tag", operation = "update", key = ?key), err(Debug, level = "warn"))]
+    async fn update<'c, A>(db: A, key: TagBy, input: UpdateTagDAO) -> Result<TagDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match &key {
+            TagBy::Id(uuid) => sqlx::query_as::<_, TagDAO>("UPDATE tags SET name = $2, version = version + 1 WHERE id = $1 AND version = $3 RETURNING id, organization_id, name, created_at, updated_at, version")
+                .bind(*uuid),
+            TagBy::Name { organization_id, name } => sqlx::query_as::<_, TagDAO>("UPDATE tags SET name = $3, version = version + 1 WHERE organization_id = $1 AND name = $2 AND version = $4 RETURNING id, organization_id, name, created_at, updated_at, version")
+                .bind(*organization_id)
+                .bind(name.clone()),
+        };
+        let updated = query
+            .bind(input.name)
+            .bind(input.version)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?;
+        match updated {
+            Some(tag) => Ok(tag),
+            None => {
+                let missing = format!("tag {key:?}");
+                let current = Self::try_get(&mut *conn, key).await;
+                Err(version_rejection(
+                    current.map(|tag| tag.map(|tag| tag.version)),
+                    missing,
+                ))
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "tag", operation = "delete", key = ?key), err(Debug, level = "warn"))]
+    async fn delete<'c, A>(db: A, key: TagBy) -> Result<TagDAO, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let query = match key {
+            TagBy::Id(uuid) => sqlx::query_as::<_, TagDAO>(
+                "DELETE FROM tags WHERE id = $1 RETURNING id, organization_id, name, created_at, updated_at, version",
+            )
+            .bind(uuid),
+            TagBy::Name {
+                organization_id,
+                name,
+            } => sqlx::query_as::<_, TagDAO>(
+                "DELETE FROM tags WHERE organization_id = $1 AND name = $2 RETURNING id, organization_id, name, created_at, updated_at, version",
+            )
+            .bind(organization_id)
+            .bind(name),
+        };
+        query
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)
+    }
+}
+
+#[async_trait::async_trait]
+impl ProductTags<Postgres> for ProductRepository {
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "product", operation = "add_tag", key = ?product_id, tag = ?tag_id), err(Debug, level = "warn"))]
+    async fn add_tag<'c, A>(db: A, product_id: Uuid, tag_id: Uuid) -> Result<(), DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        let inserted = sqlx::query(
+            "INSERT INTO product_tags (product_id, tag_id, organization_id) SELECT id, $2, organization_id FROM products WHERE id = $1 AND deleted_at IS NULL ON CONFLICT DO NOTHING",
+        )
+        .bind(product_id)
+        .bind(tag_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)?;
+        if inserted.rows_affected() == 0 {
+            // Either tagged already or not a live product
+            ProductRepository::get(&mut *conn, ProductBy::Id(product_id)).await?;
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "product", operation = "remove_tag", key = ?product_id, tag = ?tag_id), err(Debug, level = "warn"))]
+    async fn remove_tag<'c, A>(db: A, product_id: Uuid, tag_id: Uuid) -> Result<(), DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        sqlx::query("DELETE FROM product_tags WHERE product_id = $1 AND tag_id = $2")
+            .bind(product_id)
+            .bind(tag_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "repository", level = "debug", skip_all, fields(entity = "product", operation = "tags", key = ?product_id), err(Debug, level = "warn"))]
+    async fn tags<'c, A>(db: A, product_id: Uuid) -> Result<Vec<TagDAO>, DatabaseError>
+    where
+        A: Acquire<'c, Database = Postgres> + Send,
+    {
+        let mut conn = db.acquire().await.map_err(DatabaseError::from)?;
+        sqlx::query_as::<_, TagDAO>(
+            "SELECT tags.id, tags.organization_id, tags.name, tags.created_at, tags.updated_at, tags.version FROM product_tags JOIN tags ON tags.id = product_tags.tag_id WHERE product_tags.product_id = $1 ORDER BY tags.name, tags.id",
+        )
+        .bind(product_id)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)
+    }
+}
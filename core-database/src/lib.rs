@@ -1,3 +1,12 @@
+pub mod audit;
 pub mod entities;
+pub mod migration;
+pub mod money;
+pub mod pagination;
+mod password;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+#[cfg(test)]
+mod query_plan;
 pub mod sqlite;
 pub mod traits;
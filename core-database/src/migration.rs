@@ -0,0 +1,189 @@
+use sqlx::migrate::{AppliedMigration, Migrate, MigrateError, Migration, Migrator};
+
+use crate::traits::DatabaseError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationState {
+    /// Recorded in the database with the same checksum as the local script.
+    Applied,
+    /// Known locally but not applied yet.
+    Pending,
+    /// Applied, but the local script changed since then.
+    ChecksumMismatch,
+    /// Applied, but no local script exists for this version anymore.
+    Missing,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub state: MigrationState,
+}
+
+fn failed(error: MigrateError) -> DatabaseError {
+    DatabaseError::MigrationFailed(error.to_string())
+}
+
+fn up_migrations(migrator: &Migrator) -> impl Iterator<Item = &Migration> {
+    migrator
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+}
+
+fn state_of(migration: &Migration, applied: &[AppliedMigration]) -> MigrationState {
+    match applied.iter().find(|m| m.version == migration.version) {
+        Some(m) if m.checksum == migration.checksum => MigrationState::Applied,
+        Some(_) => MigrationState::ChecksumMismatch,
+        None => MigrationState::Pending,
+    }
+}
+
+async fn prepare<C: Migrate + Send>(conn: &mut C) -> Result<Vec<AppliedMigration>, DatabaseError> {
+    conn.ensure_migrations_table().await.map_err(failed)?;
+    if let Some(version) = conn.dirty_version().await.map_err(failed)? {
+        return Err(failed(MigrateError::Dirty(version)));
+    }
+    conn.list_applied_migrations().await.map_err(failed)
+}
+
+/// Lists every known migration along with the ones recorded in the database that are no
+/// longer available locally, ordered by version.
+pub async fn status<C: Migrate + Send>(
+    conn: &mut C,
+    migrator: &Migrator,
+) -> Result<Vec<MigrationStatus>, DatabaseError> {
+    let applied = prepare(conn).await?;
+
+    let mut status: Vec<MigrationStatus> = up_migrations(migrator)
+        .map(|migration| MigrationStatus {
+            version: migration.version,
+            description: migration.description.to_string(),
+            state: state_of(migration, &applied),
+        })
+        .collect();
+
+    for migration in applied.iter() {
+        if !status.iter().any(|s| s.version == migration.version) {
+            status.push(MigrationStatus {
+                version: migration.version,
+                description: String::new(),
+                state: MigrationState::Missing,
+            });
+        }
+    }
+
+    status.sort_by_key(|s| s.version);
+    Ok(status)
+}
+
+/// Applies pending migrations up to and including `target` (every pending migration when
+/// `None`). Refuses to run when an applied migration drifted from its local script.
+/// Returns the versions that were applied.
+pub async fn up<C: Migrate + Send>(
+    conn: &mut C,
+    migrator: &Migrator,
+    target: Option<i64>,
+) -> Result<Vec<i64>, DatabaseError> {
+    // Concurrent runs wait here, then see what the previous one applied.
+    conn.lock().await.map_err(failed)?;
+    let applied = apply_pending(conn, migrator, target).await;
+    let unlocked = conn.unlock().await.map_err(failed);
+    let versions = applied?;
+    unlocked?;
+    Ok(versions)
+}
+
+async fn apply_pending<C: Migrate + Send>(
+    conn: &mut C,
+    migrator: &Migrator,
+    target: Option<i64>,
+) -> Result<Vec<i64>, DatabaseError> {
+    let applied = prepare(conn).await?;
+    verify_applied(migrator, &applied)?;
+
+    let mut versions = vec![];
+    for migration in up_migrations(migrator) {
+        if target.is_some_and(|target| migration.version > target) {
+            break;
+        }
+        if state_of(migration, &applied) != MigrationState::Pending {
+            continue;
+        }
+        conn.apply(migration).await.map_err(failed)?;
+        tracing::info!(
+            version = migration.version,
+            description = %migration.description,
+            "applied migration"
+        );
+        versions.push(migration.version);
+    }
+    Ok(versions)
+}
+
+/// Reverts applied migrations newer than `target` using their down scripts, newest first.
+/// Returns the versions that were reverted.
+pub async fn down<C: Migrate + Send>(
+    conn: &mut C,
+    migrator: &Migrator,
+    target: i64,
+) -> Result<Vec<i64>, DatabaseError> {
+    conn.lock().await.map_err(failed)?;
+    let reverted = revert_applied(conn, migrator, target).await;
+    let unlocked = conn.unlock().await.map_err(failed);
+    let versions = reverted?;
+    unlocked?;
+    Ok(versions)
+}
+
+async fn revert_applied<C: Migrate + Send>(
+    conn: &mut C,
+    migrator: &Migrator,
+    target: i64,
+) -> Result<Vec<i64>, DatabaseError> {
+    let applied = prepare(conn).await?;
+    verify_applied(migrator, &applied)?;
+
+    let mut to_revert = vec![];
+    for migration in applied.iter().rev() {
+        if migration.version <= target {
+            continue;
+        }
+        let down = migrator
+            .iter()
+            .find(|m| m.version == migration.version && m.migration_type.is_down_migration())
+            .ok_or_else(|| {
+                DatabaseError::MigrationFailed(format!(
+                    "migration {} has no down script",
+                    migration.version
+                ))
+            })?;
+        to_revert.push(down);
+    }
+
+    let mut versions = vec![];
+    for migration in to_revert {
+        conn.revert(migration).await.map_err(failed)?;
+        tracing::info!(
+            version = migration.version,
+            description = %migration.description,
+            "reverted migration"
+        );
+        versions.push(migration.version);
+    }
+    Ok(versions)
+}
+
+fn verify_applied(migrator: &Migrator, applied: &[AppliedMigration]) -> Result<(), DatabaseError> {
+    for migration in applied {
+        match up_migrations(migrator).find(|m| m.version == migration.version) {
+            Some(local) if local.checksum != migration.checksum => {
+                return Err(failed(MigrateError::VersionMismatch(migration.version)))
+            }
+            Some(_) => {}
+            None => return Err(failed(MigrateError::VersionMissing(migration.version))),
+        }
+    }
+
+    Ok(())
+}
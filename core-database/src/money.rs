@@ -0,0 +1,346 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Serialize, Serializer};
+use sqlx::{
+    database::{HasArguments, HasValueRef},
+    encode::IsNull,
+    error::BoxDynError,
+    Database, Decode, Encode, Type,
+};
+
+use crate::traits::DatabaseError;
+
+/// ISO 4217 currency code, stored as three uppercase ASCII letters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Currency([u8; 3]);
+
+impl Currency {
+    pub const EUR: Currency = Currency(*b"EUR");
+    pub const USD: Currency = Currency(*b"USD");
+
+    pub fn as_str(&self) -> &str {
+        // Only ASCII letters get past `from_str`
+        std::str::from_utf8(&self.0).unwrap_or_default()
+    }
+
+    /// Number of decimal digits of the minor unit, e.g. 2 for cents.
+    pub fn minor_unit_digits(&self) -> u32 {
+        match self.as_str() {
+            "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF"
+            | "UGX" | "VND" | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+            "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+            _ => 2,
+        }
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Currency {
+    type Err = DatabaseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.as_bytes() {
+            &[a, b, c] if [a, b, c].iter().all(u8::is_ascii_uppercase) => Ok(Currency([a, b, c])),
+            _ => Err(DatabaseError::InvalidMoney(format!(
+                "invalid currency code {s:?}"
+            ))),
+        }
+    }
+}
+
+impl Serialize for Currency {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<DB: Database> Type<DB> for Currency
+where
+    str: Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <str as Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <str as Type<DB>>::compatible(ty)
+    }
+}
+
+impl<'q, DB: Database> Encode<'q, DB> for Currency
+where
+    String: Encode<'q, DB>,
+{
+    fn encode_by_ref(&self, buf: &mut <DB as HasArguments<'q>>::ArgumentBuffer) -> IsNull {
+        self.as_str().to_string().encode_by_ref(buf)
+    }
+}
+
+impl<'r, DB: Database> Decode<'r, DB> for Currency
+where
+    &'r str: Decode<'r, DB>,
+{
+    fn decode(value: <DB as HasValueRef<'r>>::ValueRef) -> Result<Self, BoxDynError> {
+        let code = <&str as Decode<DB>>::decode(value)?;
+        code.parse().map_err(|e| format!("{e:?}").into())
+    }
+}
+
+/// An amount of money as an integer number of minor units (cents for USD) in a currency.
+///
+/// Amounts in different currencies are not comparable, so `Money` is not `Ord`; SQL queries
+/// compare `minor_units` within a single currency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct Money {
+    pub minor_units: i64,
+    pub currency: Currency,
+}
+
+impl Money {
+    pub fn new(minor_units: i64, currency: Currency) -> Self {
+        Self {
+            minor_units,
+            currency,
+        }
+    }
+
+    /// Price of `quantity` units, or `None` on overflow.
+    pub fn checked_mul(self, quantity: u32) -> Option<Self> {
+        self.minor_units
+            .checked_mul(i64::from(quantity))
+            .map(|minor_units| Self::new(minor_units, self.currency))
+    }
+
+    /// Converts into `currency` at `rate`, rounding half away from zero to the minor unit of
+    /// `currency`, or `None` on overflow.
+    pub fn convert(self, currency: Currency, rate: Rate) -> Option<Self> {
+        let digits = |currency: Currency| 10i128.pow(currency.minor_unit_digits());
+        let numerator = i128::from(self.minor_units)
+            .checked_mul(i128::from(rate.0))?
+            .checked_mul(digits(currency))?;
+        let denominator = i128::from(Rate::SCALE) * digits(self.currency);
+        let rounded = (numerator.abs() + denominator / 2) / denominator;
+        let minor_units = i64::try_from(rounded).ok()?;
+        Some(Self::new(
+            if numerator < 0 {
+                -minor_units
+            } else {
+                minor_units
+            },
+            currency,
+        ))
+    }
+}
+
+/// Formats the amount in major units, e.g. `12.50 USD`.
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.currency.minor_unit_digits();
+        let scale = 10u64.pow(digits);
+        let sign = if self.minor_units < 0 { "-" } else { "" };
+        let units = self.minor_units.unsigned_abs();
+        if digits == 0 {
+            write!(f, "{sign}{units} {}", self.currency)
+        } else {
+            write!(
+                f,
+                "{sign}{}.{:0width$} {}",
+                units / scale,
+                units % scale,
+                self.currency,
+                width = digits as usize
+            )
+        }
+    }
+}
+
+/// Parses the [`Display`](fmt::Display) form, e.g. `12.50 USD` or `12 USD`.
+impl FromStr for Money {
+    type Err = DatabaseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || DatabaseError::InvalidMoney(format!("invalid amount {s:?}"));
+        let (amount, currency) = s.trim().split_once(' ').ok_or_else(invalid)?;
+        let currency = Currency::from_str(currency.trim())?;
+        let digits = currency.minor_unit_digits() as usize;
+
+        let (negative, amount) = match amount.strip_prefix('-') {
+            Some(amount) => (true, amount),
+            None => (false, amount),
+        };
+        let (major, minor) = amount.split_once('.').unwrap_or((amount, ""));
+        let is_number = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if major.is_empty() || !is_number(major) || !is_number(minor) || minor.len() > digits {
+            return Err(invalid());
+        }
+        let minor = format!("{minor:0<digits$}");
+        let minor_units = format!("{major}{minor}")
+            .parse::<i64>()
+            .map_err(|_| invalid())?;
+        Ok(Money::new(
+            if negative { -minor_units } else { minor_units },
+            currency,
+        ))
+    }
+}
+
+/// Exchange rate: the amount of the target currency one major unit of the source currency buys,
+/// as a fixed-point number with nine decimal digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct Rate(i64);
+
+impl Rate {
+    const SCALE: i64 = 1_000_000_000;
+
+    /// One to one, the rate between a currency and itself.
+    pub const PAR: Rate = Rate(Self::SCALE);
+
+    /// The rate in billionths, as stored in the database.
+    pub fn billionths(&self) -> i64 {
+        self.0
+    }
+}
+
+impl fmt::Display for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fraction = format!("{:09}", self.0 % Self::SCALE);
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.is_empty() {
+            write!(f, "{}", self.0 / Self::SCALE)
+        } else {
+            write!(f, "{}.{fraction}", self.0 / Self::SCALE)
+        }
+    }
+}
+
+/// Parses a positive decimal number with at most nine fractional digits, e.g. `1.0845`.
+impl FromStr for Rate {
+    type Err = DatabaseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || DatabaseError::InvalidMoney(format!("invalid exchange rate {s:?}"));
+        let (major, minor) = s.split_once('.').unwrap_or((s, ""));
+        let is_number = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if major.is_empty() || !is_number(major) || !is_number(minor) || minor.len() > 9 {
+            return Err(invalid());
+        }
+        let billionths = format!("{major}{minor:0<9}")
+            .parse::<i64>()
+            .map_err(|_| invalid())?;
+        if billionths == 0 {
+            return Err(invalid());
+        }
+        Ok(Rate(billionths))
+    }
+}
+
+impl<DB: Database> Type<DB> for Rate
+where
+    i64: Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <i64 as Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <i64 as Type<DB>>::compatible(ty)
+    }
+}
+
+impl<'q, DB: Database> Encode<'q, DB> for Rate
+where
+    i64: Encode<'q, DB>,
+{
+    fn encode_by_ref(&self, buf: &mut <DB as HasArguments<'q>>::ArgumentBuffer) -> IsNull {
+        self.0.encode_by_ref(buf)
+    }
+}
+
+impl<'r, DB: Database> Decode<'r, DB> for Rate
+where
+    i64: Decode<'r, DB>,
+{
+    fn decode(value: <DB as HasValueRef<'r>>::ValueRef) -> Result<Self, BoxDynError> {
+        Ok(Rate(<i64 as Decode<DB>>::decode(value)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formatting() {
+        let price = Money::new(1250, Currency::USD);
+        assert_eq!(price.to_string(), "12.50 USD");
+        assert_eq!("12.50 USD".parse::<Money>(), Ok(price));
+        assert_eq!("12.5 USD".parse::<Money>(), Ok(price));
+        assert_eq!(
+            "-0.05 EUR".parse::<Money>(),
+            Ok(Money::new(-5, Currency::EUR))
+        );
+        assert_eq!(Money::new(-5, Currency::EUR).to_string(), "-0.05 EUR");
+
+        let yen = "1200 JPY".parse::<Money>().expect("Could not parse yen");
+        assert_eq!(yen.minor_units, 1200);
+        assert_eq!(yen.to_string(), "1200 JPY");
+
+        for invalid in [
+            "12.50",
+            "12.505 USD",
+            "1.2 JPY",
+            "12,50 USD",
+            "12 usd",
+            ". USD",
+        ] {
+            assert!(
+                matches!(
+                    invalid.parse::<Money>(),
+                    Err(DatabaseError::InvalidMoney(_))
+                ),
+                "{invalid} was accepted"
+            );
+        }
+        assert!("92233720368547758.08 USD".parse::<Money>().is_err());
+        assert_eq!(price.checked_mul(3), Some(Money::new(3750, Currency::USD)));
+        assert_eq!(Money::new(i64::MAX, Currency::USD).checked_mul(2), None);
+    }
+
+    #[test]
+    fn conversion() {
+        let rate = "1.0845".parse::<Rate>().expect("Could not parse rate");
+        assert_eq!(rate.billionths(), 1_084_500_000);
+        assert_eq!(rate.to_string(), "1.0845");
+        assert_eq!(Rate::PAR.to_string(), "1");
+        for invalid in ["0", "0.0", "-1.2", "1.0000000001", "1,2", ""] {
+            assert!(invalid.parse::<Rate>().is_err(), "{invalid} was accepted");
+        }
+
+        let euros = Money::new(1000, Currency::EUR);
+        assert_eq!(
+            euros.convert(Currency::USD, rate),
+            Some(Money::new(1085, Currency::USD))
+        );
+        assert_eq!(
+            Money::new(-1000, Currency::EUR).convert(Currency::USD, rate),
+            Some(Money::new(-1085, Currency::USD))
+        );
+        let jpy = Currency::from_str("JPY").unwrap();
+        let yen_rate = "157.25".parse::<Rate>().unwrap();
+        assert_eq!(euros.convert(jpy, yen_rate), Some(Money::new(1573, jpy)));
+        assert_eq!(
+            Money::new(1573, jpy).convert(Currency::EUR, "0.00636".parse().unwrap()),
+            Some(Money::new(1000, Currency::EUR))
+        );
+        assert_eq!(euros.convert(Currency::EUR, Rate::PAR), Some(euros));
+        assert_eq!(
+            Money::new(i64::MAX, Currency::EUR).convert(Currency::USD, rate),
+            None
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use std::{fmt, str::FromStr};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sqlx::{QueryBuilder, Sqlite};
+use uuid::Uuid;
+
+use crate::{entities::SortOrder, sqlite, traits::DatabaseError};
+
+/// One page of a listing. `next_cursor` is `None` once the last page has been returned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<Cursor>,
+}
+
+impl<T> Page<T> {
+    /// Builds a page out of rows fetched with `LIMIT limit + 1`: the extra row, when present,
+    /// only tells that another page exists and is dropped.
+    pub(crate) fn from_rows<F>(mut rows: Vec<T>, limit: i32, cursor: F) -> Self
+    where
+        F: Fn(&T) -> Cursor,
+    {
+        let limit = usize::try_from(limit).unwrap_or_default();
+        if rows.len() <= limit {
+            return Self {
+                items: rows,
+                next_cursor: None,
+            };
+        }
+        rows.truncate(limit);
+        Self {
+            next_cursor: rows.last().map(cursor),
+            items: rows,
+        }
+    }
+}
+
+/// Value of the sort column stored in a cursor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum CursorKey {
+    Integer(i64),
+    Text(String),
+    Timestamp(DateTime<Utc>),
+}
+
+/// Opaque token pointing right after the last row of a page. It remembers the sort column and
+/// direction it was produced for, and is rejected when used with a different ordering.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Cursor(String);
+
+/// The token encodes the sort value of a row, such as an email, so it stays out of logs.
+impl fmt::Debug for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Cursor(..)")
+    }
+}
+
+impl Cursor {
+    pub(crate) fn new(column: &str, order: SortOrder, key: CursorKey, id: Uuid) -> Self {
+        let key = match key {
+            CursorKey::Integer(value) => format!("i:{value}"),
+            CursorKey::Text(value) => format!("t:{value}"),
+            CursorKey::Timestamp(value) => format!("d:{}", value.timestamp_micros()),
+        };
+        let raw = format!("{column}:{}:{id}:{key}", order.keyword());
+        Self(URL_SAFE_NO_PAD.encode(raw))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Decodes the sort value and row id, checking the cursor was built for `column` and
+    /// `order`.
+    pub(crate) fn decode(
+        &self,
+        column: &str,
+        order: SortOrder,
+    ) -> Result<(CursorKey, Uuid), DatabaseError> {
+        let raw = URL_SAFE_NO_PAD
+            .decode(&self.0)
+            .ok()
+            .and_then(|raw| String::from_utf8(raw).ok())
+            .ok_or(DatabaseError::InvalidCursor)?;
+        let mut parts = raw.splitn(5, ':');
+        let (Some(c), Some(o), Some(id), Some(tag), Some(value)) = (
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+        ) else {
+            return Err(DatabaseError::InvalidCursor);
+        };
+        if c != column || o != order.keyword() {
+            return Err(DatabaseError::InvalidCursor);
+        }
+        let id = Uuid::parse_str(id).map_err(|_| DatabaseError::InvalidCursor)?;
+        let key = match tag {
+            "i" => value.parse().map(CursorKey::Integer).ok(),
+            "t" => Some(CursorKey::Text(value.to_string())),
+            "d" => value
+                .parse()
+                .ok()
+                .and_then(NaiveDateTime::from_timestamp_micros)
+                .map(|naive| CursorKey::Timestamp(DateTime::from_utc(naive, Utc))),
+            _ => None,
+        }
+        .ok_or(DatabaseError::InvalidCursor)?;
+        Ok((key, id))
+    }
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = DatabaseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(DatabaseError::InvalidCursor);
+        }
+        Ok(Self(s.to_string()))
+    }
+}
+
+fn comparison(order: SortOrder) -> &'static str {
+    match order {
+        SortOrder::Ascending => " > ",
+        SortOrder::Descending => " < ",
+    }
+}
+
+/// Appends `AND (column, id) > (key, id)` (or `<` when descending) so the query resumes right
+/// after the row the cursor points to. SQLite stores timestamps as fixed-width UTC text such as
+/// `2023-07-10T12:00:00.000000Z`, so timestamp keys are bound through [`sqlite::timestamp`] and
+/// compared as text.
+pub(crate) fn push_after_sqlite(
+    query: &mut QueryBuilder<'_, Sqlite>,
+    column: &str,
+    order: SortOrder,
+    after: &Cursor,
+) -> Result<(), DatabaseError> {
+    let (key, id) = after.decode(column, order)?;
+    query
+        .push(" AND (")
+        .push(column)
+        .push(", id)")
+        .push(comparison(order))
+        .push("(");
+    match key {
+        CursorKey::Integer(value) => query.push_bind(value),
+        CursorKey::Text(value) => query.push_bind(value),
+        CursorKey::Timestamp(value) => query.push_bind(sqlite::timestamp(value)),
+    };
+    query.push(", ").push_bind(id).push(")");
+    Ok(())
+}
+
+#[cfg(feature = "postgres")]
+pub(crate) fn push_after_postgres(
+    query: &mut QueryBuilder<'_, sqlx::Postgres>,
+    column: &str,
+    order: SortOrder,
+    after: &Cursor,
+) -> Result<(), DatabaseError> {
+    let (key, id) = after.decode(column, order)?;
+    query
+        .push(" AND (")
+        .push(column)
+        .push(", id)")
+        .push(comparison(order))
+        .push("(");
+    match key {
+        CursorKey::Integer(value) => query.push_bind(value),
+        CursorKey::Text(value) => query.push_bind(value),
+        CursorKey::Timestamp(value) => query.push_bind(value),
+    };
+    query.push(", ").push_bind(id).push(")");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_round_trip() {
+        let id = Uuid::new_v4();
+        let now = DateTime::from_utc(
+            NaiveDateTime::from_timestamp_micros(Utc::now().timestamp_micros()).unwrap(),
+            Utc,
+        );
+        for key in [
+            CursorKey::Integer(-42),
+            CursorKey::Text("a:b:c".to_string()),
+            CursorKey::Timestamp(now),
+        ] {
+            let cursor = Cursor::new("name", SortOrder::Descending, key.clone(), id);
+            let parsed: Cursor = cursor.to_string().parse().unwrap();
+            assert_eq!(parsed.decode("name", SortOrder::Descending), Ok((key, id)));
+            assert_eq!(
+                parsed.decode("name", SortOrder::Ascending),
+                Err(DatabaseError::InvalidCursor)
+            );
+            assert_eq!(
+                parsed.decode("amount", SortOrder::Descending),
+                Err(DatabaseError::InvalidCursor)
+            );
+        }
+
+        assert_eq!(
+            Cursor("garbage".to_string()).decode("name", SortOrder::Ascending),
+            Err(DatabaseError::InvalidCursor)
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use std::sync::OnceLock;
+
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Algorithm, Argon2, Params, Version,
+};
+
+use crate::traits::DatabaseError;
+
+/// Outcome of checking a candidate password against a stored value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Verification {
+    Invalid,
+    Valid,
+    /// The password matches, but the stored value was hashed with other parameters than the
+    /// current ones and should be replaced.
+    ValidNeedsRehash,
+}
+
+/// The migration that renamed `password` to `password_hash`. Rows written before it hold the
+/// plaintext password until `migrate_up` hashes them.
+pub(crate) const PASSWORD_HASH_MIGRATION: i64 = 20230702120000;
+
+/// Tables with a `password_hash` column.
+pub(crate) const CREDENTIAL_TABLES: [&str; 2] = ["admins", "sellers"];
+
+fn hasher() -> Argon2<'static> {
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, Params::default())
+}
+
+fn failed(error: argon2::password_hash::Error) -> DatabaseError {
+    DatabaseError::Unknown(format!("password hashing failed: {error}"))
+}
+
+fn hash_blocking(password: &str) -> Result<String, DatabaseError> {
+    let salt = SaltString::generate(&mut OsRng);
+    hasher()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(failed)
+}
+
+fn verify_blocking(stored: &str, candidate: &str) -> Verification {
+    // Plaintext left by older versions is hashed when migrating, so it never counts as a match.
+    let Ok(hash) = PasswordHash::new(stored) else {
+        return Verification::Invalid;
+    };
+    if hasher()
+        .verify_password(candidate.as_bytes(), &hash)
+        .is_err()
+    {
+        return Verification::Invalid;
+    }
+
+    let current = Params::default();
+    let outdated = hash.algorithm != Algorithm::Argon2id.ident()
+        || hash.version != Some(Version::V0x13.into())
+        || Params::try_from(&hash).map_or(true, |params| {
+            params.m_cost() != current.m_cost()
+                || params.t_cost() != current.t_cost()
+                || params.p_cost() != current.p_cost()
+        });
+    if outdated {
+        Verification::ValidNeedsRehash
+    } else {
+        Verification::Valid
+    }
+}
+
+/// Hashes `password` with Argon2id and a fresh random salt, off the async executor.
+pub(crate) async fn hash(password: String) -> Result<String, DatabaseError> {
+    tokio::task::spawn_blocking(move || hash_blocking(&password))
+        .await
+        .map_err(|e| DatabaseError::Unknown(e.to_string()))?
+}
+
+/// Checks `candidate` against the `stored` hash, off the async executor.
+pub(crate) async fn verify(
+    stored: String,
+    candidate: String,
+) -> Result<Verification, DatabaseError> {
+    tokio::task::spawn_blocking(move || verify_blocking(&stored, &candidate))
+        .await
+        .map_err(|e| DatabaseError::Unknown(e.to_string()))
+}
+
+/// Spends the same time as a real verification, so unknown emails cannot be told apart from
+/// wrong passwords by timing.
+pub(crate) async fn verify_nothing(candidate: String) -> Result<(), DatabaseError> {
+    static DUMMY: OnceLock<String> = OnceLock::new();
+    tokio::task::spawn_blocking(move || {
+        let dummy = match DUMMY.get() {
+            Some(dummy) => dummy,
+            None => {
+                let dummy = hash_blocking("dummy password")?;
+                DUMMY.get_or_init(|| dummy)
+            }
+        };
+        verify_blocking(dummy, &candidate);
+        Ok(())
+    })
+    .await
+    .map_err(|e| DatabaseError::Unknown(e.to_string()))?
+}
+
+#[cfg(test)]
+pub(crate) fn hash_with_params(password: &str, params: Params) -> String {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verification() {
+        let hash = hash_blocking("secret").unwrap();
+        assert!(hash.starts_with("$argon2id$"));
+        assert_ne!(hash, hash_blocking("secret").unwrap());
+
+        assert_eq!(verify_blocking(&hash, "secret"), Verification::Valid);
+        assert_eq!(verify_blocking(&hash, "Secret"), Verification::Invalid);
+
+        assert_eq!(verify_blocking("secret", "secret"), Verification::Invalid);
+        assert_eq!(verify_blocking("secret", "other"), Verification::Invalid);
+
+        let weak = hash_with_params("secret", Params::new(1024, 1, 1, None).unwrap());
+        assert_eq!(
+            verify_blocking(&weak, "secret"),
+            Verification::ValidNeedsRehash
+        );
+        assert_eq!(verify_blocking(&weak, "other"), Verification::Invalid);
+    }
+}
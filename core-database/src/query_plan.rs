@@ -0,0 +1,321 @@
+//! Query plan regression tests: the queries of the SQLite repositories must find their rows
+//! through an index. A `SCAN` of a table in the output of `EXPLAIN QUERY PLAN` fails the test
+//! with the query and its plan.
+//!
+//! Static statements are read from the repository sources, so new ones are checked as soon as
+//! they are added. Listings are built by the repositories' own query builders, with the filters
+//! that are expected to be selective; listing everything is a scan by nature. Trigger bodies are
+//! read from the migrated schema, since they run on every write to their table.
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection};
+use uuid::Uuid;
+
+use crate::{
+    audit::{self, AuditEventsWhere},
+    entities::{
+        admin::{self, AdminsWhere},
+        category::{self, CategoriesWhere},
+        organization::{self, OrganizationsWhere},
+        product::{self, ProductsOrderBy, ProductsWhere},
+        sales::{self, SalesOrderBy, SalesWhere},
+        seller::{self, SellersOrderBy, SellersWhere},
+        tag::{self, TagsWhere},
+    },
+    money::Currency,
+    sqlite::DatabaseRepository,
+};
+
+const SOURCES: [(&str, &str); 9] = [
+    ("audit.rs", include_str!("audit.rs")),
+    ("entities/admin.rs", include_str!("entities/admin.rs")),
+    ("entities/category.rs", include_str!("entities/category.rs")),
+    (
+        "entities/exchange_rate.rs",
+        include_str!("entities/exchange_rate.rs"),
+    ),
+    (
+        "entities/organization.rs",
+        include_str!("entities/organization.rs"),
+    ),
+    ("entities/product.rs", include_str!("entities/product.rs")),
+    ("entities/sales.rs", include_str!("entities/sales.rs")),
+    ("entities/seller.rs", include_str!("entities/seller.rs")),
+    ("entities/tag.rs", include_str!("entities/tag.rs")),
+];
+
+/// The complete statements held in string literals of `source`, outside its tests. The first
+/// part of a query builder is left out, the builder is checked as a whole instead.
+fn static_queries(source: &str) -> Vec<&str> {
+    let source = source.split("#[cfg(test)]").next().unwrap_or_default();
+    let mut queries = vec![];
+    for keyword in ["\"SELECT ", "\"INSERT ", "\"UPDATE ", "\"DELETE "] {
+        for (start, _) in source.match_indices(keyword) {
+            if source[..start].trim_end().ends_with("::new(") {
+                continue;
+            }
+            let literal = &source[start + 1..];
+            queries.push(&literal[..literal.find('"').expect("Unterminated literal")]);
+        }
+    }
+    queries
+}
+
+/// Fails when the plan of `sql` scans a table. Parameters are left unbound, which SQLite
+/// treats as `NULL`; plans do not depend on them.
+async fn assert_indexed(conn: &mut SqliteConnection, tables: &[String], sql: &str) {
+    let plan = sqlx::query_as::<_, (i64, i64, i64, String)>(&format!("EXPLAIN QUERY PLAN {sql}"))
+        .fetch_all(&mut *conn)
+        .await
+        .unwrap_or_else(|e| panic!("Could not explain {sql}: {e:?}"));
+    let scans = plan
+        .iter()
+        .map(|(_, _, _, detail)| detail)
+        .filter(|detail| {
+            tables.iter().any(|table| {
+                detail
+                    .strip_prefix("SCAN ")
+                    .and_then(|scanned| scanned.strip_prefix(table.as_str()))
+                    .is_some_and(|rest| {
+                        (rest.is_empty() || rest.starts_with(' ')) && !full_text_lookup(rest)
+                    })
+            })
+        })
+        .collect::<Vec<_>>();
+    assert!(scans.is_empty(), "{sql}\nscans {scans:?}");
+}
+
+/// FTS5 reports a full-text query as a scan of the virtual table, with an `M` among the
+/// constraints of its index string, e.g. `VIRTUAL TABLE INDEX 0:M3`, and a lookup by rowid with
+/// an `=`. Both go through an index all the same.
+fn full_text_lookup(scan: &str) -> bool {
+    scan.strip_prefix(" VIRTUAL TABLE INDEX ")
+        .and_then(|index| index.split_once(':'))
+        .is_some_and(|(_, constraints)| constraints.contains(['M', '=']))
+}
+
+/// Replaces the `NEW` and `OLD` columns a trigger statement reads with parameters, so the
+/// statement can be explained on its own.
+fn unbind_row_references(statement: &str) -> String {
+    let mut unbound = String::with_capacity(statement.len());
+    let mut rest = statement;
+    while let Some(start) = ["NEW.", "OLD."]
+        .iter()
+        .filter_map(|prefix| rest.find(prefix))
+        .min()
+    {
+        unbound.push_str(&rest[..start]);
+        unbound.push('?');
+        let column = &rest[start + 4..];
+        let end = column
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(column.len());
+        rest = &column[end..];
+    }
+    unbound.push_str(rest);
+    unbound
+}
+
+/// The statements between `BEGIN` and `END` of every trigger in the schema.
+async fn trigger_statements(conn: &mut SqliteConnection) -> Vec<String> {
+    sqlx::query_as::<_, (String,)>("SELECT sql FROM sqlite_master WHERE type = 'trigger'")
+        .fetch_all(&mut *conn)
+        .await
+        .expect("Could not list triggers")
+        .into_iter()
+        .flat_map(|(sql,)| {
+            let (_, body) = sql.split_once("BEGIN").expect("Trigger without BEGIN");
+            let (body, _) = body.rsplit_once("END").expect("Trigger without END");
+            body.split(';')
+                .map(str::trim)
+                .filter(|statement| !statement.is_empty())
+                .map(unbind_row_references)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+async fn tables(conn: &mut SqliteConnection) -> Vec<String> {
+    sqlx::query_as::<_, (String,)>("SELECT name FROM sqlite_master WHERE type = 'table'")
+        .fetch_all(&mut *conn)
+        .await
+        .expect("Could not list tables")
+        .into_iter()
+        .map(|(name,)| name)
+        .collect()
+}
+
+#[tokio::test]
+async fn static_queries_use_indexes() {
+    let db = DatabaseRepository::new()
+        .await
+        .expect("Could not initialize db");
+    let mut conn = db.connection.acquire().await.expect("Could not connect");
+    let tables = tables(&mut conn).await;
+
+    for (file, source) in SOURCES {
+        let queries = static_queries(source);
+        assert!(!queries.is_empty(), "No queries found in {file}");
+        for sql in queries {
+            assert_indexed(&mut conn, &tables, sql).await;
+        }
+    }
+}
+
+#[tokio::test]
+async fn listings_use_indexes() {
+    let db = DatabaseRepository::new()
+        .await
+        .expect("Could not initialize db");
+    let mut conn = db.connection.acquire().await.expect("Could not connect");
+    let tables = tables(&mut conn).await;
+
+    let id = Some(Uuid::new_v4());
+    let since = Some(chrono::Utc::now());
+    let mut listings: Vec<QueryBuilder<'static, Sqlite>> =
+        vec![organization::list_query(&OrganizationsWhere::Active {
+            active: true,
+            limit: 10,
+            after: None,
+        })
+        .unwrap()];
+    for is_default in [None, Some(true)] {
+        listings.push(
+            admin::list_query(&AdminsWhere {
+                organization_id: id,
+                is_default,
+                email_prefix: Some("a".to_string()),
+                ..Default::default()
+            })
+            .unwrap(),
+        );
+    }
+    for order_by in [SellersOrderBy::CreatedAt, SellersOrderBy::Email] {
+        listings.push(
+            seller::list_query(&SellersWhere {
+                organization_id: id,
+                active: Some(true),
+                order_by,
+                ..Default::default()
+            })
+            .unwrap(),
+        );
+    }
+    for order_by in [
+        ProductsOrderBy::CreatedAt,
+        ProductsOrderBy::Name,
+        ProductsOrderBy::Amount,
+        ProductsOrderBy::Price,
+    ] {
+        listings.push(
+            product::list_query(&ProductsWhere {
+                organization_id: id,
+                name_prefix: Some("i".to_string()),
+                currency: Some(Currency::USD),
+                order_by,
+                ..Default::default()
+            })
+            .unwrap(),
+        );
+    }
+    for key in [
+        ProductsWhere {
+            organization_id: id,
+            category_id: id,
+            ..Default::default()
+        },
+        ProductsWhere {
+            tag_id: id,
+            order_by: ProductsOrderBy::Name,
+            ..Default::default()
+        },
+    ] {
+        listings.push(product::list_query(&key).unwrap());
+    }
+    for key in [
+        CategoriesWhere {
+            organization_id: id,
+            roots: true,
+            ..Default::default()
+        },
+        CategoriesWhere {
+            parent_id: id,
+            ..Default::default()
+        },
+    ] {
+        listings.push(category::list_query(&key).unwrap());
+    }
+    listings.push(
+        tag::list_query(&TagsWhere {
+            organization_id: id,
+            name_prefix: Some("o".to_string()),
+            ..Default::default()
+        })
+        .unwrap(),
+    );
+    for key in [
+        SalesWhere {
+            organization_id: id,
+            created_after: since,
+            ..Default::default()
+        },
+        SalesWhere {
+            product_id: id,
+            order_by: SalesOrderBy::Amount,
+            ..Default::default()
+        },
+        SalesWhere {
+            seller_id: id,
+            created_after: since,
+            created_before: since,
+            ..Default::default()
+        },
+    ] {
+        listings.push(sales::list_query(&key).unwrap());
+        listings.push(sales::revenue_query(&key));
+        listings.push(sales::reporting_revenue_query(
+            Uuid::new_v4(),
+            &key,
+            Currency::USD,
+        ));
+    }
+    for key in [
+        AuditEventsWhere {
+            entity_type: Some("product".to_string()),
+            entity_id: id,
+            ..Default::default()
+        },
+        AuditEventsWhere {
+            organization_id: id,
+            ..Default::default()
+        },
+        AuditEventsWhere {
+            actor_id: id,
+            created_after: since,
+            ..Default::default()
+        },
+        AuditEventsWhere {
+            created_after: since,
+            ..Default::default()
+        },
+    ] {
+        listings.push(audit::events_query(&key).unwrap());
+    }
+
+    for query in listings {
+        assert_indexed(&mut conn, &tables, query.sql()).await;
+    }
+}
+
+#[tokio::test]
+async fn triggers_use_indexes() {
+    let db = DatabaseRepository::new()
+        .await
+        .expect("Could not initialize db");
+    let mut conn = db.connection.acquire().await.expect("Could not connect");
+    let tables = tables(&mut conn).await;
+
+    let statements = trigger_statements(&mut conn).await;
+    assert!(!statements.is_empty(), "No trigger statements found");
+    for sql in statements {
+        assert_indexed(&mut conn, &tables, &sql).await;
+    }
+}
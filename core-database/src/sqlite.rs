@@ -1,42 +1,368 @@
-use crate::traits::DatabaseError;
+use crate::{
+    migration::{self, MigrationStatus},
+    password,
+    traits::DatabaseError,
+};
+use chrono::{DateTime, Utc};
 use sqlx::{
-    migrate::{Migrate, Migrator},
-    Pool, Sqlite, SqlitePool,
+    migrate::Migrator,
+    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions},
+    Pool, Sqlite, SqliteConnection,
 };
+use std::{str::FromStr, time::Duration};
 
 #[derive(Debug)]
 pub struct DatabaseRepository {
     pub connection: Pool<Sqlite>,
 }
 
+/// Options used to open a SQLite database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionOptions {
+    /// Connection URL (`sqlite://data.db`, `sqlite::memory:`) or a plain file path.
+    pub url: String,
+    /// Create the database file if it does not exist yet.
+    pub create_if_missing: bool,
+    /// Use write-ahead logging instead of the rollback journal.
+    pub wal: bool,
+    /// How long a connection waits on a locked database before giving up.
+    pub busy_timeout: Duration,
+    /// Maximum number of connections kept by the pool.
+    pub max_connections: u32,
+    /// Apply pending migrations right after connecting.
+    pub run_migrations: bool,
+}
+
+impl ConnectionOptions {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            create_if_missing: true,
+            wal: true,
+            busy_timeout: Duration::from_secs(5),
+            max_connections: 10,
+            run_migrations: true,
+        }
+    }
+}
+
+impl Default for ConnectionOptions {
+    fn default() -> Self {
+        Self::new("sqlite::memory:")
+    }
+}
+
+/// Formats `value` the way SQLite timestamp columns store it, e.g. `2023-07-10T12:00:00.000000Z`.
+/// Stored timestamps are compared as text, so bound values must use the same fixed-width form.
+/// Bound values keep their microseconds, while the timestamps set by the database itself only
+/// have millisecond resolution and end in `000`.
+pub(crate) fn timestamp(value: DateTime<Utc>) -> String {
+    value.format("%Y-%m-%dT%H:%M:%S%.6fZ").to_string()
+}
+
 static MIGRATOR: Migrator = sqlx::migrate!("./sqlite-migrations");
 
+/// Replaces the plaintext passwords stored before they were hashed. Hashing is not
+/// expressible in SQL, so this runs after the migrations. Hashed values are PHC strings, which
+/// start with `$`.
+async fn hash_plaintext_passwords(conn: &mut SqliteConnection) -> Result<(), DatabaseError> {
+    for table in password::CREDENTIAL_TABLES {
+        let plaintext = sqlx::query_as::<_, (uuid::Uuid, String)>(&format!(
+            "SELECT id, password_hash FROM {table} WHERE password_hash NOT LIKE '$%'"
+        ))
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(DatabaseError::from)?;
+        for (id, stored) in plaintext.iter() {
+            let hashed = password::hash(stored.clone()).await?;
+            // Same password, new hash: not a change callers need to see in `version`
+            sqlx::query(&format!(
+                "UPDATE {table} SET password_hash = $2 WHERE id = $1 AND password_hash = $3"
+            ))
+            .bind(id)
+            .bind(hashed)
+            .bind(stored)
+            .execute(&mut *conn)
+            .await
+            .map_err(DatabaseError::from)?;
+        }
+        if !plaintext.is_empty() {
+            tracing::info!(table, count = plaintext.len(), "hashed plaintext passwords");
+        }
+    }
+    Ok(())
+}
+
 impl DatabaseRepository {
+    /// Opens a throwaway in-memory database, mostly useful for tests.
     pub async fn new() -> Result<Self, DatabaseError> {
-        let connection = SqlitePool::connect("sqlite::memory:")
+        Self::connect(ConnectionOptions::default()).await
+    }
+
+    /// Opens the database described by `options`, applying pending migrations when
+    /// `options.run_migrations` is set.
+    pub async fn connect(options: ConnectionOptions) -> Result<Self, DatabaseError> {
+        let mut connect_options =
+            SqliteConnectOptions::from_str(&options.url).map_err(DatabaseError::from)?;
+        // SQLite only enforces foreign keys on connections that ask for it. sqlx happens to by
+        // default, but the schema relies on it, so it is not left to the default.
+        connect_options = connect_options
+            .create_if_missing(options.create_if_missing)
+            .busy_timeout(options.busy_timeout)
+            .foreign_keys(true);
+        if options.wal {
+            connect_options = connect_options.journal_mode(SqliteJournalMode::Wal);
+        }
+
+        let connection = SqlitePoolOptions::new()
+            .max_connections(options.max_connections)
+            .connect_with(connect_options)
             .await
             .map_err(DatabaseError::from)?;
 
-        DatabaseRepository::migrate(&connection).await?;
         let repository: DatabaseRepository = Self { connection };
+        if options.run_migrations {
+            repository.migrate_up(None).await?;
+        }
         Ok(repository)
     }
 
-    async fn migrate(pool: &Pool<Sqlite>) -> Result<(), DatabaseError> {
-        let mut conn = pool.acquire().await.map_err(DatabaseError::from)?;
-        conn.ensure_migrations_table()
+    /// Lists applied, pending and drifted migrations.
+    pub async fn migration_status(&self) -> Result<Vec<MigrationStatus>, DatabaseError> {
+        let mut conn = self
+            .connection
+            .acquire()
             .await
-            .map_err(|e| DatabaseError::MigrationFailed(e.to_string()))?;
-        for migration in MIGRATOR.iter() {
-            if migration.migration_type.is_down_migration() {
-                // Skipping down migrations
-                continue;
-            }
-            conn.apply(migration)
+            .map_err(DatabaseError::from)?;
+        migration::status(&mut *conn, &MIGRATOR).await
+    }
+
+    /// Applies pending migrations up to `target`, or all of them when `None`. When that applies
+    /// the migration to `password_hash`, it then hashes the plaintext passwords left by
+    /// versions that stored them as is; databases already past it are not scanned again.
+    pub async fn migrate_up(&self, target: Option<i64>) -> Result<Vec<i64>, DatabaseError> {
+        let mut conn = self
+            .connection
+            .acquire()
+            .await
+            .map_err(DatabaseError::from)?;
+        let applied = migration::up(&mut *conn, &MIGRATOR, target).await?;
+        if applied.contains(&password::PASSWORD_HASH_MIGRATION) {
+            hash_plaintext_passwords(&mut conn).await?;
+        }
+        Ok(applied)
+    }
+
+    /// Reverts every migration newer than `target`; `0` reverts all of them.
+    pub async fn migrate_down(&self, target: i64) -> Result<Vec<i64>, DatabaseError> {
+        let mut conn = self
+            .connection
+            .acquire()
+            .await
+            .map_err(DatabaseError::from)?;
+        migration::down(&mut *conn, &MIGRATOR, target).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        entities::{
+            organization::{NewOrganizationDAO, OrganizationBy, OrganizationRepository},
+            seller::{NewSellerDAO, SellerRepository},
+        },
+        migration::MigrationState,
+        money::Currency,
+        traits::{CredentialsRepository, EntityRepository},
+    };
+    use uuid::Uuid;
+
+    fn temp_database() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("core-database-{}.db", Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn file_database_persists_between_connections() {
+        let path = temp_database();
+        let options = ConnectionOptions::new(path.to_string_lossy());
+
+        let db = DatabaseRepository::connect(options.clone())
+            .await
+            .expect("Could not create file database");
+        let organization = OrganizationRepository::insert(
+            &db.connection,
+            NewOrganizationDAO {
+                name: "persisted".to_string(),
+                reporting_currency: Currency::USD,
+            },
+        )
+        .await
+        .expect("Could not create organization");
+        db.connection.close().await;
+
+        let db = DatabaseRepository::connect(options)
+            .await
+            .expect("Could not reopen file database");
+        let maybe_organization = OrganizationRepository::try_get(
+            &db.connection,
+            OrganizationBy::Name("persisted".to_string()),
+        )
+        .await
+        .expect("Could not find organization");
+        assert_eq!(maybe_organization, Some(organization));
+
+        db.connection.close().await;
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn missing_file_is_not_created_unless_requested() {
+        let path = temp_database();
+        let options = ConnectionOptions {
+            create_if_missing: false,
+            ..ConnectionOptions::new(path.to_string_lossy())
+        };
+
+        assert!(DatabaseRepository::connect(options).await.is_err());
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn migrations_can_be_reverted_and_reapplied() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+
+        let status = db.migration_status().await.expect("Could not get status");
+        assert!(!status.is_empty());
+        assert!(status.iter().all(|s| s.state == MigrationState::Applied));
+
+        let reverted = db.migrate_down(0).await.expect("Could not revert");
+        assert_eq!(reverted.len(), status.len());
+        let status = db.migration_status().await.expect("Could not get status");
+        assert!(status.iter().all(|s| s.state == MigrationState::Pending));
+
+        let first = status[0].version;
+        let applied = db.migrate_up(Some(first)).await.expect("Could not migrate");
+        assert_eq!(applied, vec![first]);
+
+        db.migrate_up(None).await.expect("Could not migrate");
+        let status = db.migration_status().await.expect("Could not get status");
+        assert!(status.iter().all(|s| s.state == MigrationState::Applied));
+
+        let applied = db.migrate_up(None).await.expect("Could not migrate");
+        assert!(applied.is_empty());
+    }
+
+    #[tokio::test]
+    async fn checksum_drift_is_detected() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+
+        let version = db.migration_status().await.expect("Could not get status")[0].version;
+        sqlx::query("UPDATE _sqlx_migrations SET checksum = x'00' WHERE version = $1")
+            .bind(version)
+            .execute(&db.connection)
+            .await
+            .expect("Could not tamper checksum");
+
+        let status = db.migration_status().await.expect("Could not get status");
+        assert_eq!(status[0].state, MigrationState::ChecksumMismatch);
+        assert!(matches!(
+            db.migrate_up(None).await,
+            Err(DatabaseError::MigrationFailed(_))
+        ));
+        assert!(matches!(
+            db.migrate_down(0).await,
+            Err(DatabaseError::MigrationFailed(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn foreign_keys_are_enforced_on_every_connection() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+
+        let mut connections = vec![];
+        for _ in 0..3 {
+            let mut conn = db.connection.acquire().await.expect("Could not connect");
+            let (enabled,) = sqlx::query_as::<_, (bool,)>("PRAGMA foreign_keys")
+                .fetch_one(&mut *conn)
                 .await
-                .map_err(|e| DatabaseError::MigrationFailed(e.to_string()))?;
+                .expect("Could not read pragma");
+            assert!(enabled);
+            connections.push(conn);
         }
+    }
 
-        Ok(())
+    #[tokio::test]
+    async fn plaintext_passwords_are_hashed_on_upgrade() {
+        let db = DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        let organization = OrganizationRepository::insert(
+            &db.connection,
+            NewOrganizationDAO {
+                name: "legacy".to_string(),
+                reporting_currency: Currency::USD,
+            },
+        )
+        .await
+        .expect("Could not create organization");
+        let seller = SellerRepository::insert(
+            &db.connection,
+            NewSellerDAO {
+                organization_id: organization.id,
+                email: "seller@legacy.com".to_string(),
+                password: "secret".to_string(),
+            },
+        )
+        .await
+        .expect("Could not create seller");
+        // As stored before passwords were hashed
+        sqlx::query("UPDATE sellers SET password_hash = 'legacy' WHERE id = $1")
+            .bind(seller.id)
+            .execute(&db.connection)
+            .await
+            .expect("Could not store plaintext");
+
+        db.migrate_down(password::PASSWORD_HASH_MIGRATION - 1)
+            .await
+            .expect("Could not revert migrations");
+        let applied = db.migrate_up(None).await.expect("Could not migrate");
+        assert!(applied.contains(&password::PASSWORD_HASH_MIGRATION));
+
+        let stored_hash = || async {
+            sqlx::query_as::<_, (String,)>("SELECT password_hash FROM sellers WHERE id = $1")
+                .bind(seller.id)
+                .fetch_one(&db.connection)
+                .await
+                .expect("Could not read hash")
+                .0
+        };
+        let stored = stored_hash().await;
+        assert!(stored.starts_with("$argon2id$"), "{stored}");
+        let verified = SellerRepository::verify_password(
+            &db.connection,
+            organization.id,
+            "seller@legacy.com",
+            "legacy",
+        )
+        .await
+        .expect("Could not verify password");
+        assert_eq!(verified.map(|seller| seller.id), Some(seller.id));
+
+        // Databases already past the migration are not scanned again on start
+        sqlx::query("UPDATE sellers SET password_hash = 'legacy' WHERE id = $1")
+            .bind(seller.id)
+            .execute(&db.connection)
+            .await
+            .expect("Could not store plaintext");
+        let applied = db.migrate_up(None).await.expect("Could not migrate");
+        assert!(applied.is_empty());
+        assert_eq!(stored_hash().await, "legacy");
     }
 }
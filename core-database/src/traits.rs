@@ -1,4 +1,7 @@
-use sqlx::{Database, Error as SqlxError, Pool};
+use sqlx::{error::DatabaseError as SqlxDatabaseError, Acquire, Database, Error as SqlxError};
+use uuid::Uuid;
+
+use crate::{money::Currency, pagination::Page};
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum DatabaseError {
@@ -13,16 +16,118 @@ pub enum DatabaseError {
     Unknown(String),
     DatabaseInconsistence(String),
     MigrationFailed(String),
+    InsufficientStock {
+        available: u32,
+        requested: u32,
+    },
+    OrganizationMismatch,
+    /// A pagination cursor could not be decoded or belongs to a different ordering.
+    InvalidCursor,
+    /// A unique or primary key constraint was violated. `constraint` holds the constraint name
+    /// on PostgreSQL and the offending `table.column` list on SQLite.
+    UniqueViolation {
+        constraint: String,
+    },
+    /// A referenced row does not exist, or is still referenced by other rows.
+    ForeignKeyViolation {
+        constraint: Option<String>,
+    },
+    /// A CHECK constraint rejected the row.
+    CheckViolation {
+        constraint: Option<String>,
+    },
+    /// The default admin of an organization cannot be deleted, and `is_default` only changes
+    /// through a default admin transfer.
+    DefaultAdminProtected,
+    /// The organization cannot be deleted with [`DeletePolicy::Refuse`] while rows still
+    /// reference it.
+    ///
+    /// [`DeletePolicy::Refuse`]: crate::entities::organization::DeletePolicy::Refuse
+    OrganizationInUse {
+        admins: u64,
+        sellers: u64,
+        products: u64,
+        sales: u64,
+        categories: u64,
+        tags: u64,
+    },
+    /// A category cannot be moved under itself or one of its subcategories.
+    CategoryCycle,
+    /// The row was modified since the caller read it; `current_version` is the version now
+    /// stored, to re-read the row and retry.
+    Conflict {
+        current_version: i64,
+    },
+    /// A money amount or currency code could not be parsed, or an amount overflowed.
+    InvalidMoney(String),
+    /// A stock or sale quantity is out of the range stored by the database: above `i32::MAX`
+    /// on the way in, or negative on the way out. Also reported for a sale of zero units.
+    InvalidQuantity(i64),
+    /// No exchange rate from `from` to `to` was in effect when a sale was made.
+    MissingExchangeRate {
+        from: Currency,
+        to: Currency,
+    },
+}
+
+// SQLite extended result codes, see https://www.sqlite.org/rescode.html
+const SQLITE_CONSTRAINT_CHECK: &str = "275";
+const SQLITE_CONSTRAINT_FOREIGNKEY: &str = "787";
+const SQLITE_CONSTRAINT_PRIMARYKEY: &str = "1555";
+const SQLITE_CONSTRAINT_UNIQUE: &str = "2067";
+
+// PostgreSQL SQLSTATE codes of the integrity constraint violation class
+const POSTGRES_FOREIGN_KEY_VIOLATION: &str = "23503";
+const POSTGRES_UNIQUE_VIOLATION: &str = "23505";
+const POSTGRES_CHECK_VIOLATION: &str = "23514";
+
+impl From<Box<dyn SqlxDatabaseError>> for DatabaseError {
+    fn from(error: Box<dyn SqlxDatabaseError>) -> Self {
+        let code = error.code().unwrap_or_default();
+        let message = error.message();
+        // SQLite does not name the constraint, the message ends with the offending
+        // columns (`UNIQUE constraint failed: admins.email`) or the CHECK name instead.
+        let constraint = error.constraint().map(str::to_string).or_else(|| {
+            message
+                .split_once("constraint failed: ")
+                .map(|(_, constraint)| constraint.to_string())
+        });
+        // Violations raised while stepping a statement with a RETURNING clause are reported
+        // by SQLite with the generic SQLITE_ERROR code, so the message is checked as well.
+        match code.as_ref() {
+            SQLITE_CONSTRAINT_UNIQUE | SQLITE_CONSTRAINT_PRIMARYKEY | POSTGRES_UNIQUE_VIOLATION => {
+                Self::UniqueViolation {
+                    constraint: constraint.unwrap_or_default(),
+                }
+            }
+            _ if message.starts_with("UNIQUE constraint failed") => Self::UniqueViolation {
+                constraint: constraint.unwrap_or_default(),
+            },
+            SQLITE_CONSTRAINT_FOREIGNKEY | POSTGRES_FOREIGN_KEY_VIOLATION => {
+                Self::ForeignKeyViolation { constraint }
+            }
+            _ if message.starts_with("FOREIGN KEY constraint failed") => {
+                Self::ForeignKeyViolation { constraint }
+            }
+            SQLITE_CONSTRAINT_CHECK | POSTGRES_CHECK_VIOLATION => {
+                Self::CheckViolation { constraint }
+            }
+            _ if message.starts_with("CHECK constraint failed") => {
+                Self::CheckViolation { constraint }
+            }
+            _ => Self::QueryFailed(error.to_string()),
+        }
+    }
 }
 
 impl From<SqlxError> for DatabaseError {
     fn from(value: SqlxError) -> Self {
-        println!("error: {:#?}", value);
         match value {
+            SqlxError::RowNotFound => Self::NotFound(value.to_string()),
             SqlxError::ColumnNotFound(column_name) => Self::ColumnNotFound(column_name),
             SqlxError::Io(_) | SqlxError::Tls(_) => Self::CommunicationError,
             SqlxError::PoolTimedOut => Self::ConnectionNotAvailable,
-            SqlxError::Database(e) => Self::QueryFailed(e.to_string()),
+            SqlxError::Database(e) => Self::from(e),
             SqlxError::Protocol(_) => Self::ProtocolNotSupported,
             SqlxError::TypeNotFound { type_name } => {
                 Self::DatabaseInconsistence(format!("TypeNotFound {type_name}"))
@@ -32,6 +137,9 @@ impl From<SqlxError> for DatabaseError {
     }
 }
 
+/// Every method accepts anything that can hand out a connection: a `&Pool` for standalone
+/// calls, or `&mut Transaction` to compose several repository calls into one unit of work that
+/// is committed or rolled back as a whole.
 #[async_trait::async_trait]
 pub trait EntityRepository<
     DB: Database,
@@ -42,14 +150,121 @@ pub trait EntityRepository<
     QueryMany: Send + Sync,
 >
 {
-    async fn get(db: &Pool<DB>, key: QueryOne) -> Result<Entity, DatabaseError>;
-    async fn try_get(db: &Pool<DB>, key: QueryOne) -> Result<Option<Entity>, DatabaseError>;
-    async fn get_all(db: &Pool<DB>, key: QueryMany) -> Result<Vec<Entity>, DatabaseError>;
-    async fn insert(db: &Pool<DB>, input: CreateInput) -> Result<Entity, DatabaseError>;
-    async fn update(
-        db: &Pool<DB>,
+    async fn get<'c, A>(db: A, key: QueryOne) -> Result<Entity, DatabaseError>
+    where
+        A: Acquire<'c, Database = DB> + Send;
+    async fn try_get<'c, A>(db: A, key: QueryOne) -> Result<Option<Entity>, DatabaseError>
+    where
+        A: Acquire<'c, Database = DB> + Send;
+    async fn get_all<'c, A>(db: A, key: QueryMany) -> Result<Page<Entity>, DatabaseError>
+    where
+        A: Acquire<'c, Database = DB> + Send;
+    async fn insert<'c, A>(db: A, input: CreateInput) -> Result<Entity, DatabaseError>
+    where
+        A: Acquire<'c, Database = DB> + Send;
+    async fn update<'c, A>(
+        db: A,
         key: QueryOne,
         input: UpdateInput,
-    ) -> Result<Entity, DatabaseError>;
-    async fn delete(db: &Pool<DB>, key: QueryOne) -> Result<Entity, DatabaseError>;
+    ) -> Result<Entity, DatabaseError>
+    where
+        A: Acquire<'c, Database = DB> + Send;
+    async fn delete<'c, A>(db: A, key: QueryOne) -> Result<Entity, DatabaseError>
+    where
+        A: Acquire<'c, Database = DB> + Send;
+}
+
+/// Password checks for accounts that sign in with an email, such as admins and sellers.
+#[async_trait::async_trait]
+pub trait CredentialsRepository<DB: Database, Entity: Send> {
+    /// Returns the account registered with `email` in the organization when `candidate` is its
+    /// password, and `None` for an unknown email or a wrong password alike. Hashes made with
+    /// outdated parameters are replaced by a fresh hash on success; the rehash keeps the
+    /// password, so it writes no audit event.
+    async fn verify_password<'c, A>(
+        db: A,
+        organization_id: Uuid,
+        email: &str,
+        candidate: &str,
+    ) -> Result<Option<Entity>, DatabaseError>
+    where
+        A: Acquire<'c, Database = DB> + Send;
+}
+
+/// `EntityRepository::delete` only marks rows as deleted by setting `deleted_at`; deleted rows
+/// are left out of `get`, `try_get`, `update` and the default listings.
+#[async_trait::async_trait]
+pub trait SoftDeleteRepository<DB: Database, Entity: Send, QueryOne: Send> {
+    /// Brings back a soft-deleted row. Fails with `NotFound` when no deleted row matches.
+    async fn restore<'c, A>(db: A, key: QueryOne) -> Result<Entity, DatabaseError>
+    where
+        A: Acquire<'c, Database = DB> + Send;
+
+    /// Removes a row for good, whether it was soft-deleted or not.
+    async fn purge<'c, A>(db: A, key: QueryOne) -> Result<Entity, DatabaseError>
+    where
+        A: Acquire<'c, Database = DB> + Send;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::{Executor, Pool};
+
+    async fn constraint_errors<DB: Database>(db: &Pool<DB>)
+    where
+        for<'c> &'c Pool<DB>: Executor<'c, Database = DB>,
+        DB::QueryResult: std::fmt::Debug,
+    {
+        db.execute("CREATE TABLE checked (id INTEGER PRIMARY KEY, code TEXT UNIQUE, amount INTEGER CONSTRAINT positive_amount CHECK (amount >= 0))")
+            .await
+            .expect("Could not create table");
+        db.execute("INSERT INTO checked VALUES (1, 'a', 1)")
+            .await
+            .expect("Could not insert row");
+
+        let duplicate = db
+            .execute("INSERT INTO checked VALUES (2, 'a', 1)")
+            .await
+            .map_err(DatabaseError::from)
+            .unwrap_err();
+        assert!(
+            matches!(duplicate, DatabaseError::UniqueViolation { constraint } if constraint.contains("code"))
+        );
+
+        let duplicate = db
+            .execute("INSERT INTO checked VALUES (1, 'b', 1)")
+            .await
+            .map_err(DatabaseError::from)
+            .unwrap_err();
+        assert!(matches!(duplicate, DatabaseError::UniqueViolation { .. }));
+
+        let negative = db
+            .execute("INSERT INTO checked VALUES (3, 'c', -1)")
+            .await
+            .map_err(DatabaseError::from)
+            .unwrap_err();
+        assert_eq!(
+            negative,
+            DatabaseError::CheckViolation {
+                constraint: Some("positive_amount".to_string())
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn sqlite_constraint_errors() {
+        let db = crate::sqlite::DatabaseRepository::new()
+            .await
+            .expect("Could not initialize db");
+        constraint_errors(&db.connection).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn postgres_constraint_errors() {
+        if let Some(db) = crate::postgres::DatabaseRepository::new_test_database().await {
+            constraint_errors(&db.connection).await;
+        }
+    }
 }
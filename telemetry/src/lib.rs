@@ -0,0 +1,73 @@
+use std::{fmt, str::FromStr};
+
+use tracing_subscriber::{fmt::format::FmtSpan, EnvFilter};
+
+/// Shape of the emitted log lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Human readable, multi-line output.
+    #[default]
+    Pretty,
+    /// One JSON object per line, for log collectors.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "pretty" => Ok(Self::Pretty),
+            "json" => Ok(Self::Json),
+            other => Err(format!(
+                "unknown log format '{other}', expected pretty or json"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogFormat::Pretty => f.write_str("pretty"),
+            LogFormat::Json => f.write_str("json"),
+        }
+    }
+}
+
+/// Options used to install the global tracing subscriber.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TelemetryOptions {
+    /// Level or `EnvFilter` directives, e.g. `info` or `warn,core_database=debug`.
+    pub level: String,
+    pub format: LogFormat,
+}
+
+impl TelemetryOptions {
+    /// Reads `LOG_LEVEL` and `LOG_FORMAT`, falling back to `default_level` and pretty output.
+    pub fn from_env(default_level: &str) -> Result<Self, String> {
+        let level = std::env::var("LOG_LEVEL").unwrap_or_else(|_| default_level.to_string());
+        let format = match std::env::var("LOG_FORMAT") {
+            Ok(format) => format.parse()?,
+            Err(_) => LogFormat::default(),
+        };
+        Ok(Self { level, format })
+    }
+}
+
+/// Installs the global subscriber. Logs go to stderr so they never mix with command output,
+/// and every span is reported when it closes along with how long it took.
+pub fn init(options: &TelemetryOptions) -> Result<(), String> {
+    let filter = EnvFilter::try_new(&options.level)
+        .map_err(|e| format!("invalid log level '{}': {e}", options.level))?;
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(std::io::stderr);
+
+    match options.format {
+        LogFormat::Pretty => builder.pretty().try_init(),
+        LogFormat::Json => builder.json().try_init(),
+    }
+    .map_err(|e| format!("could not install the tracing subscriber: {e}"))
+}